@@ -0,0 +1,6 @@
+// The keyed js-framework-benchmark app: rust-bench-keyed renders the
+// suite's buttons and table, so this only mounts it.
+import { createHost } from '../src/runtime.js';
+import { renderer as bench } from './dist/rust-bench-keyed/rust-bench-keyed.js';
+
+createHost(document.getElementById('main')).mount(bench);
@@ -0,0 +1,158 @@
+// The rust-bench component with rows keyed by id: swapping or removing
+// rows moves or drops their DOM instead of rewriting the rows after them.
+
+wit_bindgen::generate!({
+    path: "../../../wit",
+    world: "leaf-component",
+});
+
+use exports::wasm_components::dom::renderer::{Guest, Opcode, PropValue};
+use rust_bench_rows::{Row, RowGen};
+use wasm_components::dom::host::event_query;
+use wasm_html_macro::{html, Handlers};
+use wasm_html_state::State;
+
+struct Bench;
+
+struct Model {
+    // A plain `Vec`: a keyed `for` can't take `@dirty`, the runtime matches
+    // rows by key instead
+    data: Vec<Row>,
+    selected: u32, // 0 = none
+    rows: RowGen,
+    templates: u64, // html! @compact state
+}
+
+static MODEL: State<Model> = State::new(Model {
+    data: Vec::new(),
+    selected: 0,
+    rows: RowGen::new(),
+    templates: 0,
+});
+
+#[derive(Handlers)]
+enum Msg {
+    Run,
+    Runlots,
+    Add,
+    Update,
+    Clear,
+    Swaprows,
+    OnClick,
+}
+
+fn render(model: &mut Model) -> Vec<Opcode> {
+    let selected = model.selected;
+    let data = &model.data;
+    html! { @compact(&mut model.templates)
+        <div class="container">
+            <div class="jumbotron">
+                <div class="row">
+                    <div class="col-md-6">
+                        <h1>"WASM Component Protocol (keyed)"</h1>
+                    </div>
+                    <div class="col-md-6">
+                        <div class="row">
+                            <div class="col-sm-6 smallpad">
+                                <button type="button" class="btn btn-primary btn-block" id="run" on:click={Msg::Run}>"Create 1,000 rows"</button>
+                            </div>
+                            <div class="col-sm-6 smallpad">
+                                <button type="button" class="btn btn-primary btn-block" id="runlots" on:click={Msg::Runlots}>"Create 10,000 rows"</button>
+                            </div>
+                            <div class="col-sm-6 smallpad">
+                                <button type="button" class="btn btn-primary btn-block" id="add" on:click={Msg::Add}>"Append 1,000 rows"</button>
+                            </div>
+                            <div class="col-sm-6 smallpad">
+                                <button type="button" class="btn btn-primary btn-block" id="update" on:click={Msg::Update}>"Update every 10th row"</button>
+                            </div>
+                            <div class="col-sm-6 smallpad">
+                                <button type="button" class="btn btn-primary btn-block" id="clear" on:click={Msg::Clear}>"Clear"</button>
+                            </div>
+                            <div class="col-sm-6 smallpad">
+                                <button type="button" class="btn btn-primary btn-block" id="swaprows" on:click={Msg::Swaprows}>"Swap Rows"</button>
+                            </div>
+                        </div>
+                    </div>
+                </div>
+            </div>
+            <table class="table table-hover table-striped test-data">
+                <tbody id="tbody" on:click={Msg::OnClick}>
+                    for row in data.iter() {
+                        <tr key={row.id} class:danger={row.id == selected}>
+                            <td class="col-md-1">{row.id}</td>
+                            <td class="col-md-4">
+                                <a class="lbl" data-id={row.id}>{row.label}</a>
+                            </td>
+                            <td class="col-md-1">
+                                <a class="remove" data-id={row.id}>
+                                    <span class="glyphicon glyphicon-remove" aria-hidden="true"></span>
+                                </a>
+                            </td>
+                            <td class="col-md-6"></td>
+                        </tr>
+                    }
+                </tbody>
+            </table>
+            <span class="preloadicon glyphicon glyphicon-remove" aria-hidden="true"></span>
+        </div>
+    }
+}
+
+impl Guest for Bench {
+    fn render(_props: Vec<(String, PropValue)>) -> Vec<Opcode> {
+        render(&mut MODEL.lock())
+    }
+
+    fn handle_event(handler: String) {
+        let mut model = MODEL.lock();
+        match Msg::from_handler(&handler) {
+            Some(Msg::Run) => {
+                model.data = model.rows.create_rows(1000);
+                model.selected = 0;
+            }
+            Some(Msg::Runlots) => {
+                model.data = model.rows.create_rows(10000);
+                model.selected = 0;
+            }
+            Some(Msg::Add) => {
+                let mut rows = model.rows.create_rows(1000);
+                model.data.append(&mut rows);
+            }
+            Some(Msg::Update) => {
+                for row in model.data.iter_mut().step_by(10) {
+                    row.label.push_str(" !!!");
+                }
+            }
+            Some(Msg::Clear) => {
+                model.data.clear();
+                model.selected = 0;
+            }
+            Some(Msg::Swaprows) if model.data.len() > 998 => {
+                model.data.swap(1, 998);
+            }
+            Some(Msg::OnClick) => {
+                let class = event_query("target.className");
+                if class.contains("lbl") {
+                    let id_str = event_query("target.dataset.id");
+                    if let Ok(id) = id_str.parse::<u32>() {
+                        model.selected = if model.selected == id { 0 } else { id };
+                    }
+                } else if class.contains("glyphicon") {
+                    // Clicked the <span> inside <a class="remove"> — read parent's data-id
+                    let id_str = event_query("target.parentElement.dataset.id");
+                    if let Ok(id) = id_str.parse::<u32>() {
+                        model.data.retain(|r| r.id != id);
+                    }
+                } else if class == "remove" {
+                    let id_str = event_query("target.dataset.id");
+                    if let Ok(id) = id_str.parse::<u32>() {
+                        model.data.retain(|r| r.id != id);
+                    }
+                }
+            }
+            Some(Msg::Swaprows) | None => {}
+        }
+    }
+}
+
+export!(Bench);
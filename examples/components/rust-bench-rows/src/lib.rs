@@ -0,0 +1,92 @@
+//! The benchmark's rows and their generator, shared by `rust-bench` and
+//! `rust-bench-keyed` so both benches build the same rows.
+
+pub struct Row {
+    pub id: u32,
+    pub label: String,
+}
+
+const ADJECTIVES: &[&str] = &[
+    "pretty",
+    "large",
+    "big",
+    "small",
+    "tall",
+    "short",
+    "long",
+    "handsome",
+    "plain",
+    "quaint",
+    "clean",
+    "elegant",
+    "easy",
+    "angry",
+    "crazy",
+    "helpful",
+    "mushy",
+    "odd",
+    "unsightly",
+    "adorable",
+    "important",
+    "inexpensive",
+    "cheap",
+    "expensive",
+    "fancy",
+];
+const COLOURS: &[&str] = &[
+    "red", "yellow", "blue", "green", "pink", "brown", "purple", "brown", "white", "black",
+    "orange",
+];
+const NOUNS: &[&str] = &[
+    "table", "chair", "house", "bbq", "desk", "car", "pony", "cookie", "sandwich", "burger",
+    "pizza", "mouse", "keyboard",
+];
+
+/// Hands out row ids and pseudo-random labels.
+pub struct RowGen {
+    next_id: u32,
+    seed: u32,
+}
+
+impl Default for RowGen {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RowGen {
+    pub const fn new() -> Self {
+        RowGen {
+            next_id: 1,
+            seed: 0,
+        }
+    }
+
+    fn random(&mut self, max: usize) -> usize {
+        self.seed = self.seed.wrapping_mul(1103515245).wrapping_add(12345) & 0x7fffffff;
+        (self.seed % max as u32) as usize
+    }
+
+    fn build_label(&mut self) -> String {
+        let mut s = String::with_capacity(32);
+        s.push_str(ADJECTIVES[self.random(ADJECTIVES.len())]);
+        s.push(' ');
+        s.push_str(COLOURS[self.random(COLOURS.len())]);
+        s.push(' ');
+        s.push_str(NOUNS[self.random(NOUNS.len())]);
+        s
+    }
+
+    pub fn create_rows(&mut self, count: usize) -> Vec<Row> {
+        let mut rows = Vec::with_capacity(count);
+        for _ in 0..count {
+            let id = self.next_id;
+            self.next_id += 1;
+            rows.push(Row {
+                id,
+                label: self.build_label(),
+            });
+        }
+        rows
+    }
+}
@@ -4,280 +4,174 @@ wit_bindgen::generate!({
 });
 
 use exports::wasm_components::dom::renderer::{Guest, Opcode, PropValue};
+use rust_bench_rows::{Row, RowGen};
 use wasm_components::dom::host::event_query;
+use wasm_html_macro::{html, Handlers};
+use wasm_html_state::{State, TrackedVec};
 
 struct Bench;
 
-struct Row {
-    id: u32,
-    label: String,
+struct Model {
+    data: TrackedVec<Row>,
+    selected: u32, // 0 = none
+    rows: RowGen,
+    templates: u64, // html! @compact state
 }
 
-static mut DATA: Vec<Row> = Vec::new();
-static mut NEXT_ID: u32 = 1;
-static mut SELECTED: u32 = 0; // 0 = none
-static mut TEMPLATE_SENT: bool = false;
-
-const ADJECTIVES: &[&str] = &[
-    "pretty", "large", "big", "small", "tall", "short", "long",
-    "handsome", "plain", "quaint", "clean", "elegant", "easy", "angry", "crazy",
-    "helpful", "mushy", "odd", "unsightly", "adorable", "important",
-    "inexpensive", "cheap", "expensive", "fancy",
-];
-const COLOURS: &[&str] = &[
-    "red", "yellow", "blue", "green", "pink", "brown", "purple",
-    "brown", "white", "black", "orange",
-];
-const NOUNS: &[&str] = &[
-    "table", "chair", "house", "bbq", "desk", "car", "pony",
-    "cookie", "sandwich", "burger", "pizza", "mouse", "keyboard",
-];
-
-static mut SEED: u32 = 0;
+static MODEL: State<Model> = State::new(Model {
+    data: TrackedVec::new(),
+    selected: 0,
+    rows: RowGen::new(),
+    templates: 0,
+});
 
-fn random(max: usize) -> usize {
-    unsafe {
-        SEED = SEED.wrapping_mul(1103515245).wrapping_add(12345) & 0x7fffffff;
-        (SEED % max as u32) as usize
+impl Model {
+    fn position(&self, id: u32) -> Option<usize> {
+        self.data.iter().position(|r| r.id == id)
     }
-}
-
-fn build_label() -> String {
-    let mut s = String::with_capacity(32);
-    s.push_str(ADJECTIVES[random(ADJECTIVES.len())]);
-    s.push(' ');
-    s.push_str(COLOURS[random(COLOURS.len())]);
-    s.push(' ');
-    s.push_str(NOUNS[random(NOUNS.len())]);
-    s
-}
 
-fn create_rows(count: usize) -> Vec<Row> {
-    let mut rows = Vec::with_capacity(count);
-    for _ in 0..count {
-        unsafe {
-            let id = NEXT_ID;
-            NEXT_ID += 1;
-            rows.push(Row { id, label: build_label() });
+    fn select(&mut self, id: u32) {
+        let id = if self.selected == id { 0 } else { id };
+        let old = std::mem::replace(&mut self.selected, id);
+        // Both the old and the new row change class
+        for idx in self.position(old).into_iter().chain(self.position(id)) {
+            self.data.mark(idx);
         }
     }
-    rows
-}
 
-fn format_u32(n: u32) -> String {
-    if n == 0 { return "0".into(); }
-    let mut v = n;
-    let mut buf = [0u8; 10];
-    let mut pos = buf.len();
-    while v > 0 {
-        pos -= 1;
-        buf[pos] = b'0' + (v % 10) as u8;
-        v /= 10;
+    fn remove_row(&mut self, id: u32) {
+        if let Some(idx) = self.position(id) {
+            self.data.remove(idx);
+        }
     }
-    unsafe { core::str::from_utf8_unchecked(&buf[pos..]).to_string() }
-}
-
-fn push_button(ops: &mut Vec<Opcode>, id: &str, label: &str, handler: &str) {
-    ops.push(Opcode::Open("div".into()));
-    ops.push(Opcode::Attr(("class".into(), "col-sm-6 smallpad".into())));
-    ops.push(Opcode::Open("button".into()));
-    ops.push(Opcode::Attr(("type".into(), "button".into())));
-    ops.push(Opcode::Attr(("class".into(), "btn btn-primary btn-block".into())));
-    ops.push(Opcode::Attr(("id".into(), id.into())));
-    ops.push(Opcode::Event(("click".into(), handler.into())));
-    ops.push(Opcode::Text(label.into()));
-    ops.push(Opcode::Close); // button
-    ops.push(Opcode::Close); // div
 }
 
-fn full_render(data: &[Row], selected: u32) -> Vec<Opcode> {
-    let row_count = data.len();
-    // Static opcodes (~70) + per-row opcodes (~20 each)
-    let mut ops = Vec::with_capacity(70 + row_count * 20);
-
-    // --- Container ---
-    ops.push(Opcode::Open("div".into()));
-    ops.push(Opcode::Attr(("class".into(), "container".into())));
-
-    // --- Jumbotron ---
-    ops.push(Opcode::Open("div".into()));
-    ops.push(Opcode::Attr(("class".into(), "jumbotron".into())));
-    ops.push(Opcode::Open("div".into()));
-    ops.push(Opcode::Attr(("class".into(), "row".into())));
-
-    // Title
-    ops.push(Opcode::Open("div".into()));
-    ops.push(Opcode::Attr(("class".into(), "col-md-6".into())));
-    ops.push(Opcode::Open("h1".into()));
-    ops.push(Opcode::Text("WASM Component Protocol".into()));
-    ops.push(Opcode::Close); // h1
-    ops.push(Opcode::Close); // col-md-6
-
-    // Buttons
-    ops.push(Opcode::Open("div".into()));
-    ops.push(Opcode::Attr(("class".into(), "col-md-6".into())));
-    ops.push(Opcode::Open("div".into()));
-    ops.push(Opcode::Attr(("class".into(), "row".into())));
-    push_button(&mut ops, "run", "Create 1,000 rows", "run");
-    push_button(&mut ops, "runlots", "Create 10,000 rows", "runlots");
-    push_button(&mut ops, "add", "Append 1,000 rows", "add");
-    push_button(&mut ops, "update", "Update every 10th row", "update");
-    push_button(&mut ops, "clear", "Clear", "clear");
-    push_button(&mut ops, "swaprows", "Swap Rows", "swaprows");
-    ops.push(Opcode::Close); // row
-    ops.push(Opcode::Close); // col-md-6
-
-    ops.push(Opcode::Close); // row
-    ops.push(Opcode::Close); // jumbotron
-
-    // --- Table ---
-    ops.push(Opcode::Open("table".into()));
-    ops.push(Opcode::Attr(("class".into(), "table table-hover table-striped test-data".into())));
-    ops.push(Opcode::Open("tbody".into()));
-    ops.push(Opcode::Attr(("id".into(), "tbody".into())));
-    ops.push(Opcode::Event(("click".into(), "on_click".into())));
-
-    for row in data {
-        let id_str = format_u32(row.id);
-        let row_class = if row.id == selected { "danger" } else { "" };
-
-        ops.push(Opcode::Begin("row".into()));
-        ops.push(Opcode::Open("tr".into()));
-        ops.push(Opcode::AttrSlot(("class".into(), row_class.into())));
-
-        // Column 1: ID
-        ops.push(Opcode::Open("td".into()));
-        ops.push(Opcode::Attr(("class".into(), "col-md-1".into())));
-        ops.push(Opcode::Slot(id_str.clone()));
-        ops.push(Opcode::Close);
-
-        // Column 2: Label
-        ops.push(Opcode::Open("td".into()));
-        ops.push(Opcode::Attr(("class".into(), "col-md-4".into())));
-        ops.push(Opcode::Open("a".into()));
-        ops.push(Opcode::Attr(("class".into(), "lbl".into())));
-        ops.push(Opcode::AttrSlot(("data-id".into(), id_str.clone())));
-        ops.push(Opcode::Slot(row.label.clone()));
-        ops.push(Opcode::Close); // a
-        ops.push(Opcode::Close); // td
-
-        // Column 3: Delete
-        ops.push(Opcode::Open("td".into()));
-        ops.push(Opcode::Attr(("class".into(), "col-md-1".into())));
-        ops.push(Opcode::Open("a".into()));
-        ops.push(Opcode::Attr(("class".into(), "remove".into())));
-        ops.push(Opcode::AttrSlot(("data-id".into(), id_str)));
-        ops.push(Opcode::Open("span".into()));
-        ops.push(Opcode::Attr(("class".into(), "glyphicon glyphicon-remove".into())));
-        ops.push(Opcode::Attr(("aria-hidden".into(), "true".into())));
-        ops.push(Opcode::Close); // span
-        ops.push(Opcode::Close); // a
-        ops.push(Opcode::Close); // td
-
-        // Column 4: spacer
-        ops.push(Opcode::Open("td".into()));
-        ops.push(Opcode::Attr(("class".into(), "col-md-6".into())));
-        ops.push(Opcode::Close);
-
-        ops.push(Opcode::Close); // tr
-        ops.push(Opcode::End);
-    }
-
-    ops.push(Opcode::Close); // tbody
-    ops.push(Opcode::Close); // table
-
-    // Preload icon
-    ops.push(Opcode::Open("span".into()));
-    ops.push(Opcode::Attr(("class".into(), "preloadicon glyphicon glyphicon-remove".into())));
-    ops.push(Opcode::Attr(("aria-hidden".into(), "true".into())));
-    ops.push(Opcode::Close);
-
-    ops.push(Opcode::Close); // container
-    ops
+#[derive(Handlers)]
+enum Msg {
+    Run,
+    Runlots,
+    Add,
+    Update,
+    Clear,
+    Swaprows,
+    OnClick,
 }
 
-fn compact_render(data: &[Row], selected: u32) -> Vec<Opcode> {
-    let mut ops = Vec::with_capacity(data.len() * 7);
-    for row in data {
-        let id_str = format_u32(row.id);
-        let row_class = if row.id == selected { "danger" } else { "" };
-        ops.push(Opcode::Begin("row".into()));
-        ops.push(Opcode::AttrSlot(("class".into(), row_class.into())));
-        ops.push(Opcode::Slot(id_str.clone()));
-        ops.push(Opcode::AttrSlot(("data-id".into(), id_str.clone())));
-        ops.push(Opcode::Slot(row.label.clone()));
-        ops.push(Opcode::AttrSlot(("data-id".into(), id_str)));
-        ops.push(Opcode::End);
+fn render(model: &mut Model) -> Vec<Opcode> {
+    let dirty = model.data.take_dirty();
+    let selected = model.selected;
+    let data = &model.data;
+    html! { @compact(&mut model.templates)
+        <div class="container">
+            <div class="jumbotron">
+                <div class="row">
+                    <div class="col-md-6">
+                        <h1>"WASM Component Protocol"</h1>
+                    </div>
+                    <div class="col-md-6">
+                        <div class="row">
+                            <div class="col-sm-6 smallpad">
+                                <button type="button" class="btn btn-primary btn-block" id="run" on:click={Msg::Run}>"Create 1,000 rows"</button>
+                            </div>
+                            <div class="col-sm-6 smallpad">
+                                <button type="button" class="btn btn-primary btn-block" id="runlots" on:click={Msg::Runlots}>"Create 10,000 rows"</button>
+                            </div>
+                            <div class="col-sm-6 smallpad">
+                                <button type="button" class="btn btn-primary btn-block" id="add" on:click={Msg::Add}>"Append 1,000 rows"</button>
+                            </div>
+                            <div class="col-sm-6 smallpad">
+                                <button type="button" class="btn btn-primary btn-block" id="update" on:click={Msg::Update}>"Update every 10th row"</button>
+                            </div>
+                            <div class="col-sm-6 smallpad">
+                                <button type="button" class="btn btn-primary btn-block" id="clear" on:click={Msg::Clear}>"Clear"</button>
+                            </div>
+                            <div class="col-sm-6 smallpad">
+                                <button type="button" class="btn btn-primary btn-block" id="swaprows" on:click={Msg::Swaprows}>"Swap Rows"</button>
+                            </div>
+                        </div>
+                    </div>
+                </div>
+            </div>
+            <table class="table table-hover table-striped test-data">
+                <tbody id="tbody" on:click={Msg::OnClick}>
+                    for row in data.iter() @dirty(dirty) {
+                        <tr class:danger={row.id == selected}>
+                            <td class="col-md-1">{row.id}</td>
+                            <td class="col-md-4">
+                                <a class="lbl" data-id={row.id}>{row.label}</a>
+                            </td>
+                            <td class="col-md-1">
+                                <a class="remove" data-id={row.id}>
+                                    <span class="glyphicon glyphicon-remove" aria-hidden="true"></span>
+                                </a>
+                            </td>
+                            <td class="col-md-6"></td>
+                        </tr>
+                    }
+                </tbody>
+            </table>
+            <span class="preloadicon glyphicon glyphicon-remove" aria-hidden="true"></span>
+        </div>
     }
-    ops
 }
 
 impl Guest for Bench {
     fn render(_props: Vec<(String, PropValue)>) -> Vec<Opcode> {
-        let data = unsafe { &DATA };
-        let selected = unsafe { SELECTED };
-        unsafe {
-            if !TEMPLATE_SENT {
-                if !data.is_empty() { TEMPLATE_SENT = true; }
-                return full_render(data, selected);
-            }
-        }
-        compact_render(data, selected)
+        render(&mut MODEL.lock())
     }
 
     fn handle_event(handler: String) {
-        match handler.as_str() {
-            "run" => unsafe {
-                DATA = create_rows(1000);
-                SELECTED = 0;
-            },
-            "runlots" => unsafe {
-                DATA = create_rows(10000);
-                SELECTED = 0;
-            },
-            "add" => {
-                let mut new_rows = create_rows(1000);
-                unsafe { DATA.append(&mut new_rows); }
-            },
-            "update" => unsafe {
+        let mut model = MODEL.lock();
+        match Msg::from_handler(&handler) {
+            Some(Msg::Run) => {
+                let rows = model.rows.create_rows(1000);
+                model.data.replace(rows);
+                model.selected = 0;
+            }
+            Some(Msg::Runlots) => {
+                let rows = model.rows.create_rows(10000);
+                model.data.replace(rows);
+                model.selected = 0;
+            }
+            Some(Msg::Add) => {
+                let rows = model.rows.create_rows(1000);
+                model.data.extend(rows);
+            }
+            Some(Msg::Update) => {
                 let mut i = 0;
-                while i < DATA.len() {
-                    DATA[i].label.push_str(" !!!");
+                while let Some(row) = model.data.get_mut(i) {
+                    row.label.push_str(" !!!");
                     i += 10;
                 }
-            },
-            "clear" => unsafe {
-                DATA.clear();
-                SELECTED = 0;
-            },
-            "swaprows" => unsafe {
-                if DATA.len() > 998 {
-                    DATA.swap(1, 998);
-                }
-            },
-            "on_click" => {
+            }
+            Some(Msg::Clear) => {
+                model.data.clear();
+                model.selected = 0;
+            }
+            Some(Msg::Swaprows) if model.data.len() > 998 => {
+                model.data.swap(1, 998);
+            }
+            Some(Msg::OnClick) => {
                 let class = event_query("target.className");
                 if class.contains("lbl") {
                     let id_str = event_query("target.dataset.id");
                     if let Ok(id) = id_str.parse::<u32>() {
-                        unsafe {
-                            SELECTED = if SELECTED == id { 0 } else { id };
-                        }
+                        model.select(id);
                     }
                 } else if class.contains("glyphicon") {
                     // Clicked the <span> inside <a class="remove"> — read parent's data-id
                     let id_str = event_query("target.parentElement.dataset.id");
                     if let Ok(id) = id_str.parse::<u32>() {
-                        unsafe { DATA.retain(|r| r.id != id); }
+                        model.remove_row(id);
                     }
                 } else if class == "remove" {
                     let id_str = event_query("target.dataset.id");
                     if let Ok(id) = id_str.parse::<u32>() {
-                        unsafe { DATA.retain(|r| r.id != id); }
+                        model.remove_row(id);
                     }
                 }
-            },
-            _ => {}
+            }
+            Some(Msg::Swaprows) | None => {}
         }
     }
 }
@@ -1,41 +1,64 @@
-wit_bindgen::generate!({
-    path: "../../../wit",
-    world: "pure-component",
-});
+use rust_counter_props::{CountChange, CounterProps};
+use wasm_component_macro::component;
+use wasm_html_macro::{html, Handlers};
 
-use exports::wasm_components::dom::renderer::{Guest, Opcode, PropValue};
-use wasm_html_macro::html;
+const RUST_INITIAL: i32 = 3;
 
-struct CounterApp;
+#[derive(Handlers)]
+enum Msg {
+    ToggleDark,
+    RustCountChanged,
+}
 
-static mut DARK: bool = false;
+struct CounterApp {
+    dark: bool,
+    /// Kept in sync by the Rust counter's `change` events.
+    rust_count: i32,
+}
 
-impl Guest for CounterApp {
-    fn render(_props: Vec<(String, PropValue)>) -> Vec<Opcode> {
-        let theme = if unsafe { DARK } {
-            "counter-app dark"
-        } else {
-            "counter-app"
-        };
+impl Default for CounterApp {
+    fn default() -> Self {
+        CounterApp {
+            dark: false,
+            rust_count: RUST_INITIAL,
+        }
+    }
+}
+
+#[component(types = "rust_counter_props")]
+impl CounterApp {
+    fn view(&self) -> Vec<Opcode> {
+        let dark = self.dark;
+        let rust_count = self.rust_count;
 
         html! {
-            <div class={theme}>
+            <div class="counter-app" class:dark={dark}>
                 <h2>"Counter Apps"</h2>
-                <button on:click="toggle_dark">"Toggle Dark Mode"</button>
+                <button on:click={Msg::ToggleDark}>"Toggle Dark Mode"</button>
                 <h3>"Zig Counter"</h3>
-                <ZigChild initial={5} />
-                <h3>"Rust Counter"</h3>
-                <RustChild />
+                <ZigChild initial={5} dark={dark} />
+                <h3>"Rust Counter: " {rust_count}</h3>
+                <RustChild: CounterProps
+                    initial={RUST_INITIAL}
+                    dark={dark}
+                    on:change={Msg::RustCountChanged}
+                />
+                <h3>"More Rust Counters"</h3>
+                <p>"Each one has its own count."</p>
+                <RustChild: CounterProps dark={dark} />
+                <RustChild: CounterProps initial={10} dark={dark} />
             </div>
         }
     }
 
-    fn handle_event(handler: String) {
-        match handler.as_str() {
-            "toggle_dark" => unsafe { DARK = !DARK },
-            _ => {}
+    fn update(&mut self, msg: Msg) {
+        match msg {
+            Msg::ToggleDark => self.dark = !self.dark,
+            Msg::RustCountChanged => {
+                if let Ok(change) = CountChange::from_props(&event_detail()) {
+                    self.rust_count = change.count;
+                }
+            }
         }
     }
 }
-
-export!(CounterApp);
@@ -0,0 +1,38 @@
+//! The Rust counter's props and the detail of its `change` event, shared by
+//! the counter and the parents that mount it: a parent type-checks
+//! `<RustChild: CounterProps ... />` and reads the event with `CountChange`.
+//!
+//! Only the WIT `types` interface is generated here; components using these
+//! props pass `#[component(types = "rust_counter_props")]` so their bindings
+//! share its `PropValue`.
+
+wit_bindgen::generate!({
+    path: "../../../wit",
+    inline: "
+        package example:counter-props;
+
+        world props {
+            import wasm-components:dom/types@0.1.0;
+        }
+    ",
+    generate_all,
+});
+
+pub use wasm_components::dom::types::PropValue;
+use wasm_html_macro::Props;
+
+#[derive(Props)]
+pub struct CounterProps {
+    /// The count the counter starts at.
+    #[prop(default)]
+    pub initial: i32,
+    /// Set by the parent's dark mode toggle.
+    #[prop(default)]
+    pub dark: bool,
+}
+
+/// Emitted as `change` whenever the count changes.
+#[derive(Props)]
+pub struct CountChange {
+    pub count: i32,
+}
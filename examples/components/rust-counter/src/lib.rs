@@ -1,62 +1,43 @@
-wit_bindgen::generate!({
-    path: "../../../wit",
-    world: "pure-component",
-});
-
-use exports::wasm_components::dom::renderer::{Guest, Opcode, PropValue};
-use wasm_html_macro::html;
+use rust_counter_props::{CountChange, CounterProps};
+use wasm_component_macro::component;
+use wasm_html_macro::{html, Handlers};
+
+#[derive(Handlers)]
+enum Msg {
+    Decrement,
+    Increment,
+}
 
-struct Counter;
+struct Counter {
+    count: i32,
+}
 
-static mut COUNT: i32 = 0;
+#[component(types = "rust_counter_props")]
+impl Counter {
+    fn new(props: &CounterProps) -> Self {
+        Counter {
+            count: props.initial,
+        }
+    }
 
-impl Guest for Counter {
-    fn render(_props: Vec<(String, PropValue)>) -> Vec<Opcode> {
-        let count = unsafe { COUNT };
-        let count_str = format_i32(count);
+    fn view(&self, props: &CounterProps) -> Vec<Opcode> {
+        let count = self.count;
 
         html! {
-            <div class="counter">
-                <button on:click="on_decrement">"-"</button>
-                <span class="count">{ count_str }</span>
-                <button on:click="on_increment">"+"</button>
+            <div class="counter" class:dark={props.dark}>
+                <button disabled={count == 0} on:click={Msg::Decrement}>"-"</button>
+                <span class="count">{ count }</span>
+                <button on:click={Msg::Increment}>"+"</button>
             </div>
         }
     }
 
-    fn handle_event(handler: String) {
-        match handler.as_str() {
-            "on_decrement" => unsafe {
-                if COUNT > 0 {
-                    COUNT -= 1;
-                }
-            },
-            "on_increment" => unsafe {
-                COUNT += 1;
-            },
-            _ => {}
+    fn update(&mut self, msg: Msg) {
+        match msg {
+            Msg::Decrement if self.count > 0 => self.count -= 1,
+            Msg::Increment => self.count += 1,
+            Msg::Decrement => return,
         }
+        emit("change", &CountChange { count: self.count }.to_props());
     }
 }
-
-fn format_i32(n: i32) -> String {
-    if n == 0 {
-        return "0".into();
-    }
-    let mut v = if n < 0 { (-n) as u32 } else { n as u32 };
-    let mut buf = [0u8; 11];
-    let mut pos = buf.len();
-    while v > 0 {
-        pos -= 1;
-        buf[pos] = b'0' + (v % 10) as u8;
-        v /= 10;
-    }
-    if n < 0 {
-        pos -= 1;
-        buf[pos] = b'-';
-    }
-    // Safety: we only put ASCII bytes
-    unsafe { core::str::from_utf8_unchecked(&buf[pos..]).to_string() }
-}
-
-export!(Counter);
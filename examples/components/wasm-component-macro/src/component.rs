@@ -0,0 +1,308 @@
+use std::path::{Path, PathBuf};
+
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::meta::ParseNestedMeta;
+use syn::{Error, FnArg, ImplItem, ImplItemFn, ItemImpl, LitStr, Result, Type};
+
+const WORLDS: &[&str] = &["leaf-instances", "pure-instances"];
+
+/// The `host` imports; a component that calls any of them is a leaf.
+const HOST_IMPORTS: &[&str] = &["event_query", "emit", "event_detail"];
+
+/// `#[component(world = "...", wit = "...", types = "...")]`. All are
+/// optional: the world is inferred from the impl, and the WIT directory is
+/// found by walking up from the crate to the nearest `wit/dom.wit`. `types`
+/// names a crate or module whose own `wit_bindgen::generate!` produced the
+/// `types` interface, so props defined there share this component's
+/// `PropValue`.
+#[derive(Default)]
+pub struct Options {
+    world: Option<LitStr>,
+    wit: Option<LitStr>,
+    types: Option<syn::Path>,
+}
+
+impl Options {
+    pub fn parse(&mut self, meta: ParseNestedMeta) -> Result<()> {
+        if meta.path.is_ident("world") {
+            let world: LitStr = meta.value()?.parse()?;
+            if !WORLDS.contains(&world.value().as_str()) {
+                return Err(Error::new_spanned(
+                    &world,
+                    format!("unknown world, expected one of {}", WORLDS.join(", ")),
+                ));
+            }
+            self.world = Some(world);
+            Ok(())
+        } else if meta.path.is_ident("wit") {
+            self.wit = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("types") {
+            let types: LitStr = meta.value()?.parse()?;
+            self.types = Some(types.parse()?);
+            Ok(())
+        } else {
+            Err(meta.error("expected `world = \"...\"`, `wit = \"...\"` or `types = \"...\"`"))
+        }
+    }
+}
+
+/// `#[component]` on the `impl` of a component's state type. Each instance
+/// starts from `fn new(props: &P) -> Self` if the impl defines one, else
+/// from `Default`. The impl must define `fn view(&self) -> Vec<Opcode>` (or
+/// `&mut self`, e.g. for `@compact`), which may take the props as a second
+/// argument, `props: &P` or `props: P`, and may define
+/// `fn update(&mut self, msg: Msg)`, where `Msg` derives `Handlers`. `P`
+/// derives `Props`; props that don't convert render the error as text. If
+/// this type derives `Bind`, bound values are stored in its `#[bind]`
+/// fields.
+///
+/// Generates the `wit_bindgen::generate!` call, imports `Opcode` and
+/// `PropValue` (and the `host` functions for leaf components), and exports
+/// the `instances` interface: each `component` resource the host constructs
+/// owns a state of its own, so a child mounted twice keeps two states. The
+/// world is `leaf-instances` if the impl calls `event_query`, `emit` or
+/// `event_detail`, else `pure-instances`.
+///
+/// An instance's state is borrowed mutably for the whole of `render`,
+/// `handle-event` and `handle-bind`, so a host that calls back into the same
+/// instance from within one of them (say, dispatching an event synchronously
+/// while applying a render) panics on the `RefCell`.
+pub fn expand(options: Options, item: ItemImpl) -> Result<TokenStream> {
+    let state_ty = &item.self_ty;
+    if item.trait_.is_some() {
+        return Err(Error::new_spanned(
+            &item,
+            "#[component] goes on an inherent impl, e.g. `impl Counter { fn view ... }`",
+        ));
+    }
+    let Some(view) = method(&item, "view") else {
+        return Err(Error::new_spanned(
+            &item.self_ty,
+            "#[component] impl needs `fn view(&self) -> Vec<Opcode>`",
+        ));
+    };
+    let render = match props_type(view)? {
+        None => quote! { state.view() },
+        Some(ty) => {
+            let (props_ty, arg) = match ty {
+                Type::Reference(r) => (&*r.elem, quote! { &props }),
+                ty => (ty, quote! { props }),
+            };
+            quote! {
+                match <#props_ty>::from_props(&props) {
+                    ::core::result::Result::Ok(props) => state.view(#arg),
+                    ::core::result::Result::Err(err) => ::std::vec![Opcode::Text(err)],
+                }
+            }
+        }
+    };
+    let init = match method(&item, "new") {
+        None => quote! { ::core::result::Result::Ok(::core::default::Default::default()) },
+        Some(new) => {
+            let (props_ty, arg) = match constructor_props_type(new)? {
+                Type::Reference(r) => (&*r.elem, quote! { &props }),
+                ty => (ty, quote! { props }),
+            };
+            quote! { <#props_ty>::from_props(&props).map(|props| <#state_ty>::new(#arg)) }
+        }
+    };
+    let msg_ty = match method(&item, "update") {
+        Some(update) => Some(msg_type(update)?),
+        None => None,
+    };
+
+    let world = match &options.world {
+        Some(world) => world.value(),
+        None if calls(item.to_token_stream(), HOST_IMPORTS) => "leaf-instances".into(),
+        None => "pure-instances".into(),
+    };
+    let wit = match &options.wit {
+        Some(wit) => wit.value(),
+        None => find_wit()?,
+    };
+    let with = options.types.map(|types| {
+        quote! {
+            with: {
+                "wasm-components:dom/types@0.1.0": #types::wasm_components::dom::types,
+            },
+        }
+    });
+    let host = (world == "leaf-instances").then(|| {
+        quote! {
+            #[allow(unused_imports)]
+            use wasm_components::dom::host::{emit, event_detail, event_query};
+        }
+    });
+
+    let handle_event = msg_ty.map(|msg| {
+        quote! {
+            if let ::core::option::Option::Some(msg) = <#msg>::from_handler(&handler) {
+                state.update(msg);
+            }
+        }
+    });
+
+    Ok(quote! {
+        wit_bindgen::generate!({
+            path: #wit,
+            world: #world,
+            #with
+        });
+
+        #[allow(unused_imports)]
+        use exports::wasm_components::dom::instances::{Opcode, PropValue};
+        #host
+
+        #item
+
+        struct __Component;
+
+        /// One mounted instance and its state, or why its props didn't
+        /// construct one. Each export borrows the state mutably until it
+        /// returns; re-entering the same instance panics.
+        struct __Instance(
+            ::core::cell::RefCell<::core::result::Result<#state_ty, ::std::string::String>>,
+        );
+
+        impl exports::wasm_components::dom::instances::Guest for __Component {
+            type Component = __Instance;
+        }
+
+        impl exports::wasm_components::dom::instances::GuestComponent for __Instance {
+            #[allow(unused_variables)]
+            fn new(props: ::std::vec::Vec<(::std::string::String, PropValue)>) -> Self {
+                __Instance(::core::cell::RefCell::new(#init))
+            }
+
+            #[allow(unused_variables)]
+            fn render(
+                &self,
+                props: ::std::vec::Vec<(::std::string::String, PropValue)>,
+            ) -> ::std::vec::Vec<Opcode> {
+                let mut instance = self.0.borrow_mut();
+                let state = match &mut *instance {
+                    ::core::result::Result::Ok(state) => state,
+                    ::core::result::Result::Err(err) => return ::std::vec![Opcode::Text(err.clone())],
+                };
+                #render
+            }
+
+            #[allow(unused_variables)]
+            fn handle_event(&self, handler: ::std::string::String) {
+                let ::core::result::Result::Ok(state) = &mut *self.0.borrow_mut() else {
+                    return;
+                };
+                #handle_event
+            }
+
+            fn handle_bind(&self, handler: ::std::string::String, value: PropValue) {
+                // The inherent `bind` of `#[derive(Bind)]` wins over this
+                // fallback, for a state without one
+                trait NoBind {
+                    fn bind(&mut self, _handler: &str, _value: PropValue) -> bool {
+                        false
+                    }
+                }
+                impl<T: ?::core::marker::Sized> NoBind for T {}
+
+                let ::core::result::Result::Ok(state) = &mut *self.0.borrow_mut() else {
+                    return;
+                };
+                state.bind(&handler, value);
+            }
+        }
+
+        export!(__Component);
+    })
+}
+
+fn method<'a>(item: &'a ItemImpl, name: &str) -> Option<&'a ImplItemFn> {
+    item.items.iter().find_map(|item| match item {
+        ImplItem::Fn(f) if f.sig.ident == name => Some(f),
+        _ => None,
+    })
+}
+
+/// The props type of `fn view(&self, props: P)`, if it takes props.
+fn props_type(view: &ImplItemFn) -> Result<Option<&Type>> {
+    let mut inputs = view.sig.inputs.iter();
+    match (inputs.next(), inputs.next(), inputs.next()) {
+        (Some(FnArg::Receiver(_)), None, None) => Ok(None),
+        (Some(FnArg::Receiver(_)), Some(FnArg::Typed(props)), None) => Ok(Some(&props.ty)),
+        _ => Err(Error::new_spanned(
+            &view.sig,
+            "expected `fn view(&self) -> Vec<Opcode>` or `fn view(&self, props: &Props)`",
+        )),
+    }
+}
+
+/// The props type of `fn new(props: P) -> Self`.
+fn constructor_props_type(new: &ImplItemFn) -> Result<&Type> {
+    let mut inputs = new.sig.inputs.iter();
+    match (inputs.next(), inputs.next()) {
+        (Some(FnArg::Typed(props)), None) => Ok(&props.ty),
+        _ => Err(Error::new_spanned(
+            &new.sig,
+            "expected `fn new(props: &Props) -> Self`",
+        )),
+    }
+}
+
+/// The message type of `fn update(&mut self, msg: Msg)`.
+fn msg_type(update: &ImplItemFn) -> Result<&Type> {
+    let mut inputs = update.sig.inputs.iter();
+    match (inputs.next(), inputs.next(), inputs.next()) {
+        (Some(FnArg::Receiver(_)), Some(FnArg::Typed(msg)), None) => Ok(&msg.ty),
+        _ => Err(Error::new_spanned(
+            &update.sig,
+            "expected `fn update(&mut self, msg: Msg)`",
+        )),
+    }
+}
+
+/// Whether any of `names` appears as an identifier anywhere in `tokens`.
+fn calls(tokens: TokenStream, names: &[&str]) -> bool {
+    tokens.into_iter().any(|tt| match tt {
+        TokenTree::Ident(id) => names.iter().any(|name| id == name),
+        TokenTree::Group(g) => calls(g.stream(), names),
+        _ => false,
+    })
+}
+
+/// The nearest `wit` directory with a `dom.wit`, from the crate up.
+fn find_wit() -> Result<String> {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").map_err(|_| {
+        Error::new(
+            Span::call_site(),
+            "CARGO_MANIFEST_DIR is not set; pass `#[component(wit = \"path/to/wit\")]`",
+        )
+    })?;
+    let dir = PathBuf::from(&manifest_dir);
+    dir.ancestors()
+        .map(|dir| dir.join("wit"))
+        .find(|wit| wit.join("dom.wit").is_file())
+        .map(|wit| relative_to(&wit, &dir))
+        .ok_or_else(|| {
+            Error::new(
+                Span::call_site(),
+                format!(
+                    "no wit/dom.wit found above {}; pass `#[component(wit = \"path/to/wit\")]`",
+                    manifest_dir
+                ),
+            )
+        })
+}
+
+/// `wit` (an ancestor's child) as a path relative to `dir`, the form
+/// `wit_bindgen::generate!` resolves against the crate root.
+fn relative_to(wit: &Path, dir: &Path) -> String {
+    let parent = wit.parent().unwrap_or(wit);
+    let ups = dir
+        .strip_prefix(parent)
+        .map_or(0, |rest| rest.components().count());
+    let mut path = "../".repeat(ups);
+    path.push_str("wit");
+    path
+}
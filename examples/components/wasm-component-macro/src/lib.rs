@@ -0,0 +1,15 @@
+mod component;
+
+use proc_macro::TokenStream;
+
+#[proc_macro_attribute]
+pub fn component(args: TokenStream, input: TokenStream) -> TokenStream {
+    let mut options = component::Options::default();
+    let parser = syn::meta::parser(|meta| options.parse(meta));
+    syn::parse_macro_input!(args with parser);
+    let item = syn::parse_macro_input!(input as syn::ItemImpl);
+    match component::expand(options, item) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
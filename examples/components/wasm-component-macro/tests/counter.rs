@@ -0,0 +1,62 @@
+//! A component with no props, starting from `Default`, that handles events
+//! and bindings. The world is inferred as `pure-instances`.
+
+use exports::wasm_components::dom::instances::GuestComponent;
+use wasm_component_macro::component;
+use wasm_html_macro::{html, Bind, Handlers};
+
+#[derive(Handlers)]
+enum Msg {
+    Increment,
+}
+
+#[derive(Default, Bind)]
+struct Counter {
+    count: i32,
+    #[bind]
+    label: String,
+}
+
+#[component]
+impl Counter {
+    fn view(&self) -> Vec<Opcode> {
+        html! {
+            <p>
+                <input bind:value={self.label}>
+                <button on:click={Msg::Increment}>"{self.count}"</button>
+            </p>
+        }
+    }
+
+    fn update(&mut self, msg: Msg) {
+        match msg {
+            Msg::Increment => self.count += 1,
+        }
+    }
+}
+
+fn slots(ops: &[Opcode]) -> Vec<String> {
+    ops.iter()
+        .filter_map(|op| match op {
+            Opcode::Slot(text) => Some(text.clone()),
+            Opcode::TypedAttrSlot((name, PropValue::Str(value))) => {
+                Some(format!("{}={}", name, value))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn instances_start_from_default_and_keep_their_own_state() {
+    let a = __Instance::new(Vec::new());
+    let b = __Instance::new(Vec::new());
+    assert_eq!(slots(&a.render(Vec::new())), ["value=", "0"]);
+
+    a.handle_event("increment".into());
+    a.handle_event("increment".into());
+    a.handle_event("unknown".into());
+    a.handle_bind("label".into(), PropValue::Str("clicks".into()));
+    assert_eq!(slots(&a.render(Vec::new())), ["value=clicks", "2"]);
+    assert_eq!(slots(&b.render(Vec::new())), ["value=", "0"]);
+}
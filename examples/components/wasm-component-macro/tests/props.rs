@@ -0,0 +1,87 @@
+//! A component constructed by `new` from its props and given them again on
+//! each render. It calls `emit`, so the world is inferred as
+//! `leaf-instances`, and names its WIT directory rather than finding it.
+
+use exports::wasm_components::dom::instances::GuestComponent;
+use wasm_component_macro::component;
+use wasm_html_macro::{html, Handlers, Props};
+
+#[derive(Props)]
+struct StepProps {
+    step: i32,
+    #[prop(default)]
+    unit: String,
+}
+
+#[derive(Handlers)]
+enum Msg {
+    Step,
+    Report,
+}
+
+struct Stepper {
+    count: i32,
+}
+
+#[component(wit = "../../../wit")]
+impl Stepper {
+    fn new(props: &StepProps) -> Self {
+        Stepper { count: props.step }
+    }
+
+    fn view(&self, props: &StepProps) -> Vec<Opcode> {
+        html! { <p>"{self.count} {props.unit}"</p> }
+    }
+
+    fn update(&mut self, msg: Msg) {
+        match msg {
+            Msg::Step => self.count += 1,
+            // Never dispatched here: outside a host, imports aren't linked
+            Msg::Report => emit("report", &[]),
+        }
+    }
+}
+
+fn props(step: i32, unit: &str) -> Vec<(String, PropValue)> {
+    vec![
+        ("step".into(), PropValue::Int(step)),
+        ("unit".into(), PropValue::Str(unit.into())),
+    ]
+}
+
+fn text(ops: &[Opcode]) -> Vec<String> {
+    ops.iter()
+        .filter_map(|op| match op {
+            Opcode::Slot(text) | Opcode::Text(text) => Some(text.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn new_builds_the_state_from_the_props() {
+    let stepper = __Instance::new(props(5, "cm"));
+    assert_eq!(text(&stepper.render(props(5, "cm"))), ["5 cm"]);
+    stepper.handle_event("step".into());
+    // `view` takes the props of each render, `new` only the first
+    assert_eq!(text(&stepper.render(props(1, "mm"))), ["6 mm"]);
+}
+
+#[test]
+fn props_that_fail_to_convert_render_as_text() {
+    let bad = vec![("step".into(), PropValue::Str("five".into()))];
+    let stepper = __Instance::new(bad.clone());
+    let [error] = &text(&stepper.render(props(1, "")))[..] else {
+        panic!("expected one text");
+    };
+    assert!(error.contains("prop `step`"), "{}", error);
+    // Events and bindings have no state to go to
+    stepper.handle_event("step".into());
+    stepper.handle_bind("step".into(), PropValue::Int(1));
+
+    let stepper = __Instance::new(props(1, ""));
+    let [error] = &text(&stepper.render(bad))[..] else {
+        panic!("expected one text");
+    };
+    assert!(error.contains("prop `step`"), "{}", error);
+}
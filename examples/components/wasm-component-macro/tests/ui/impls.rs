@@ -0,0 +1,37 @@
+use wasm_component_macro::component;
+
+struct NoView;
+
+#[component]
+impl NoView {
+    fn render(&self) {}
+}
+
+struct OnTrait;
+
+#[component]
+impl Default for OnTrait {
+    fn default() -> Self {
+        OnTrait
+    }
+}
+
+struct BadWorld;
+
+#[component(world = "leaf-component")]
+impl BadWorld {
+    fn view(&self) -> Vec<Opcode> {
+        Vec::new()
+    }
+}
+
+struct BadOption;
+
+#[component(name = "x")]
+impl BadOption {
+    fn view(&self) -> Vec<Opcode> {
+        Vec::new()
+    }
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+use wasm_component_macro::component;
+
+#[derive(Default)]
+struct Query;
+
+// A pure world has no host imports, whatever the impl calls
+#[component(world = "pure-instances")]
+impl Query {
+    fn view(&self) -> Vec<Opcode> {
+        vec![Opcode::Text(event_query("type"))]
+    }
+}
+
+fn main() {}
@@ -0,0 +1,34 @@
+use wasm_component_macro::component;
+
+struct NoReceiver;
+
+#[component]
+impl NoReceiver {
+    fn view(props: &()) -> Vec<Opcode> {
+        Vec::new()
+    }
+}
+
+struct TwoProps;
+
+#[component]
+impl TwoProps {
+    fn view(&self, a: &(), b: &()) -> Vec<Opcode> {
+        Vec::new()
+    }
+}
+
+struct NewWithoutProps;
+
+#[component]
+impl NewWithoutProps {
+    fn new() -> Self {
+        NewWithoutProps
+    }
+
+    fn view(&self) -> Vec<Opcode> {
+        Vec::new()
+    }
+}
+
+fn main() {}
@@ -0,0 +1,23 @@
+//! wasmtime bindings for the worlds in `wit/dom.wit`. A component built for
+//! a `pure-*` world imports nothing, so the `leaf-*` bindings serve both.
+
+pub mod renderer {
+    wasmtime::component::bindgen!({
+        path: "../../../wit",
+        world: "leaf-component",
+        additional_derives: [PartialEq],
+    });
+}
+
+pub mod instances {
+    wasmtime::component::bindgen!({
+        path: "../../../wit",
+        world: "leaf-instances",
+        with: {
+            "wasm-components:dom/types": super::renderer::wasm_components::dom::types,
+            "wasm-components:dom/host": super::renderer::wasm_components::dom::host,
+        },
+    });
+}
+
+pub use renderer::wasm_components::dom::types::{Opcode, PropValue};
@@ -0,0 +1,501 @@
+//! The in-memory DOM the host renders into: an arena of nodes with the
+//! operations the runtime needs and a few for reading the result back.
+
+use std::collections::HashSet;
+use std::fmt::Write;
+
+/// A node in a [`Document`]. Ids stay valid while the node is detached,
+/// and go stale once it is freed: a freed node's slot is reused, but never
+/// under the same id.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId {
+    index: u32,
+    generation: u32,
+}
+
+#[derive(Clone, Debug)]
+pub struct Node {
+    pub data: NodeData,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+}
+
+#[derive(Clone, Debug)]
+pub enum NodeData {
+    Element(Element),
+    Text(String),
+    Comment(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct Element {
+    pub tag: String,
+    pub attrs: Vec<(String, String)>,
+    /// The `value`, `checked` and `selected` properties once set; until
+    /// then they reflect the attribute, as in a browser.
+    value: Option<String>,
+    checked: Option<bool>,
+    selected: Option<bool>,
+}
+
+/// The value of an element property: `value` is a string, `checked` and
+/// `selected` are booleans.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Property {
+    Str(String),
+    Bool(bool),
+}
+
+impl Node {
+    pub fn tag(&self) -> Option<&str> {
+        match &self.data {
+            NodeData::Element(el) => Some(&el.tag),
+            _ => None,
+        }
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        match &self.data {
+            NodeData::Element(el) => el
+                .attrs
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, v)| v.as_str()),
+            _ => None,
+        }
+    }
+
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+
+    pub fn is_element(&self) -> bool {
+        matches!(self.data, NodeData::Element(_))
+    }
+}
+
+pub struct Document {
+    slots: Vec<Slot>,
+    /// Indices of empty slots, to reuse
+    free: Vec<u32>,
+    /// Nodes freed since the last `take_freed`
+    freed: Vec<NodeId>,
+    body: NodeId,
+}
+
+struct Slot {
+    generation: u32,
+    node: Option<Node>,
+}
+
+impl Default for Document {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Document {
+    /// An empty document with a `<body>` to mount into.
+    pub fn new() -> Self {
+        let mut doc = Document {
+            slots: Vec::new(),
+            free: Vec::new(),
+            freed: Vec::new(),
+            body: NodeId {
+                index: 0,
+                generation: 0,
+            },
+        };
+        doc.body = doc.create_element("body");
+        doc
+    }
+
+    pub fn body(&self) -> NodeId {
+        self.body
+    }
+
+    /// Panics if `id` is stale.
+    pub fn node(&self, id: NodeId) -> &Node {
+        self.get(id).expect("node has been freed")
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<&Node> {
+        let slot = self.slots.get(id.index as usize)?;
+        slot.node
+            .as_ref()
+            .filter(|_| slot.generation == id.generation)
+    }
+
+    /// Whether `id` is a node that hasn't been freed.
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.get(id).is_some()
+    }
+
+    fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        let slot = &mut self.slots[id.index as usize];
+        match &mut slot.node {
+            Some(node) if slot.generation == id.generation => node,
+            _ => panic!("node has been freed"),
+        }
+    }
+
+    fn push(&mut self, data: NodeData) -> NodeId {
+        let node = Node {
+            data,
+            parent: None,
+            children: Vec::new(),
+        };
+        if let Some(index) = self.free.pop() {
+            let slot = &mut self.slots[index as usize];
+            slot.node = Some(node);
+            return NodeId {
+                index,
+                generation: slot.generation,
+            };
+        }
+        self.slots.push(Slot {
+            generation: 0,
+            node: Some(node),
+        });
+        NodeId {
+            index: (self.slots.len() - 1) as u32,
+            generation: 0,
+        }
+    }
+
+    pub fn create_element(&mut self, tag: &str) -> NodeId {
+        self.push(NodeData::Element(Element {
+            tag: tag.into(),
+            attrs: Vec::new(),
+            value: None,
+            checked: None,
+            selected: None,
+        }))
+    }
+
+    pub fn create_text(&mut self, text: &str) -> NodeId {
+        self.push(NodeData::Text(text.into()))
+    }
+
+    pub fn create_comment(&mut self, text: &str) -> NodeId {
+        self.push(NodeData::Comment(text.into()))
+    }
+
+    pub fn append_child(&mut self, parent: NodeId, child: NodeId) {
+        self.insert_before(parent, child, None);
+    }
+
+    /// Insert `child` before `reference` (or at the end), moving it if it
+    /// is already in the tree.
+    pub fn insert_before(&mut self, parent: NodeId, child: NodeId, reference: Option<NodeId>) {
+        self.remove(child);
+        let children = &mut self.node_mut(parent).children;
+        let idx = reference
+            .and_then(|r| children.iter().position(|&c| c == r))
+            .unwrap_or(children.len());
+        children.insert(idx, child);
+        self.node_mut(child).parent = Some(parent);
+    }
+
+    /// Detach `node` (and its subtree) from its parent.
+    pub fn remove(&mut self, node: NodeId) {
+        if let Some(parent) = self.node_mut(node).parent.take() {
+            self.node_mut(parent).children.retain(|&c| c != node);
+        }
+    }
+
+    /// Detach `node` and drop it and its subtree, leaving their ids stale.
+    pub fn free(&mut self, node: NodeId) {
+        self.remove(node);
+        for id in self.descendants(node) {
+            let slot = &mut self.slots[id.index as usize];
+            slot.node = None;
+            slot.generation += 1;
+            self.free.push(id.index);
+            self.freed.push(id);
+        }
+    }
+
+    /// The nodes freed since the last call, so whatever refers to them
+    /// (listeners) can be dropped too.
+    pub(crate) fn take_freed(&mut self) -> Vec<NodeId> {
+        std::mem::take(&mut self.freed)
+    }
+
+    pub fn parent(&self, node: NodeId) -> Option<NodeId> {
+        self.node(node).parent
+    }
+
+    pub fn children(&self, node: NodeId) -> &[NodeId] {
+        &self.node(node).children
+    }
+
+    pub fn first_child(&self, node: NodeId) -> Option<NodeId> {
+        self.node(node).children.first().copied()
+    }
+
+    pub fn next_sibling(&self, node: NodeId) -> Option<NodeId> {
+        let siblings = &self.node(self.parent(node)?).children;
+        let idx = siblings.iter().position(|&c| c == node)?;
+        siblings.get(idx + 1).copied()
+    }
+
+    /// A detached deep copy of `node`.
+    pub fn clone_node(&mut self, node: NodeId) -> NodeId {
+        let data = self.node(node).data.clone();
+        let copy = self.push(data);
+        for child in self.node(node).children.clone() {
+            let child = self.clone_node(child);
+            self.append_child(copy, child);
+        }
+        copy
+    }
+
+    /// A detached copy of `node` without the subtrees in `skip`, pushing
+    /// the nodes copied onto `copied` in document order.
+    pub(crate) fn clone_without(
+        &mut self,
+        node: NodeId,
+        skip: &HashSet<NodeId>,
+        copied: &mut Vec<NodeId>,
+    ) -> NodeId {
+        let data = self.node(node).data.clone();
+        let copy = self.push(data);
+        copied.push(node);
+        for child in self.node(node).children.clone() {
+            if !skip.contains(&child) {
+                let child = self.clone_without(child, skip, copied);
+                self.append_child(copy, child);
+            }
+        }
+        copy
+    }
+
+    /// `node` and its descendants, in document order.
+    pub fn descendants(&self, node: NodeId) -> Vec<NodeId> {
+        let mut list = Vec::new();
+        self.collect(node, &mut list);
+        list
+    }
+
+    fn collect(&self, node: NodeId, list: &mut Vec<NodeId>) {
+        list.push(node);
+        for &child in &self.node(node).children {
+            self.collect(child, list);
+        }
+    }
+
+    /// The first node under `root` (inclusive), in document order, that
+    /// matches `pred`.
+    pub fn find(&self, root: NodeId, mut pred: impl FnMut(&Node) -> bool) -> Option<NodeId> {
+        self.descendants(root)
+            .into_iter()
+            .find(|&id| pred(self.node(id)))
+    }
+
+    /// Whether `a` comes before `b` in document order.
+    pub fn precedes(&self, a: NodeId, b: NodeId) -> bool {
+        let (path_a, path_b) = (self.path(a), self.path(b));
+        match path_a.iter().zip(&path_b).find(|(x, y)| x != y) {
+            Some((x, y)) => x < y,
+            // One is an ancestor of the other
+            None => path_a.len() < path_b.len(),
+        }
+    }
+
+    /// Child indices from the root down to `node`.
+    fn path(&self, mut node: NodeId) -> Vec<usize> {
+        let mut path = Vec::new();
+        while let Some(parent) = self.parent(node) {
+            let idx = self.children(parent).iter().position(|&c| c == node);
+            path.push(idx.unwrap_or(0));
+            node = parent;
+        }
+        path.reverse();
+        path
+    }
+
+    fn element(&self, node: NodeId) -> Option<&Element> {
+        match &self.node(node).data {
+            NodeData::Element(el) => Some(el),
+            _ => None,
+        }
+    }
+
+    fn element_mut(&mut self, node: NodeId) -> Option<&mut Element> {
+        match &mut self.node_mut(node).data {
+            NodeData::Element(el) => Some(el),
+            _ => None,
+        }
+    }
+
+    pub fn attribute(&self, node: NodeId, name: &str) -> Option<&str> {
+        self.node(node).attribute(name)
+    }
+
+    pub fn set_attribute(&mut self, node: NodeId, name: &str, value: &str) {
+        let Some(el) = self.element_mut(node) else {
+            return;
+        };
+        match el.attrs.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = value.into(),
+            None => el.attrs.push((name.into(), value.into())),
+        }
+    }
+
+    pub fn remove_attribute(&mut self, node: NodeId, name: &str) {
+        if let Some(el) = self.element_mut(node) {
+            el.attrs.retain(|(n, _)| n != name);
+        }
+    }
+
+    /// `value`, `checked` or `selected`; `None` for any other name.
+    pub fn property(&self, node: NodeId, name: &str) -> Option<Property> {
+        let el = self.element(node)?;
+        let attr = |name: &str| el.attrs.iter().find(|(n, _)| n == name);
+        match name {
+            "value" => Some(Property::Str(el.value.clone().unwrap_or_else(|| {
+                attr("value").map(|(_, v)| v.clone()).unwrap_or_default()
+            }))),
+            "checked" => Some(Property::Bool(
+                el.checked.unwrap_or(attr("checked").is_some()),
+            )),
+            "selected" => Some(Property::Bool(
+                el.selected.unwrap_or(attr("selected").is_some()),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Set `value` (as a string) or `checked`/`selected` (as a boolean).
+    pub fn set_property(&mut self, node: NodeId, name: &str, value: Property) {
+        let Some(el) = self.element_mut(node) else {
+            return;
+        };
+        let truthy = |value: Property| match value {
+            Property::Bool(b) => b,
+            Property::Str(s) => !s.is_empty(),
+        };
+        match name {
+            "value" => {
+                el.value = Some(match value {
+                    Property::Str(s) => s,
+                    Property::Bool(b) => b.to_string(),
+                })
+            }
+            "checked" => el.checked = Some(truthy(value)),
+            "selected" => el.selected = Some(truthy(value)),
+            _ => {}
+        }
+    }
+
+    /// The data of a text or comment node.
+    pub fn text(&self, node: NodeId) -> Option<&str> {
+        match &self.node(node).data {
+            NodeData::Text(text) | NodeData::Comment(text) => Some(text),
+            NodeData::Element(_) => None,
+        }
+    }
+
+    pub fn set_text(&mut self, node: NodeId, text: &str) {
+        match &mut self.node_mut(node).data {
+            NodeData::Text(data) | NodeData::Comment(data) => {
+                data.clear();
+                data.push_str(text);
+            }
+            NodeData::Element(_) => {}
+        }
+    }
+
+    /// The text of `node` and its descendants, comments excluded.
+    pub fn text_content(&self, node: NodeId) -> String {
+        self.descendants(node)
+            .into_iter()
+            .filter_map(|id| match &self.node(id).data {
+                NodeData::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// `node` serialized as HTML, like `outerHTML`.
+    pub fn to_html(&self, node: NodeId) -> String {
+        let mut out = String::new();
+        self.write_html(node, &mut out);
+        out
+    }
+
+    /// The children of `node` serialized as HTML, like `innerHTML`.
+    pub fn inner_html(&self, node: NodeId) -> String {
+        let mut out = String::new();
+        for &child in self.children(node) {
+            self.write_html(child, &mut out);
+        }
+        out
+    }
+
+    fn write_html(&self, node: NodeId, out: &mut String) {
+        match &self.node(node).data {
+            NodeData::Text(text) => escape(text, false, out),
+            NodeData::Comment(text) => {
+                let _ = write!(out, "<!--{}-->", text);
+            }
+            NodeData::Element(el) => {
+                out.push('<');
+                out.push_str(&el.tag);
+                for (name, value) in &el.attrs {
+                    let _ = write!(out, " {}=\"", name);
+                    escape(value, true, out);
+                    out.push('"');
+                }
+                out.push('>');
+                if is_void_element(&el.tag) {
+                    return;
+                }
+                for &child in self.children(node) {
+                    self.write_html(child, out);
+                }
+                let _ = write!(out, "</{}>", el.tag);
+            }
+        }
+    }
+}
+
+/// Escape `text` for an HTML text node, or for a double-quoted attribute.
+pub(crate) fn escape(text: &str, attr: bool, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' if !attr => out.push_str("&lt;"),
+            '>' if !attr => out.push_str("&gt;"),
+            '"' if attr => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+}
+
+/// HTML elements that never have children or a closing tag: the list
+/// `html!` parses by, so what it accepts serialises as it was written.
+pub(crate) fn is_void_element(tag: &str) -> bool {
+    matches!(
+        tag,
+        "area"
+            | "base"
+            | "br"
+            | "col"
+            | "embed"
+            | "hr"
+            | "img"
+            | "input"
+            | "link"
+            | "meta"
+            | "param"
+            | "source"
+            | "track"
+            | "wbr"
+    )
+}
@@ -0,0 +1,131 @@
+//! Synthetic events for [`Host::dispatch`](crate::Host::dispatch) and the
+//! `host::event-query` paths they answer.
+
+use crate::dom::{Document, NodeId, Property};
+use crate::PropValue;
+
+/// An event to dispatch at a node. `type` and `target.*` are answered from
+/// the DOM; anything else a component queries (`key`, `clientX`, ...) is
+/// set with [`Event::with`].
+#[derive(Clone, Debug)]
+pub struct Event {
+    event_type: String,
+    fields: Vec<(String, String)>,
+}
+
+impl Event {
+    pub fn new(event_type: &str) -> Self {
+        Event {
+            event_type: event_type.into(),
+            fields: Vec::new(),
+        }
+    }
+
+    /// Answer `event_query(path)` with `value`, e.g. `.with("key", "Enter")`.
+    pub fn with(mut self, path: &str, value: &str) -> Self {
+        self.fields.push((path.into(), value.into()));
+        self
+    }
+
+    pub fn event_type(&self) -> &str {
+        &self.event_type
+    }
+}
+
+/// The event being handled: what `event-query` and `event-detail` read.
+pub(crate) struct Current {
+    pub event: Event,
+    pub target: Option<NodeId>,
+    pub current_target: Option<NodeId>,
+    pub detail: Vec<(String, PropValue)>,
+}
+
+impl Current {
+    /// `event_query(path)`, as `src/host.js` answers it from a DOM event;
+    /// unknown paths are empty.
+    pub fn query(&self, doc: &Document, path: &str) -> String {
+        if let Some((_, value)) = self.event.fields.iter().find(|(p, _)| p == path) {
+            return value.clone();
+        }
+        let mut segments = path.split('.');
+        match segments.next() {
+            Some("type") => self.event.event_type.clone(),
+            Some("target") => node_query(doc, self.target, segments),
+            Some("currentTarget") => node_query(doc, self.current_target, segments),
+            Some("detail") => {
+                let name = segments.next().unwrap_or_default();
+                self.detail
+                    .iter()
+                    .find(|(n, _)| n == name)
+                    .map(|(_, value)| prop_string(value))
+                    .unwrap_or_default()
+            }
+            _ => String::new(),
+        }
+    }
+}
+
+/// `String(value)` of a prop value.
+pub(crate) fn prop_string(value: &PropValue) -> String {
+    match value {
+        PropValue::Int(n) => n.to_string(),
+        PropValue::Str(s) => s.clone(),
+        PropValue::Boolean(b) => b.to_string(),
+        PropValue::Float(f) => f.to_string(),
+    }
+}
+
+fn node_query<'a>(
+    doc: &Document,
+    node: Option<NodeId>,
+    mut segments: impl Iterator<Item = &'a str>,
+) -> String {
+    let Some(mut node) = node else {
+        return String::new();
+    };
+    while let Some(segment) = segments.next() {
+        let attr = |name: &str| doc.attribute(node, name).unwrap_or_default().to_string();
+        match segment {
+            "parentElement" => match doc.parent(node) {
+                Some(parent) if doc.node(parent).is_element() => node = parent,
+                _ => return String::new(),
+            },
+            "dataset" => {
+                let key = segments.next().unwrap_or_default();
+                return attr(&format!("data-{}", camel_to_kebab(key)));
+            }
+            "className" => return attr("class"),
+            "id" => return attr("id"),
+            "tagName" | "nodeName" => {
+                return doc
+                    .node(node)
+                    .tag()
+                    .map(str::to_uppercase)
+                    .unwrap_or_default()
+            }
+            "textContent" => return doc.text_content(node),
+            name => {
+                return match doc.property(node, name) {
+                    Some(Property::Str(s)) => s,
+                    Some(Property::Bool(b)) => b.to_string(),
+                    None => String::new(),
+                }
+            }
+        }
+    }
+    String::new()
+}
+
+/// `dataset` keys to attribute names: `rowId` → `row-id`.
+fn camel_to_kebab(key: &str) -> String {
+    let mut out = String::new();
+    for c in key.chars() {
+        if c.is_ascii_uppercase() {
+            out.push('-');
+            out.push(c.to_ascii_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
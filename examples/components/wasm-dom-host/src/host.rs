@@ -0,0 +1,374 @@
+//! Loading and mounting components, and dispatching events to them: the
+//! `createHost`/`mount`/`dispatch` half of `src/runtime.js`, plus the
+//! `host` interface of `src/host.js`.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::rc::Rc;
+
+use anyhow::Result;
+use wasmtime::component::{Component, HasSelf, Linker, ResourceAny};
+use wasmtime::{Engine, Store};
+
+use crate::bindings::{instances, renderer};
+use crate::dom::{Document, NodeId, Property};
+use crate::event::{Current, Event};
+use crate::runtime::{Action, Listener, Mounted};
+use crate::{Opcode, PropValue};
+
+const INSTANCES: &str = "wasm-components:dom/instances@0.1.0";
+
+/// What the component's `host` imports read and write.
+pub(crate) struct State {
+    pub doc: Document,
+    pub current: Option<Current>,
+    pub emitted: Vec<(String, Vec<(String, PropValue)>)>,
+}
+
+impl renderer::wasm_components::dom::types::Host for State {}
+
+impl renderer::wasm_components::dom::host::Host for State {
+    fn event_query(&mut self, path: String) -> String {
+        match &self.current {
+            Some(current) => current.query(&self.doc, &path),
+            None => String::new(),
+        }
+    }
+
+    // Emits outside an event handler are dropped, as in `src/host.js`
+    fn emit(&mut self, name: String, detail: Vec<(String, PropValue)>) {
+        if self.current.is_some() {
+            self.emitted.push((name, detail));
+        }
+    }
+
+    fn event_detail(&mut self) -> Vec<(String, PropValue)> {
+        match &self.current {
+            Some(current) => current.detail.clone(),
+            None => Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Exports {
+    Renderer(Rc<renderer::LeafComponent>),
+    Instances(Rc<instances::LeafInstances>),
+}
+
+/// A loaded component, ready to mount any number of times. Components
+/// exporting `renderer` share their state between mounts; those exporting
+/// `instances` get a new `component` resource for each.
+#[derive(Clone)]
+pub struct Loaded(Exports);
+
+/// A mounted component.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ComponentId(pub(crate) usize);
+
+/// How a mounted component is called.
+pub(crate) enum Renderer {
+    Functions(Rc<renderer::LeafComponent>),
+    Instance(Rc<instances::LeafInstances>, ResourceAny),
+}
+
+/// The handler to run for an event.
+enum Call {
+    Event(String),
+    Bind(String, PropValue),
+}
+
+pub struct Host {
+    engine: Engine,
+    linker: Linker<State>,
+    store: Store<State>,
+    pub(crate) components: Vec<Option<Mounted>>,
+    pub(crate) listeners: HashMap<NodeId, Vec<Listener>>,
+}
+
+impl Host {
+    pub fn new() -> Result<Self> {
+        let engine = Engine::default();
+        let mut linker = Linker::new(&engine);
+        // The `instances` bindings reuse these interfaces, so this serves both
+        renderer::LeafComponent::add_to_linker::<_, HasSelf<_>>(&mut linker, |state| state)?;
+        let store = Store::new(
+            &engine,
+            State {
+                doc: Document::new(),
+                current: None,
+                emitted: Vec::new(),
+            },
+        );
+        Ok(Host {
+            engine,
+            linker,
+            store,
+            components: Vec::new(),
+            listeners: HashMap::new(),
+        })
+    }
+
+    /// Load a component from its binary or text format.
+    pub fn load(&mut self, bytes: impl AsRef<[u8]>) -> Result<Loaded> {
+        let component = Component::new(&self.engine, bytes)?;
+        self.instantiate(component)
+    }
+
+    pub fn load_file(&mut self, path: impl AsRef<Path>) -> Result<Loaded> {
+        let component = Component::from_file(&self.engine, path)?;
+        self.instantiate(component)
+    }
+
+    fn instantiate(&mut self, component: Component) -> Result<Loaded> {
+        let exports = if component.get_export_index(None, INSTANCES).is_some() {
+            Exports::Instances(Rc::new(instances::LeafInstances::instantiate(
+                &mut self.store,
+                &component,
+                &self.linker,
+            )?))
+        } else {
+            Exports::Renderer(Rc::new(renderer::LeafComponent::instantiate(
+                &mut self.store,
+                &component,
+                &self.linker,
+            )?))
+        };
+        Ok(Loaded(exports))
+    }
+
+    /// Mount a component in a new `<div>` at the end of the body and render
+    /// it. `children` maps the names of its `child` opcodes to components.
+    pub fn mount(
+        &mut self,
+        component: &Loaded,
+        children: &[(&str, &Loaded)],
+        props: Vec<(String, PropValue)>,
+    ) -> Result<ComponentId> {
+        let doc = self.doc();
+        let mount_point = doc.create_element("div");
+        let body = doc.body();
+        doc.append_child(body, mount_point);
+        let children = children
+            .iter()
+            .map(|(name, child)| (name.to_string(), (*child).clone()))
+            .collect();
+        self.mount_at(component, children, mount_point, props)
+    }
+
+    pub(crate) fn mount_at(
+        &mut self,
+        component: &Loaded,
+        children: HashMap<String, Loaded>,
+        mount_point: NodeId,
+        props: Vec<(String, PropValue)>,
+    ) -> Result<ComponentId> {
+        let renderer = match &component.0 {
+            Exports::Renderer(exports) => Renderer::Functions(exports.clone()),
+            Exports::Instances(exports) => {
+                let resource = exports
+                    .wasm_components_dom_instances()
+                    .component()
+                    .call_constructor(&mut self.store, &props)?;
+                Renderer::Instance(exports.clone(), resource)
+            }
+        };
+        let id = ComponentId(self.components.len());
+        self.components
+            .push(Some(Mounted::new(renderer, children, props, mount_point)));
+        self.render(id)?;
+        Ok(id)
+    }
+
+    /// One render of `component` outside the document: `instances`
+    /// components get a `component` resource just for it.
+    pub(crate) fn render_detached(
+        &mut self,
+        component: &Loaded,
+        props: Vec<(String, PropValue)>,
+    ) -> Result<Vec<Opcode>> {
+        match &component.0 {
+            Exports::Renderer(exports) => exports
+                .wasm_components_dom_renderer()
+                .call_render(&mut self.store, &props),
+            Exports::Instances(exports) => {
+                let guest = exports.wasm_components_dom_instances().component();
+                let resource = guest.call_constructor(&mut self.store, &props)?;
+                let opcodes = guest.call_render(&mut self.store, resource, &props);
+                resource.resource_drop(&mut self.store)?;
+                opcodes
+            }
+        }
+    }
+
+    /// The element a component renders into.
+    pub fn mount_point(&self, id: ComponentId) -> NodeId {
+        self.mounted(id).mount_point
+    }
+
+    pub fn document(&self) -> &Document {
+        &self.store.data().doc
+    }
+
+    /// The document, for changing what components will see, e.g. an
+    /// input's value before dispatching `input` at it.
+    pub fn document_mut(&mut self) -> &mut Document {
+        self.doc()
+    }
+
+    pub(crate) fn doc(&mut self) -> &mut Document {
+        &mut self.store.data_mut().doc
+    }
+
+    fn mounted(&self, id: ComponentId) -> &Mounted {
+        self.components[id.0]
+            .as_ref()
+            .expect("component is rendering or unmounted")
+    }
+
+    pub(crate) fn mounted_mut(&mut self, id: ComponentId) -> &mut Mounted {
+        self.components[id.0]
+            .as_mut()
+            .expect("component is rendering or unmounted")
+    }
+
+    /// Dispatch `event` at `target`. It bubbles: listeners on the target
+    /// and then each of its ancestors run, each followed by a re-render of
+    /// the component that added it.
+    pub fn dispatch(&mut self, target: NodeId, event: Event) -> Result<()> {
+        anyhow::ensure!(
+            self.document().contains(target),
+            "dispatch target {:?} has been removed",
+            target
+        );
+        let mut path = vec![target];
+        while let Some(parent) = self.document().parent(*path.last().unwrap()) {
+            path.push(parent);
+        }
+        for node in path {
+            let listeners: Vec<Listener> = self
+                .listeners
+                .get(&node)
+                .into_iter()
+                .flatten()
+                .filter(|listener| listener.event_type == event.event_type())
+                .cloned()
+                .collect();
+            for listener in listeners {
+                let call = match listener.action {
+                    Action::Event(handler) => Call::Event(handler),
+                    // Deliver the property's new value with the handler
+                    Action::Bind { property, handler } => {
+                        let value = match self.document().property(node, &property) {
+                            Some(Property::Bool(b)) => PropValue::Boolean(b),
+                            Some(Property::Str(s)) => PropValue::Str(s),
+                            None => PropValue::Str(String::new()),
+                        };
+                        Call::Bind(handler, value)
+                    }
+                };
+                let current = Current {
+                    event: event.clone(),
+                    target: Some(target),
+                    current_target: Some(node),
+                    detail: Vec::new(),
+                };
+                self.handle(listener.component, current, call)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Run a component's handler for an event, re-render it, then deliver
+    /// what it emitted to its parent.
+    fn handle(&mut self, id: ComponentId, current: Current, call: Call) -> Result<()> {
+        // Unmounted by an earlier handler, with the group instance it was in
+        if self.components[id.0].is_none() {
+            return Ok(());
+        }
+        self.store.data_mut().current = Some(current);
+        let result = self.call(id, call);
+        let state = self.store.data_mut();
+        state.current = None;
+        let emitted = std::mem::take(&mut state.emitted);
+        result?;
+        self.render(id)?;
+        // Looked up first: the parent's handlers can unmount this component
+        let comp = self.mounted(id);
+        let deliveries: Vec<_> = emitted
+            .into_iter()
+            .filter_map(|(name, detail)| {
+                Some((
+                    comp.parent?,
+                    comp.listeners.get(&name)?.clone(),
+                    name,
+                    detail,
+                ))
+            })
+            .collect();
+        for (parent, handler, name, detail) in deliveries {
+            let current = Current {
+                event: Event::new(&name),
+                target: None,
+                current_target: None,
+                detail,
+            };
+            self.handle(parent, current, Call::Event(handler))?;
+        }
+        Ok(())
+    }
+
+    fn call(&mut self, id: ComponentId, call: Call) -> Result<()> {
+        let store = &mut self.store;
+        match &self.components[id.0]
+            .as_ref()
+            .expect("component is rendering or unmounted")
+            .renderer
+        {
+            Renderer::Functions(exports) => {
+                let renderer = exports.wasm_components_dom_renderer();
+                match call {
+                    Call::Event(handler) => renderer.call_handle_event(store, &handler),
+                    Call::Bind(handler, _) => anyhow::bail!(
+                        "cannot deliver bind `{}`: the component exports `renderer`, \
+                         which has no `handle-bind`; export `instances` to bind",
+                        handler
+                    ),
+                }
+            }
+            Renderer::Instance(exports, resource) => {
+                let component = exports.wasm_components_dom_instances().component();
+                match call {
+                    Call::Event(handler) => component.call_handle_event(store, *resource, &handler),
+                    Call::Bind(handler, value) => {
+                        component.call_handle_bind(store, *resource, &handler, &value)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Drop the resource a component constructed for its mount, if any.
+    pub(crate) fn drop_renderer(&mut self, renderer: Renderer) -> Result<()> {
+        match renderer {
+            Renderer::Functions(_) => Ok(()),
+            Renderer::Instance(_, resource) => resource.resource_drop(&mut self.store),
+        }
+    }
+
+    pub(crate) fn call_render(&mut self, id: ComponentId) -> Result<Vec<Opcode>> {
+        let store = &mut self.store;
+        let comp = self.components[id.0]
+            .as_ref()
+            .expect("component is rendering or unmounted");
+        match &comp.renderer {
+            Renderer::Functions(exports) => exports
+                .wasm_components_dom_renderer()
+                .call_render(store, &comp.props),
+            Renderer::Instance(exports, resource) => exports
+                .wasm_components_dom_instances()
+                .component()
+                .call_render(store, *resource, &comp.props),
+        }
+    }
+}
@@ -0,0 +1,29 @@
+//! A native host for `wit/dom.wit` components: loads them with wasmtime and
+//! renders their opcodes into an in-memory DOM, following the same
+//! group/template rules as `src/runtime.js`, so components can be exercised
+//! from `cargo test` or on a server. [`Host::render_to_string`] prerenders
+//! a component to HTML without mounting it.
+//!
+//! ```ignore
+//! let mut host = Host::new()?;
+//! let app = host.load_file("counter-app.component.wasm")?;
+//! let counter = host.load_file("rust-counter.component.wasm")?;
+//! let id = host.mount(&app, &[("rust-child", &counter)], Vec::new())?;
+//!
+//! let button = host.document().find(host.mount_point(id), |node| node.tag() == Some("button"));
+//! host.dispatch(button.unwrap(), Event::new("click"))?;
+//! println!("{}", host.document().to_html(host.mount_point(id)));
+//! ```
+
+mod bindings;
+mod dom;
+mod event;
+mod host;
+mod runtime;
+mod ssr;
+
+pub use bindings::{Opcode, PropValue};
+pub use dom::{Document, Element, Node, NodeData, NodeId, Property};
+pub use event::Event;
+pub use host::{ComponentId, Host, Loaded};
+pub use ssr::{opcodes_to_html, opcodes_to_hydratable_html};
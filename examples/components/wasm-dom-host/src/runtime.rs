@@ -0,0 +1,1025 @@
+//! The opcode interpreter: `buildDOM`/`updateDOM` of `src/runtime.js`,
+//! rendering into a [`Document`]. The two are kept in step, so the comments
+//! here mostly cover where they differ.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+
+use crate::dom::{Document, NodeId, Property};
+use crate::event::prop_string;
+use crate::host::{ComponentId, Host, Loaded, Renderer};
+use crate::{Opcode, PropValue};
+
+const PROP_ATTRS: &[&str] = &["value", "checked", "selected"];
+
+/// A mounted component and what `build` recorded about its DOM.
+pub(crate) struct Mounted {
+    pub renderer: Renderer,
+    pub children: HashMap<String, Loaded>,
+    pub props: Vec<(String, PropValue)>,
+    pub mount_point: NodeId,
+    pub parent: Option<ComponentId>,
+    /// Handlers in the parent for events this component emits
+    pub listeners: HashMap<String, String>,
+    scope: Scope,
+    /// Group templates by template ID, cached from each group's first
+    /// instance
+    templates: HashMap<String, Template>,
+    initialized: bool,
+}
+
+impl Mounted {
+    pub fn new(
+        renderer: Renderer,
+        children: HashMap<String, Loaded>,
+        props: Vec<(String, PropValue)>,
+        mount_point: NodeId,
+    ) -> Self {
+        Mounted {
+            renderer,
+            children,
+            props,
+            mount_point,
+            parent: None,
+            listeners: HashMap::new(),
+            scope: Scope::default(),
+            templates: HashMap::new(),
+            initialized: false,
+        }
+    }
+}
+
+/// What a DOM listener runs: `handle-event`, or `handle-bind` with the
+/// element's property.
+#[derive(Clone, Debug)]
+pub(crate) enum Action {
+    Event(String),
+    Bind { property: String, handler: String },
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Listener {
+    pub event_type: String,
+    pub component: ComponentId,
+    pub action: Action,
+}
+
+#[derive(Clone, Debug)]
+enum Part {
+    Slot(NodeId),
+    AttrSlot {
+        element: NodeId,
+        name: String,
+    },
+    TypedAttrSlot {
+        element: NodeId,
+        name: String,
+    },
+    /// `None` in an instance cloned from its template, until the child is
+    /// mounted by the update that follows
+    Child {
+        node: NodeId,
+        component: Option<ComponentId>,
+    },
+}
+
+impl Part {
+    fn node(&self) -> NodeId {
+        match self {
+            Part::Slot(node) | Part::Child { node, .. } => *node,
+            Part::AttrSlot { element, .. } | Part::TypedAttrSlot { element, .. } => *element,
+        }
+    }
+
+    fn with_node(&self, node: NodeId) -> Part {
+        match self {
+            Part::Slot(_) => Part::Slot(node),
+            Part::AttrSlot { name, .. } => Part::AttrSlot {
+                element: node,
+                name: name.clone(),
+            },
+            Part::TypedAttrSlot { name, .. } => Part::TypedAttrSlot {
+                element: node,
+                name: name.clone(),
+            },
+            Part::Child { .. } => Part::Child {
+                node,
+                component: None,
+            },
+        }
+    }
+}
+
+/// A DOM listener added inside a group instance, kept so the group's
+/// template can add it to clones.
+#[derive(Clone)]
+struct InstanceEvent {
+    element: NodeId,
+    event_type: String,
+    action: Action,
+}
+
+/// What `update` needs of the nodes `build` made from a run of opcodes:
+/// the component's top level, or one group instance. Parts, groups and
+/// static nodes are in opcode order; a nested group belongs to its
+/// instance.
+#[derive(Default)]
+struct Scope {
+    parts: Vec<Part>,
+    groups: Vec<Group>,
+    static_nodes: Vec<NodeId>,
+    /// Listeners added in the scope, which a group instance's template
+    /// copies
+    events: Vec<InstanceEvent>,
+}
+
+struct Instance {
+    start: NodeId,
+    end: NodeId,
+    key: Option<String>,
+    scope: Scope,
+    /// Position in the previous render, for reused keyed instances
+    old_idx: Option<usize>,
+}
+
+impl Instance {
+    fn new(start: NodeId, key: Option<String>) -> Self {
+        Instance {
+            start,
+            end: start,
+            key,
+            scope: Scope::default(),
+            old_idx: None,
+        }
+    }
+}
+
+struct Group {
+    template_id: String,
+    parent: NodeId,
+    sentinel: NodeId,
+    instances: Vec<Instance>,
+}
+
+impl Group {
+    fn new(template_id: &str, parent: NodeId, sentinel: NodeId) -> Self {
+        Group {
+            template_id: template_id.into(),
+            parent,
+            sentinel,
+            instances: Vec::new(),
+        }
+    }
+}
+
+/// Detached copies of a group's first instance, less the instances of its
+/// nested groups and the content of its children, with what its scope
+/// recorded as indices into the copies' depth-first order.
+struct Template {
+    roots: Vec<NodeId>,
+    part_map: Vec<(usize, Part)>,
+    group_map: Vec<(usize, String)>,
+    static_map: Vec<usize>,
+    event_map: Vec<(usize, InstanceEvent)>,
+}
+
+/// The `prop` and `event` opcodes following the `child` at index `i`.
+struct ChildOps {
+    props: Vec<(String, PropValue)>,
+    listeners: HashMap<String, String>,
+    count: usize,
+}
+
+fn child_ops(opcodes: &[Opcode], i: usize) -> ChildOps {
+    let mut ops = ChildOps {
+        props: Vec::new(),
+        listeners: HashMap::new(),
+        count: 0,
+    };
+    for op in &opcodes[i + 1..] {
+        match op {
+            Opcode::Prop(prop) => ops.props.push(prop.clone()),
+            Opcode::Event((name, handler)) => {
+                ops.listeners.insert(name.clone(), handler.clone());
+            }
+            _ => break,
+        }
+        ops.count += 1;
+    }
+    ops
+}
+
+/// The template ID of a `begin` or `begin-keyed`.
+pub(crate) fn begin_id(op: Option<&Opcode>) -> Option<&str> {
+    match op {
+        Some(Opcode::Begin(id) | Opcode::BeginKeyed((id, _))) => Some(id),
+        _ => None,
+    }
+}
+
+fn bind_event(property: &str) -> &'static str {
+    if property == "value" {
+        "input"
+    } else {
+        "change"
+    }
+}
+
+fn set_attr_slot(doc: &mut Document, element: NodeId, name: &str, value: &str) {
+    if PROP_ATTRS.contains(&name) {
+        let value = match name {
+            "value" => Property::Str(value.into()),
+            _ => Property::Bool(!value.is_empty()),
+        };
+        if doc.property(element, name).as_ref() != Some(&value) {
+            doc.set_property(element, name, value);
+        }
+    } else if doc.attribute(element, name) != Some(value) {
+        doc.set_attribute(element, name, value);
+    }
+}
+
+/// `setTypedAttr`: a false boolean removes the attribute, and
+/// value/checked/selected are set as properties.
+fn set_typed_attr(doc: &mut Document, element: NodeId, name: &str, value: &PropValue) {
+    if PROP_ATTRS.contains(&name) {
+        let value = match (name, value) {
+            ("value", value) => Property::Str(prop_string(value)),
+            (_, PropValue::Boolean(b)) => Property::Bool(*b),
+            (_, PropValue::Int(n)) => Property::Bool(*n != 0),
+            (_, PropValue::Float(f)) => Property::Bool(*f != 0.0 && !f.is_nan()),
+            (_, PropValue::Str(s)) => Property::Bool(!s.is_empty()),
+        };
+        if doc.property(element, name).as_ref() != Some(&value) {
+            doc.set_property(element, name, value);
+        }
+    } else if let PropValue::Boolean(b) = value {
+        if doc.attribute(element, name).is_some() != *b {
+            if *b {
+                doc.set_attribute(element, name, "");
+            } else {
+                doc.remove_attribute(element, name);
+            }
+        }
+    } else {
+        let value = prop_string(value);
+        if doc.attribute(element, name) != Some(value.as_str()) {
+            doc.set_attribute(element, name, &value);
+        }
+    }
+}
+
+/// Update a slot, attr-slot or typed-attr-slot part from its opcode.
+fn update_part(doc: &mut Document, part: &Part, op: &Opcode) {
+    match (part, op) {
+        (Part::Slot(node), Opcode::Slot(text)) if doc.text(*node) != Some(text.as_str()) => {
+            doc.set_text(*node, text)
+        }
+        (Part::AttrSlot { element, name }, Opcode::AttrSlot((_, value))) => {
+            set_attr_slot(doc, *element, name, value)
+        }
+        (Part::TypedAttrSlot { element, name }, Opcode::TypedAttrSlot((_, value))) => {
+            set_typed_attr(doc, *element, name, value)
+        }
+        _ => {}
+    }
+}
+
+/// The template ID of a `begin`, `begin-keyed` or `seek`.
+fn group_id(op: &Opcode) -> &str {
+    match op {
+        Opcode::Begin(id) | Opcode::BeginKeyed((id, _)) | Opcode::Seek((id, _)) => id,
+        _ => "",
+    }
+}
+
+/// The key of a `begin-keyed`.
+fn begin_key(op: &Opcode) -> Option<String> {
+    match op {
+        Opcode::BeginKeyed((_, key)) => Some(key.clone()),
+        _ => None,
+    }
+}
+
+/// The nodes between an instance's markers.
+fn instance_nodes(doc: &Document, inst: &Instance) -> Vec<NodeId> {
+    let mut nodes = Vec::new();
+    let mut node = doc.next_sibling(inst.start);
+    while let Some(n) = node.filter(|&n| n != inst.end) {
+        nodes.push(n);
+        node = doc.next_sibling(n);
+    }
+    nodes
+}
+
+/// An instance's nodes, markers inclusive.
+fn instance_range(doc: &Document, inst: &Instance) -> Vec<NodeId> {
+    let mut nodes = vec![inst.start];
+    nodes.extend(instance_nodes(doc, inst));
+    nodes.push(inst.end);
+    nodes
+}
+
+fn new_instance(doc: &mut Document, group: &Group, key: Option<String>) -> Instance {
+    let start = doc.create_comment(&format!("begin:{}", group.template_id));
+    doc.insert_before(group.parent, start, Some(group.sentinel));
+    Instance::new(start, key)
+}
+
+/// Cache a group's template from its first instance: a copy of its nodes
+/// without the instances of nested groups or the content of children, so
+/// a clone starts with its nested groups empty and its children not yet
+/// mounted.
+fn cache_template(doc: &mut Document, inst: &Instance) -> Template {
+    let scope = &inst.scope;
+    let mut skip = HashSet::new();
+    for group in &scope.groups {
+        for nested in &group.instances {
+            skip.extend(instance_range(doc, nested));
+        }
+    }
+    for part in &scope.parts {
+        if let Part::Child { node, .. } = part {
+            skip.extend(doc.children(*node).iter().copied());
+        }
+    }
+    let mut copied = Vec::new();
+    let roots = instance_nodes(doc, inst)
+        .into_iter()
+        .filter(|node| !skip.contains(node))
+        .map(|node| doc.clone_without(node, &skip, &mut copied))
+        .collect();
+    let index: HashMap<NodeId, usize> = copied.iter().enumerate().map(|(i, &n)| (n, i)).collect();
+    Template {
+        roots,
+        part_map: scope
+            .parts
+            .iter()
+            .map(|part| (index[&part.node()], part.clone()))
+            .collect(),
+        group_map: scope
+            .groups
+            .iter()
+            .map(|group| (index[&group.sentinel], group.template_id.clone()))
+            .collect(),
+        static_map: scope.static_nodes.iter().map(|node| index[node]).collect(),
+        event_map: scope
+            .events
+            .iter()
+            .map(|ev| (index[&ev.element], ev.clone()))
+            .collect(),
+    }
+}
+
+/// Unmount the children in a scope, and in its groups' instances, before
+/// its nodes go.
+fn unmount_children(host: &mut Host, scope: &Scope) -> Result<()> {
+    for part in &scope.parts {
+        if let Part::Child {
+            component: Some(child),
+            ..
+        } = part
+        {
+            host.unmount(*child)?;
+        }
+    }
+    for group in &scope.groups {
+        for inst in &group.instances {
+            unmount_children(host, &inst.scope)?;
+        }
+    }
+    Ok(())
+}
+
+fn trim_group(host: &mut Host, group: &mut Group, keep: usize) -> Result<()> {
+    while group.instances.len() > keep {
+        if let Some(inst) = group.instances.pop() {
+            remove_instance(host, &inst)?;
+        }
+    }
+    Ok(())
+}
+
+/// Remove and free all nodes between the markers (inclusive), unmounting
+/// the children among them.
+fn remove_instance(host: &mut Host, inst: &Instance) -> Result<()> {
+    unmount_children(host, &inst.scope)?;
+    let doc = host.doc();
+    for node in instance_range(doc, inst) {
+        doc.free(node);
+    }
+    Ok(())
+}
+
+/// Find the group for a `begin` or `seek` from `groups[*group_idx]` on,
+/// leaving `*group_idx` at it. Groups are matched by template ID in
+/// document order: groups passed over rendered nothing this time (an empty
+/// list or an untaken branch) and are emptied. A template ID not seen
+/// before (e.g. from hand-written opcodes that leave out an empty group's
+/// `seek`) gets a new group in `parent`, anchored after `prev`, the last
+/// node passed at this level.
+fn enter_group(
+    host: &mut Host,
+    groups: &mut Vec<Group>,
+    group_idx: &mut usize,
+    template_id: &str,
+    parent: NodeId,
+    prev: Option<NodeId>,
+) -> Result<()> {
+    if let Some(g) = (*group_idx..groups.len()).find(|&g| groups[g].template_id == template_id) {
+        for group in &mut groups[*group_idx..g] {
+            trim_group(host, group, 0)?;
+        }
+        *group_idx = g;
+        return Ok(());
+    }
+    let doc = host.doc();
+    let sentinel = doc.create_comment("/group");
+    let reference = match prev {
+        Some(prev) => doc.next_sibling(prev),
+        None => doc.first_child(parent),
+    };
+    doc.insert_before(parent, sentinel, reference);
+    // Groups still ahead in the list that sit before the new sentinel in
+    // the document are empty this render.
+    while *group_idx < groups.len() && host.doc().precedes(groups[*group_idx].sentinel, sentinel) {
+        trim_group(host, &mut groups[*group_idx], 0)?;
+        *group_idx += 1;
+    }
+    groups.insert(*group_idx, Group::new(template_id, parent, sentinel));
+    Ok(())
+}
+
+impl Host {
+    /// Render a component: build its DOM the first time, update it after.
+    pub(crate) fn render(&mut self, id: ComponentId) -> Result<()> {
+        let opcodes = self.call_render(id)?;
+        // Out of the list while rendering, so its children can be rendered
+        let mut comp = self.components[id.0]
+            .take()
+            .expect("component is already rendering");
+        let Mounted {
+            children,
+            mount_point,
+            scope,
+            templates,
+            initialized,
+            ..
+        } = &mut comp;
+        let mut render = Render {
+            host: self,
+            id,
+            children,
+            templates,
+            opcodes: &opcodes,
+        };
+        let result = if *initialized {
+            render.update(scope, *mount_point, None, 0)
+        } else {
+            *initialized = true;
+            render.build(scope, *mount_point, None, 0)
+        };
+        self.components[id.0] = Some(comp);
+        // Listeners go with the nodes of removed instances
+        for node in self.doc().take_freed() {
+            self.listeners.remove(&node);
+        }
+        result.map(drop)
+    }
+
+    /// Unmount a child with the group instance it's in: its own children,
+    /// then the resource it constructed, if any.
+    fn unmount(&mut self, id: ComponentId) -> Result<()> {
+        let Some(comp) = self.components[id.0].take() else {
+            return Ok(());
+        };
+        unmount_children(self, &comp.scope)?;
+        self.drop_renderer(comp.renderer)
+    }
+
+    fn listen(
+        &mut self,
+        element: NodeId,
+        component: ComponentId,
+        event_type: &str,
+        action: Action,
+    ) {
+        self.listeners.entry(element).or_default().push(Listener {
+            event_type: event_type.into(),
+            component,
+            action,
+        });
+    }
+}
+
+/// One render of a component: its opcodes, and what `build` and `update`
+/// need of it besides the scope they're working on.
+struct Render<'a> {
+    host: &'a mut Host,
+    id: ComponentId,
+    children: &'a HashMap<String, Loaded>,
+    templates: &'a mut HashMap<String, Template>,
+    opcodes: &'a [Opcode],
+}
+
+/// Where `update_group` is in a group's instances.
+struct GroupUpdate {
+    instance_idx: usize,
+    /// Keyed groups: instances of the previous render by key, still
+    /// unclaimed
+    keyed_old: Option<HashMap<Option<String>, Instance>>,
+    /// Keyed groups: the instances of this render in order
+    keyed_new: Vec<Instance>,
+}
+
+impl Render<'_> {
+    fn listen(&mut self, scope: &mut Scope, element: NodeId, event_type: &str, action: Action) {
+        self.host
+            .listen(element, self.id, event_type, action.clone());
+        scope.events.push(InstanceEvent {
+            element,
+            event_type: event_type.into(),
+            action,
+        });
+    }
+
+    fn mount_child(&mut self, name: &str, node: NodeId, ops: ChildOps) -> Result<ComponentId> {
+        let child = self.children[name].clone();
+        let component = self
+            .host
+            .mount_at(&child, self.children.clone(), node, ops.props)?;
+        let mounted = self.host.mounted_mut(component);
+        mounted.parent = Some(self.id);
+        mounted.listeners = ops.listeners;
+        Ok(component)
+    }
+
+    /// Build the opcodes from `start` into `parent`, recording them in
+    /// `scope`, up to the `end` of the instance being built (if it's one).
+    /// Nodes at the root of the scope go before `before`: an instance's go
+    /// before its group's sentinel, so every instance stays between its own
+    /// markers. Returns the index of that `end`.
+    fn build(
+        &mut self,
+        scope: &mut Scope,
+        parent: NodeId,
+        before: Option<NodeId>,
+        start: usize,
+    ) -> Result<usize> {
+        let opcodes = self.opcodes;
+        let mut stack: Vec<(NodeId, Option<NodeId>)> = Vec::new();
+        let mut current = parent;
+        let mut element: Option<NodeId> = None;
+
+        let mut i = start;
+        while i < opcodes.len() {
+            let reference = if stack.is_empty() { before } else { None };
+            let doc = self.host.doc();
+            match &opcodes[i] {
+                Opcode::Open(tag) => {
+                    let el = doc.create_element(tag);
+                    doc.insert_before(current, el, reference);
+                    stack.push((current, element));
+                    current = el;
+                    element = Some(el);
+                    scope.static_nodes.push(el);
+                }
+                Opcode::Close => {
+                    if let Some((parent, el)) = stack.pop() {
+                        current = parent;
+                        element = el;
+                    }
+                }
+                Opcode::Attr((name, value)) => {
+                    if let Some(el) = element {
+                        doc.set_attribute(el, name, value);
+                    }
+                }
+                Opcode::Text(text) => {
+                    let node = doc.create_text(text);
+                    doc.insert_before(current, node, reference);
+                    scope.static_nodes.push(node);
+                }
+                Opcode::Slot(text) => {
+                    let node = doc.create_text(text);
+                    doc.insert_before(current, node, reference);
+                    scope.parts.push(Part::Slot(node));
+                }
+                Opcode::Event((event_type, handler)) => {
+                    if let Some(el) = element {
+                        self.listen(scope, el, event_type, Action::Event(handler.clone()));
+                    }
+                }
+                Opcode::Bind((property, handler)) => {
+                    if let Some(el) = element {
+                        let action = Action::Bind {
+                            property: property.clone(),
+                            handler: handler.clone(),
+                        };
+                        self.listen(scope, el, bind_event(property), action);
+                    }
+                }
+                Opcode::AttrSlot((name, value)) => {
+                    if let Some(el) = element {
+                        set_attr_slot(doc, el, name, value);
+                        scope.parts.push(Part::AttrSlot {
+                            element: el,
+                            name: name.clone(),
+                        });
+                    }
+                }
+                Opcode::TypedAttrSlot((name, value)) => {
+                    if let Some(el) = element {
+                        set_typed_attr(doc, el, name, value);
+                        scope.parts.push(Part::TypedAttrSlot {
+                            element: el,
+                            name: name.clone(),
+                        });
+                    }
+                }
+                Opcode::Child(name) => {
+                    let ops = child_ops(opcodes, i);
+                    i += ops.count;
+                    if self.children.contains_key(name) {
+                        let node = doc.create_element("div");
+                        doc.insert_before(current, node, reference);
+                        let component = self.mount_child(name, node, ops)?;
+                        scope.parts.push(Part::Child {
+                            node,
+                            component: Some(component),
+                        });
+                    }
+                }
+                Opcode::Begin(_) | Opcode::BeginKeyed(_) | Opcode::Seek(_) => {
+                    i = self.build_group(scope, current, reference, i)?;
+                }
+                Opcode::End => return Ok(i),
+                Opcode::Prop(_) | Opcode::Skip(_) => {}
+            }
+            i += 1;
+        }
+        Ok(opcodes.len())
+    }
+
+    /// Build a group from its first `begin`, or the `seek` marking an empty
+    /// one, and the instances that follow. A later group with the same ID
+    /// (a view spliced twice) is a group of its own. Returns the index of
+    /// the last opcode consumed.
+    fn build_group(
+        &mut self,
+        scope: &mut Scope,
+        parent: NodeId,
+        before: Option<NodeId>,
+        mut i: usize,
+    ) -> Result<usize> {
+        let opcodes = self.opcodes;
+        let template_id = group_id(&opcodes[i]);
+        let doc = self.host.doc();
+        let sentinel = doc.create_comment("/group");
+        doc.insert_before(parent, sentinel, before);
+        let mut group = Group::new(template_id, parent, sentinel);
+        if !matches!(opcodes[i], Opcode::Seek(_)) {
+            let (inst, end) = self.build_instance(&group, i)?;
+            group.instances.push(inst);
+            i = end;
+        }
+        while begin_id(opcodes.get(i + 1)) == Some(template_id) {
+            let (inst, end) = self.build_instance(&group, i + 1)?;
+            group.instances.push(inst);
+            i = end;
+        }
+        scope.groups.push(group);
+        Ok(i)
+    }
+
+    /// Build an instance of `group` from its `begin` at index `i`. Returns
+    /// it with the index of its `end`.
+    fn build_instance(&mut self, group: &Group, i: usize) -> Result<(Instance, usize)> {
+        let key = begin_key(&self.opcodes[i]);
+        let mut inst = new_instance(self.host.doc(), group, key);
+        let end_idx = self.build(&mut inst.scope, group.parent, Some(group.sentinel), i + 1)?;
+        let doc = self.host.doc();
+        let end = doc.create_comment("end");
+        doc.insert_before(group.parent, end, Some(group.sentinel));
+        inst.end = end;
+        // Cache template after first instance
+        if !self.templates.contains_key(&group.template_id) {
+            let template = cache_template(doc, &inst);
+            self.templates.insert(group.template_id.clone(), template);
+        }
+        Ok((inst, end_idx))
+    }
+
+    /// Update `scope` from the opcodes from `start` up to the `end` of its
+    /// instance (if it's one). `prev` is the node the scope starts after,
+    /// or `None` for the start of `parent`. Returns the index of that `end`.
+    fn update(
+        &mut self,
+        scope: &mut Scope,
+        parent: NodeId,
+        mut prev: Option<NodeId>,
+        start: usize,
+    ) -> Result<usize> {
+        let opcodes = self.opcodes;
+        let mut part_idx = 0;
+        let mut group_idx = 0;
+
+        // DOM position tracking via the static nodes recorded in `build`.
+        // `prev` is the last node passed at the current level, so a group
+        // seen for the first time can be anchored in the right place.
+        let mut static_node_idx = 0;
+        let mut dom_stack = vec![parent];
+        let mut prev_stack = Vec::new();
+
+        let mut i = start;
+        while i < opcodes.len() {
+            let op = &opcodes[i];
+            match op {
+                Opcode::End => break,
+                Opcode::Open(_) => {
+                    if let Some(&el) = scope.static_nodes.get(static_node_idx) {
+                        static_node_idx += 1;
+                        dom_stack.push(el);
+                        prev_stack.push(el);
+                        prev = None;
+                    }
+                }
+                Opcode::Close if dom_stack.len() > 1 => {
+                    dom_stack.pop();
+                    prev = prev_stack.pop();
+                }
+                Opcode::Text(_) => {
+                    if let Some(&node) = scope.static_nodes.get(static_node_idx) {
+                        static_node_idx += 1;
+                        prev = Some(node);
+                    }
+                }
+                Opcode::Child(name) if self.children.contains_key(name) => {
+                    // Listeners are static, like element events
+                    let ops = child_ops(opcodes, i);
+                    i += ops.count;
+                    if let Some(Part::Child { node, component }) = scope.parts.get_mut(part_idx) {
+                        part_idx += 1;
+                        match *component {
+                            // In an instance cloned from its group's template
+                            None => *component = Some(self.mount_child(name, *node, ops)?),
+                            Some(child) => {
+                                let mounted = self.host.mounted_mut(child);
+                                if mounted.props != ops.props {
+                                    mounted.props = ops.props;
+                                    self.host.render(child)?;
+                                }
+                            }
+                        }
+                        prev = Some(*node);
+                    }
+                }
+                Opcode::Slot(_) | Opcode::AttrSlot(_) | Opcode::TypedAttrSlot(_) => {
+                    if let Some(part) = scope.parts.get(part_idx) {
+                        update_part(self.host.doc(), part, op);
+                        if let Part::Slot(node) = part {
+                            prev = Some(*node);
+                        }
+                    }
+                    part_idx += 1;
+                }
+                Opcode::Begin(_) | Opcode::BeginKeyed(_) | Opcode::Seek(_) => {
+                    let parent = *dom_stack.last().expect("scope parent");
+                    enter_group(
+                        self.host,
+                        &mut scope.groups,
+                        &mut group_idx,
+                        group_id(op),
+                        parent,
+                        prev,
+                    )?;
+                    let group = &mut scope.groups[group_idx];
+                    i = self.update_group(group, i)?;
+                    prev = Some(group.sentinel);
+                    group_idx += 1;
+                }
+                // attr, event, bind, prop, skip — static after the first render
+                _ => {}
+            }
+            i += 1;
+        }
+
+        // Trim any groups that weren't visited (e.g. list went from N items to 0)
+        for group in &mut scope.groups[group_idx..] {
+            trim_group(self.host, group, 0)?;
+        }
+        Ok(i)
+    }
+
+    /// Update a group from its first `begin` or `seek` and what follows:
+    /// its instances, and any skip/seek between them. Returns the index of
+    /// the last opcode consumed.
+    fn update_group(&mut self, group: &mut Group, mut i: usize) -> Result<usize> {
+        let opcodes = self.opcodes;
+        let mut state = GroupUpdate {
+            instance_idx: 0,
+            keyed_old: None,
+            keyed_new: Vec::new(),
+        };
+        if let Opcode::Seek((_, n)) = &opcodes[i] {
+            state.instance_idx = (*n as usize).min(group.instances.len());
+        } else {
+            i = self.update_instance(group, &mut state, i)?;
+        }
+        loop {
+            let count = group.instances.len();
+            match opcodes.get(i + 1) {
+                Some(Opcode::Skip(n)) => {
+                    state.instance_idx = (state.instance_idx + *n as usize).min(count);
+                }
+                Some(Opcode::Seek((id, n))) if *id == group.template_id => {
+                    state.instance_idx = state.instance_idx.max((*n as usize).min(count));
+                }
+                next if begin_id(next) == Some(group.template_id.as_str()) => {
+                    i = self.update_instance(group, &mut state, i + 1)?;
+                    continue;
+                }
+                _ => break,
+            }
+            i += 1;
+        }
+
+        // Done with this group — drop what wasn't rendered
+        match state.keyed_old {
+            Some(keyed_old) => {
+                for old in keyed_old.values() {
+                    remove_instance(self.host, old)?;
+                }
+                arrange_keyed(self.host.doc(), group, &state.keyed_new);
+                group.instances = state.keyed_new;
+            }
+            None => trim_group(self.host, group, state.instance_idx)?,
+        }
+        Ok(i)
+    }
+
+    /// Update the next instance of `group` from its `begin` at index `i`:
+    /// reuse one (by position, or by key for begin-keyed), clone the cached
+    /// template, or build it. Returns the index of its `end`.
+    fn update_instance(
+        &mut self,
+        group: &mut Group,
+        state: &mut GroupUpdate,
+        i: usize,
+    ) -> Result<usize> {
+        let key = begin_key(&self.opcodes[i]);
+        let has_template = self.templates.contains_key(&group.template_id);
+        let idx = state.instance_idx;
+        state.instance_idx += 1;
+
+        if key.is_some() {
+            if state.keyed_old.is_none() {
+                let mut keyed_old = HashMap::new();
+                for (idx, mut old) in std::mem::take(&mut group.instances).into_iter().enumerate() {
+                    if keyed_old.contains_key(&old.key) {
+                        // Duplicate key: only the first instance can be matched
+                        remove_instance(self.host, &old)?;
+                        continue;
+                    }
+                    old.old_idx = Some(idx);
+                    keyed_old.insert(old.key.clone(), old);
+                }
+                state.keyed_old = Some(keyed_old);
+            }
+            let reused = state.keyed_old.as_mut().and_then(|old| old.remove(&key));
+            let mut inst = match reused {
+                Some(inst) => inst,
+                // Clone from cached template, then update it like a reused one
+                None if has_template => {
+                    let mut inst = self.clone_from_template(group);
+                    inst.key = key;
+                    inst
+                }
+                None => {
+                    let (inst, end) = self.build_instance(group, i)?;
+                    state.keyed_new.push(inst);
+                    return Ok(end);
+                }
+            };
+            let end = self.update(&mut inst.scope, group.parent, Some(inst.start), i + 1)?;
+            state.keyed_new.push(inst);
+            return Ok(end);
+        }
+
+        if idx >= group.instances.len() {
+            if !has_template {
+                let (inst, end) = self.build_instance(group, i)?;
+                group.instances.push(inst);
+                return Ok(end);
+            }
+            let inst = self.clone_from_template(group);
+            group.instances.push(inst);
+        }
+        let parent = group.parent;
+        let inst = &mut group.instances[idx];
+        self.update(&mut inst.scope, parent, Some(inst.start), i + 1)
+    }
+
+    fn clone_from_template(&mut self, group: &Group) -> Instance {
+        let doc = self.host.doc();
+        let template = &self.templates[&group.template_id];
+        let roots: Vec<NodeId> = template.roots.iter().map(|&r| doc.clone_node(r)).collect();
+        let nodes: Vec<NodeId> = roots.iter().flat_map(|&r| doc.descendants(r)).collect();
+        let mut inst = new_instance(doc, group, None);
+        for &root in &roots {
+            doc.insert_before(group.parent, root, Some(group.sentinel));
+        }
+        let end = doc.create_comment("end");
+        doc.insert_before(group.parent, end, Some(group.sentinel));
+        inst.end = end;
+        inst.scope = Scope {
+            parts: template
+                .part_map
+                .iter()
+                .map(|(idx, part)| part.with_node(nodes[*idx]))
+                .collect(),
+            groups: template
+                .group_map
+                .iter()
+                .map(|(idx, template_id)| {
+                    let sentinel = nodes[*idx];
+                    let parent = doc.parent(sentinel).unwrap_or(group.parent);
+                    Group::new(template_id, parent, sentinel)
+                })
+                .collect(),
+            static_nodes: template.static_map.iter().map(|&idx| nodes[idx]).collect(),
+            events: template
+                .event_map
+                .iter()
+                .map(|(idx, ev)| InstanceEvent {
+                    element: nodes[*idx],
+                    ..ev.clone()
+                })
+                .collect(),
+        };
+        for ev in &inst.scope.events {
+            self.host
+                .listen(ev.element, self.id, &ev.event_type, ev.action.clone());
+        }
+        inst
+    }
+}
+
+/// Move an instance's nodes (markers inclusive) before `reference`.
+fn move_instance(doc: &mut Document, parent: NodeId, inst: &Instance, reference: NodeId) {
+    let mut node = Some(inst.start);
+    while let Some(n) = node {
+        let next = doc.next_sibling(n);
+        doc.insert_before(parent, n, Some(reference));
+        if n == inst.end {
+            break;
+        }
+        node = next;
+    }
+}
+
+/// Put a keyed group's instances in render order with as few moves as
+/// possible: reused instances on the longest run that kept its previous
+/// order stay put, everything else is moved into place.
+fn arrange_keyed(doc: &mut Document, group: &Group, instances: &[Instance]) {
+    let (old_idx, new_idx): (Vec<usize>, Vec<usize>) = instances
+        .iter()
+        .enumerate()
+        .filter_map(|(i, inst)| inst.old_idx.map(|old| (old, i)))
+        .unzip();
+    let stay: Vec<usize> = longest_increasing(&old_idx)
+        .into_iter()
+        .map(|j| new_idx[j])
+        .collect();
+    let mut reference = group.sentinel;
+    for (i, inst) in instances.iter().enumerate().rev() {
+        if !stay.contains(&i) && doc.next_sibling(inst.end) != Some(reference) {
+            move_instance(doc, group.parent, inst, reference);
+        }
+        reference = inst.start;
+    }
+}
+
+/// Indices of a longest strictly increasing subsequence of `seq`.
+fn longest_increasing(seq: &[usize]) -> Vec<usize> {
+    let mut tails: Vec<usize> = Vec::new();
+    let mut prev = vec![None; seq.len()];
+    for i in 0..seq.len() {
+        let lo = tails.partition_point(|&t| seq[t] < seq[i]);
+        prev[i] = lo.checked_sub(1).map(|l| tails[l]);
+        if lo == tails.len() {
+            tails.push(i);
+        } else {
+            tails[lo] = i;
+        }
+    }
+    let mut result = Vec::with_capacity(tails.len());
+    let mut k = tails.last().copied();
+    while let Some(idx) = k {
+        result.push(idx);
+        k = prev[idx];
+    }
+    result.reverse();
+    result
+}
@@ -0,0 +1,260 @@
+//! Server-side rendering: a component's first render serialised as HTML,
+//! so a page can show it before the JS runtime has loaded. The markup is
+//! what `buildDOM` would create in the mount point, less the group markers,
+//! with value/checked/selected written as attributes. The hydratable
+//! flavour adds markers for the runtime's `hydrate` to adopt it by.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use anyhow::{bail, Result};
+
+use crate::dom::{escape, is_void_element};
+use crate::event::prop_string;
+use crate::host::{Host, Loaded};
+use crate::runtime::begin_id;
+use crate::{Opcode, PropValue};
+
+/// Serialise `opcodes` as HTML. `render_child` renders the component for a
+/// `child` opcode with its props, or returns `None` if there is none by
+/// that name, in which case the child is left out as in the runtime.
+/// Names are written unescaped, so a tag or attribute name that would break
+/// out of the markup is an error, as is a template ID that would end its
+/// marker comment.
+pub fn opcodes_to_html<F>(opcodes: &[Opcode], render_child: F) -> Result<String>
+where
+    F: FnMut(&str, Vec<(String, PropValue)>) -> Result<Option<String>>,
+{
+    serialize(opcodes, false, render_child)
+}
+
+/// [`opcodes_to_html`] with the markers the runtime's `hydrate` needs to
+/// adopt the markup instead of re-creating it:
+///
+/// - `<!--slot-->` before each slot's text, and `<!---->` between other
+///   adjacent text, so each text opcode finds its own text node
+/// - `data-slots="class disabled"` on elements with attr-slots, naming them
+///   in order
+/// - the runtime's own `<!--begin:ID-->`, `<!--end-->` and `<!--/group-->`
+///   around group instances
+/// - `data-child="name"` on the `<div>` a child is mounted in
+///
+/// `render_child` should return the child's markup with markers too.
+pub fn opcodes_to_hydratable_html<F>(opcodes: &[Opcode], render_child: F) -> Result<String>
+where
+    F: FnMut(&str, Vec<(String, PropValue)>) -> Result<Option<String>>,
+{
+    serialize(opcodes, true, render_child)
+}
+
+fn serialize<F>(opcodes: &[Opcode], markers: bool, mut render_child: F) -> Result<String>
+where
+    F: FnMut(&str, Vec<(String, PropValue)>) -> Result<Option<String>>,
+{
+    let mut out = String::new();
+    // Open tags whose `>` isn't written yet take attributes
+    let mut open_tag = false;
+    let mut slot_names: Vec<&str> = Vec::new();
+    // Whether the last node written was text, which the next would merge with
+    let mut last_text = false;
+    let mut stack: Vec<&str> = Vec::new();
+    // The template IDs of the group instances open, innermost last
+    let mut groups: Vec<&str> = Vec::new();
+    let mut i = 0;
+    while i < opcodes.len() {
+        let op = &opcodes[i];
+        let attr = matches!(
+            op,
+            Opcode::Attr(_) | Opcode::AttrSlot(_) | Opcode::TypedAttrSlot(_)
+        );
+        if open_tag && !attr && !matches!(op, Opcode::Event(_) | Opcode::Bind(_)) {
+            if !slot_names.is_empty() {
+                write_attr(&mut out, "data-slots", &slot_names.join(" "))?;
+                slot_names.clear();
+            }
+            out.push('>');
+            open_tag = false;
+        }
+        match op {
+            Opcode::Open(tag) => {
+                if !tag.starts_with(|c: char| c.is_ascii_alphabetic()) || !is_name(tag) {
+                    bail!("invalid tag name {:?}", tag);
+                }
+                let _ = write!(out, "<{}", tag);
+                stack.push(tag);
+                open_tag = true;
+                last_text = false;
+            }
+            Opcode::Close => {
+                if let Some(tag) = stack.pop() {
+                    if !is_void_element(tag) {
+                        let _ = write!(out, "</{}>", tag);
+                    }
+                }
+                last_text = false;
+            }
+            Opcode::Attr((name, value)) if open_tag => write_attr(&mut out, name, value)?,
+            Opcode::AttrSlot((name, value)) if open_tag => {
+                write_attr(&mut out, name, value)?;
+                if markers {
+                    slot_names.push(name);
+                }
+            }
+            Opcode::TypedAttrSlot((name, value)) if open_tag => {
+                match value {
+                    // A false boolean leaves the attribute out
+                    PropValue::Boolean(false) => check_attr(name)?,
+                    PropValue::Boolean(true) => write_attr(&mut out, name, "")?,
+                    value => write_attr(&mut out, name, &prop_string(value))?,
+                }
+                if markers {
+                    slot_names.push(name);
+                }
+            }
+            Opcode::Text(text) => {
+                if markers && last_text {
+                    out.push_str("<!---->");
+                }
+                escape(text, false, &mut out);
+                last_text = true;
+            }
+            Opcode::Slot(text) => {
+                if markers {
+                    out.push_str("<!--slot-->");
+                }
+                escape(text, false, &mut out);
+                last_text = true;
+            }
+            Opcode::Child(name) => {
+                let mut props = Vec::new();
+                while let Some(next) = opcodes.get(i + 1) {
+                    match next {
+                        Opcode::Prop(prop) => props.push(prop.clone()),
+                        Opcode::Event(_) => {}
+                        _ => break,
+                    }
+                    i += 1;
+                }
+                if let Some(html) = render_child(name, props)? {
+                    out.push_str("<div");
+                    if markers {
+                        write_attr(&mut out, "data-child", name)?;
+                    }
+                    let _ = write!(out, ">{}</div>", html);
+                    last_text = false;
+                }
+            }
+            Opcode::Begin(id) | Opcode::BeginKeyed((id, _)) => {
+                groups.push(id);
+                if markers {
+                    check_comment(id)?;
+                    let _ = write!(out, "<!--begin:{}-->", id);
+                    last_text = false;
+                }
+            }
+            Opcode::End => {
+                let id = groups.pop();
+                if markers {
+                    out.push_str("<!--end-->");
+                    // The group's sentinel follows its last instance
+                    if id.is_none() || begin_id(opcodes.get(i + 1)) != id {
+                        out.push_str("<!--/group-->");
+                    }
+                    last_text = false;
+                }
+            }
+            // A group that rendered nothing is its sentinel alone
+            Opcode::Seek((id, _)) if markers => {
+                check_comment(id)?;
+                if begin_id(opcodes.get(i + 1)) != Some(id.as_str()) {
+                    out.push_str("<!--/group-->");
+                    last_text = false;
+                }
+            }
+            // Listeners and sparse-update opcodes render nothing
+            _ => {}
+        }
+        i += 1;
+    }
+    if open_tag {
+        if !slot_names.is_empty() {
+            write_attr(&mut out, "data-slots", &slot_names.join(" "))?;
+        }
+        out.push('>');
+    }
+    Ok(out)
+}
+
+fn write_attr(out: &mut String, name: &str, value: &str) -> Result<()> {
+    check_attr(name)?;
+    let _ = write!(out, " {}=\"", name);
+    escape(value, true, out);
+    out.push('"');
+    Ok(())
+}
+
+fn check_attr(name: &str) -> Result<()> {
+    if !is_name(name) {
+        bail!("invalid attribute name {:?}", name);
+    }
+    Ok(())
+}
+
+/// Tag and attribute names are written as they are, so one that could end
+/// the tag or start another attribute (`img onerror=...`) is an error.
+fn is_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.chars().any(|c| {
+            c.is_whitespace() || c.is_control() || matches!(c, '"' | '\'' | '<' | '>' | '/' | '=')
+        })
+}
+
+/// A template ID goes in a comment, which `--` or `>` could end.
+fn check_comment(id: &str) -> Result<()> {
+    if id.contains("--") || id.contains('>') || id.ends_with('-') {
+        bail!("invalid template id {:?}", id);
+    }
+    Ok(())
+}
+
+impl Host {
+    /// Render `component` with `props` to HTML without mounting it. Its
+    /// `child` opcodes are rendered through `children`, and theirs through
+    /// the same list.
+    pub fn render_to_string(
+        &mut self,
+        component: &Loaded,
+        children: &[(&str, &Loaded)],
+        props: Vec<(String, PropValue)>,
+    ) -> Result<String> {
+        let children: HashMap<&str, &Loaded> = children.iter().copied().collect();
+        self.render_html(component, &children, props, false)
+    }
+
+    /// [`Host::render_to_string`] with hydration markers (see
+    /// [`opcodes_to_hydratable_html`]), for a page that will call the
+    /// runtime's `hydrate` on the element it's put in.
+    pub fn render_to_hydratable_string(
+        &mut self,
+        component: &Loaded,
+        children: &[(&str, &Loaded)],
+        props: Vec<(String, PropValue)>,
+    ) -> Result<String> {
+        let children: HashMap<&str, &Loaded> = children.iter().copied().collect();
+        self.render_html(component, &children, props, true)
+    }
+
+    fn render_html(
+        &mut self,
+        component: &Loaded,
+        children: &HashMap<&str, &Loaded>,
+        props: Vec<(String, PropValue)>,
+        markers: bool,
+    ) -> Result<String> {
+        let opcodes = self.render_detached(component, props)?;
+        serialize(&opcodes, markers, |name, props| match children.get(name) {
+            Some(child) => self.render_html(child, children, props, markers).map(Some),
+            None => Ok(None),
+        })
+    }
+}
@@ -0,0 +1,267 @@
+//! Components that play back a script of renders, so the runtime can be
+//! tested opcode by opcode without building a guest.
+
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+
+use wasm_dom_host::{ComponentId, Event, Host, Loaded, NodeId, Opcode, PropValue};
+use wit_component::{embed_component_metadata, ComponentEncoder, StringEncoding};
+use wit_parser::Resolve;
+
+/// A `pure-component` whose `render` returns `renders[n]`, where `n`
+/// counts the calls to `handle-event` so far (stopping at the last render).
+pub fn scripted(renders: &[Vec<Opcode>]) -> Vec<u8> {
+    let mut mem = Memory::default();
+    let lists: Vec<(u32, u32)> = renders.iter().map(|ops| mem.opcodes(ops)).collect();
+    let table = mem.alloc(8 * lists.len() as u32, 4);
+    for (i, (ptr, len)) in lists.into_iter().enumerate() {
+        mem.write(table + 8 * i as u32, &ptr.to_le_bytes());
+        mem.write(table + 8 * i as u32 + 4, &len.to_le_bytes());
+    }
+    let heap = mem.bytes.len();
+    let pages = heap / 65536 + 1;
+    let data: String = mem.bytes.iter().map(|b| format!("\\{:02x}", b)).collect();
+    let last = renders.len().saturating_sub(1);
+    let wat = format!(
+        r#"(module
+  (memory (export "memory") {pages})
+  (global $render (mut i32) (i32.const 0))
+  (global $heap (mut i32) (i32.const {heap}))
+  (data (i32.const 0) "{data}")
+  (func (export "cabi_realloc") (param i32 i32 i32 i32) (result i32)
+    (local $p i32)
+    (local.set $p (i32.and (i32.add (global.get $heap) (i32.const 7)) (i32.const -8)))
+    (global.set $heap (i32.add (local.get $p) (local.get 3)))
+    (if (i32.gt_u (global.get $heap) (i32.mul (memory.size) (i32.const 65536)))
+      (then (drop (memory.grow (i32.const 1)))))
+    (local.get $p))
+  (func $next
+    (if (i32.lt_u (global.get $render) (i32.const {last}))
+      (then (global.set $render (i32.add (global.get $render) (i32.const 1))))))
+  (func (export "wasm-components:dom/renderer@0.1.0#render") (param i32 i32) (result i32)
+    (i32.add (i32.const {table}) (i32.mul (global.get $render) (i32.const 8))))
+  (func (export "wasm-components:dom/renderer@0.1.0#handle-event") (param i32 i32)
+    (call $next))
+)"#
+    );
+    encode(&wat).expect("encode scripted component")
+}
+
+fn encode(wat: &str) -> anyhow::Result<Vec<u8>> {
+    let mut module = wat::parse_str(wat)?;
+    let mut resolve = Resolve::default();
+    let (pkg, _) = resolve.push_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/../../../wit"))?;
+    let world = resolve.select_world(&[pkg], Some("pure-component"))?;
+    embed_component_metadata(&mut module, &resolve, world, StringEncoding::UTF8)?;
+    ComponentEncoder::default()
+        .module(&module)?
+        .validate(true)
+        .encode()
+}
+
+/// Guest memory laid out as the canonical ABI lifts `list<opcode>`.
+#[derive(Default)]
+struct Memory {
+    bytes: Vec<u8>,
+    strings: HashMap<String, (u32, u32)>,
+}
+
+impl Memory {
+    fn alloc(&mut self, size: u32, align: u32) -> u32 {
+        let ptr = (self.bytes.len() as u32).div_ceil(align) * align;
+        self.bytes.resize((ptr + size) as usize, 0);
+        ptr
+    }
+
+    fn write(&mut self, ptr: u32, bytes: &[u8]) {
+        self.bytes[ptr as usize..ptr as usize + bytes.len()].copy_from_slice(bytes);
+    }
+
+    fn string(&mut self, s: &str) -> (u32, u32) {
+        if let Some(&string) = self.strings.get(s) {
+            return string;
+        }
+        let ptr = self.alloc(s.len() as u32, 1);
+        self.write(ptr, s.as_bytes());
+        self.strings.insert(s.into(), (ptr, s.len() as u32));
+        (ptr, s.len() as u32)
+    }
+
+    fn write_string(&mut self, at: u32, s: &str) {
+        let (ptr, len) = self.string(s);
+        self.write(at, &ptr.to_le_bytes());
+        self.write(at + 4, &len.to_le_bytes());
+    }
+
+    /// A `prop-value`: discriminant, then the payload at +8.
+    fn write_value(&mut self, at: u32, value: &PropValue) {
+        match value {
+            PropValue::Int(n) => {
+                self.write(at, &[0]);
+                self.write(at + 8, &n.to_le_bytes());
+            }
+            PropValue::Str(s) => {
+                self.write(at, &[1]);
+                self.write_string(at + 8, s);
+            }
+            PropValue::Boolean(b) => {
+                self.write(at, &[2]);
+                self.write(at + 8, &[*b as u8]);
+            }
+            PropValue::Float(f) => {
+                self.write(at, &[3]);
+                self.write(at + 8, &f.to_le_bytes());
+            }
+        }
+    }
+
+    /// 32 bytes an opcode: discriminant, then the payload at +8.
+    fn opcodes(&mut self, ops: &[Opcode]) -> (u32, u32) {
+        let base = self.alloc(32 * ops.len() as u32, 8);
+        for (i, op) in ops.iter().enumerate() {
+            let at = base + 32 * i as u32;
+            let (tag, payload) = case(op);
+            self.write(at, &[tag]);
+            match payload {
+                Payload::None => {}
+                Payload::Str(s) => self.write_string(at + 8, s),
+                Payload::Pair(a, b) => {
+                    self.write_string(at + 8, a);
+                    self.write_string(at + 16, b);
+                }
+                Payload::Value(name, value) => {
+                    self.write_string(at + 8, name);
+                    self.write_value(at + 16, value);
+                }
+                Payload::U32(n) => self.write(at + 8, &n.to_le_bytes()),
+                Payload::Seek(id, n) => {
+                    self.write_string(at + 8, id);
+                    self.write(at + 16, &n.to_le_bytes());
+                }
+            }
+        }
+        (base, ops.len() as u32)
+    }
+}
+
+enum Payload<'a> {
+    None,
+    Str(&'a str),
+    Pair(&'a str, &'a str),
+    Value(&'a str, &'a PropValue),
+    U32(u32),
+    Seek(&'a str, u32),
+}
+
+/// The case index of `op` in `wit/dom.wit`, and its payload.
+fn case(op: &Opcode) -> (u8, Payload<'_>) {
+    match op {
+        Opcode::Open(s) => (0, Payload::Str(s)),
+        Opcode::Close => (1, Payload::None),
+        Opcode::Attr((a, b)) => (2, Payload::Pair(a, b)),
+        Opcode::Text(s) => (3, Payload::Str(s)),
+        Opcode::Slot(s) => (4, Payload::Str(s)),
+        Opcode::Event((a, b)) => (5, Payload::Pair(a, b)),
+        Opcode::Child(s) => (6, Payload::Str(s)),
+        Opcode::Prop((name, value)) => (7, Payload::Value(name, value)),
+        Opcode::AttrSlot((a, b)) => (8, Payload::Pair(a, b)),
+        Opcode::Begin(s) => (9, Payload::Str(s)),
+        Opcode::End => (10, Payload::None),
+        Opcode::TypedAttrSlot((name, value)) => (11, Payload::Value(name, value)),
+        Opcode::Bind((a, b)) => (12, Payload::Pair(a, b)),
+        Opcode::BeginKeyed((a, b)) => (13, Payload::Pair(a, b)),
+        Opcode::Skip(n) => (14, Payload::U32(*n)),
+        Opcode::Seek((id, n)) => (15, Payload::Seek(id, *n)),
+    }
+}
+
+/// Opcodes from a compact notation, one per line: `<div`, `>`, `"text`,
+/// `{slot`, `@class=value` (attr), `$class=value` (attr-slot),
+/// `?hidden=true` (typed boolean), `!click=handler` (event),
+/// `&value=handler` (bind), `[id` and
+/// `[id#key` (begin, begin-keyed), `]` (end), `>>n` (skip), `^id@n` (seek),
+/// `+name` (child), `%name=value` (string prop).
+pub fn ops(script: &str) -> Vec<Opcode> {
+    let pair = |s: &str| {
+        let (a, b) = s.split_once('=').unwrap_or((s, ""));
+        (a.to_string(), b.to_string())
+    };
+    script
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let rest = &line[1..];
+            match line.as_bytes()[0] {
+                b'<' => Opcode::Open(rest.into()),
+                b'>' if rest.starts_with('>') => Opcode::Skip(rest[1..].parse().unwrap()),
+                b'>' => Opcode::Close,
+                b'"' => Opcode::Text(rest.into()),
+                b'{' => Opcode::Slot(rest.into()),
+                b'@' => Opcode::Attr(pair(rest)),
+                b'$' => Opcode::AttrSlot(pair(rest)),
+                b'?' => {
+                    let (name, value) = pair(rest);
+                    Opcode::TypedAttrSlot((name, PropValue::Boolean(value == "true")))
+                }
+                b'!' => Opcode::Event(pair(rest)),
+                b'&' => Opcode::Bind(pair(rest)),
+                b'[' => match rest.split_once('#') {
+                    Some((id, key)) => Opcode::BeginKeyed((id.into(), key.into())),
+                    None => Opcode::Begin(rest.into()),
+                },
+                b']' => Opcode::End,
+                b'^' => {
+                    let (id, n) = rest.split_once('@').unwrap();
+                    Opcode::Seek((id.into(), n.parse().unwrap()))
+                }
+                b'+' => Opcode::Child(rest.into()),
+                b'%' => {
+                    let (name, value) = pair(rest);
+                    Opcode::Prop((name, PropValue::Str(value)))
+                }
+                _ => panic!("unknown opcode line {:?}", line),
+            }
+        })
+        .collect()
+}
+
+/// Renders wrapped in `<main>` with a `next` listener, which [`next`]
+/// dispatches to move to the following render.
+pub fn script(renders: &[&str]) -> Vec<u8> {
+    let renders: Vec<Vec<Opcode>> = renders
+        .iter()
+        .map(|render| {
+            let mut list = vec![
+                Opcode::Open("main".into()),
+                Opcode::Event(("next".into(), "next".into())),
+            ];
+            list.extend(ops(render));
+            list.push(Opcode::Close);
+            list
+        })
+        .collect();
+    scripted(&renders)
+}
+
+pub fn load(host: &mut Host, renders: &[&str]) -> Loaded {
+    host.load(script(renders)).expect("load scripted component")
+}
+
+/// The `<main>` a [`script`] component renders.
+pub fn main(host: &Host, id: ComponentId) -> NodeId {
+    host.document().children(host.mount_point(id))[0]
+}
+
+/// Move a [`script`] component on to its next render.
+pub fn next(host: &mut Host, id: ComponentId) {
+    let main = main(host, id);
+    host.dispatch(main, Event::new("next"))
+        .expect("dispatch next");
+}
+
+/// The inner HTML of a [`script`] component's `<main>`, markers included.
+pub fn html(host: &Host, id: ComponentId) -> String {
+    host.document().inner_html(main(host, id))
+}
@@ -0,0 +1,395 @@
+mod common;
+
+use common::{html, load, main, next};
+use wasm_dom_host::{Event, Host, NodeId};
+
+/// The first node under the component's `<main>` whose text is `text`.
+fn text_node(host: &Host, root: NodeId, text: &str) -> NodeId {
+    let doc = host.document();
+    doc.find(root, |node| {
+        node.children().len() == 1 && doc.text_content(node.children()[0]) == text
+    })
+    .map(|node| doc.children(node)[0])
+    .unwrap_or_else(|| panic!("no {:?} in {}", text, doc.inner_html(root)))
+}
+
+#[test]
+fn build_and_update_parts() {
+    let mut host = Host::new().unwrap();
+    let app = load(
+        &mut host,
+        &[
+            r#"<p
+               @class=static
+               $title=one
+               ?hidden=false
+               "count:
+               {1
+               >"#,
+            r#"<p
+               @class=ignored
+               $title=two
+               ?hidden=true
+               "ignored
+               {2
+               >"#,
+        ],
+    );
+    let id = host.mount(&app, &[], Vec::new()).unwrap();
+    assert_eq!(
+        html(&host, id),
+        r#"<p class="static" title="one">count:1</p>"#
+    );
+    let slot = host
+        .document()
+        .children(host.document().children(main(&host, id))[0])[1];
+
+    next(&mut host, id);
+    // Static opcodes are only read on the first render
+    assert_eq!(
+        html(&host, id),
+        r#"<p class="static" title="two" hidden="">count:2</p>"#
+    );
+    assert_eq!(host.document().text(slot), Some("2"));
+}
+
+#[test]
+fn groups_grow_and_trim() {
+    let mut host = Host::new().unwrap();
+    let list = |items: &[&str]| {
+        let items: String = items
+            .iter()
+            .map(|item| format!("[li\n<li\n{{{}\n>\n]\n", item))
+            .collect();
+        format!("<ul\n{}>", items)
+    };
+    let app = load(
+        &mut host,
+        &[
+            &list(&["a", "b"]),
+            &list(&["a", "b", "c"]),
+            &list(&["x"]),
+            &list(&[]),
+            &list(&["y", "z"]),
+        ],
+    );
+    let id = host.mount(&app, &[], Vec::new()).unwrap();
+    assert_eq!(
+        html(&host, id),
+        "<ul><!--begin:li--><li>a</li><!--end--><!--begin:li--><li>b</li><!--end--><!--/group--></ul>"
+    );
+    let a = text_node(&host, main(&host, id), "a");
+
+    // A third instance is cloned from the first
+    next(&mut host, id);
+    assert_eq!(
+        html(&host, id),
+        "<ul><!--begin:li--><li>a</li><!--end--><!--begin:li--><li>b</li><!--end-->\
+         <!--begin:li--><li>c</li><!--end--><!--/group--></ul>"
+    );
+
+    // Instances are reused by position, the rest removed and freed
+    let c = text_node(&host, main(&host, id), "c");
+    next(&mut host, id);
+    assert_eq!(
+        html(&host, id),
+        "<ul><!--begin:li--><li>x</li><!--end--><!--/group--></ul>"
+    );
+    assert_eq!(host.document().text(a), Some("x"));
+    assert!(!host.document().contains(c));
+
+    next(&mut host, id);
+    assert_eq!(html(&host, id), "<ul><!--/group--></ul>");
+
+    next(&mut host, id);
+    assert_eq!(
+        html(&host, id),
+        "<ul><!--begin:li--><li>y</li><!--end--><!--begin:li--><li>z</li><!--end--><!--/group--></ul>"
+    );
+}
+
+#[test]
+fn keyed_instances_move() {
+    let mut host = Host::new().unwrap();
+    let list = |keys: &[&str]| {
+        let items: String = keys
+            .iter()
+            .map(|key| format!("[li#{}\n<li\n{{{}\n>\n]\n", key, key))
+            .collect();
+        format!("<ul\n{}>", items)
+    };
+    let app = load(
+        &mut host,
+        &[&list(&["a", "b", "c", "d"]), &list(&["d", "a", "c", "e"])],
+    );
+    let id = host.mount(&app, &[], Vec::new()).unwrap();
+    let root = main(&host, id);
+    let [a, b, c, d] = ["a", "b", "c", "d"].map(|key| text_node(&host, root, key));
+
+    next(&mut host, id);
+    let texts: Vec<String> = host
+        .document()
+        .descendants(root)
+        .into_iter()
+        .filter(|&node| host.document().node(node).tag() == Some("li"))
+        .map(|node| host.document().text_content(node))
+        .collect();
+    assert_eq!(texts, ["d", "a", "c", "e"]);
+    // Kept instances keep their nodes; the dropped one is freed
+    assert_eq!(text_node(&host, root, "a"), a);
+    assert_eq!(text_node(&host, root, "c"), c);
+    assert_eq!(text_node(&host, root, "d"), d);
+    assert!(!host.document().contains(b));
+}
+
+#[test]
+fn sparse_updates_skip_and_seek() {
+    let mut host = Host::new().unwrap();
+    let row = |text: &str| format!("[tr\n<tr\n{{{}\n>\n]\n", text);
+    let first: String = ["0", "1", "2", "3"].iter().map(|t| row(t)).collect();
+    // Instance 1 and 3 changed: seek to 1, skip 1, then skip the tail (none)
+    let second = format!("^tr@1\n{}>>1\n{}", row("one"), row("three"));
+    let app = load(
+        &mut host,
+        &[
+            &format!("<table\n{}>", first),
+            &format!("<table\n{}>", second),
+        ],
+    );
+    let id = host.mount(&app, &[], Vec::new()).unwrap();
+    next(&mut host, id);
+    let doc = host.document();
+    let texts: Vec<String> = doc
+        .descendants(main(&host, id))
+        .into_iter()
+        .filter(|&node| doc.node(node).tag() == Some("tr"))
+        .map(|node| doc.text_content(node))
+        .collect();
+    assert_eq!(texts, ["0", "one", "2", "three"]);
+}
+
+#[test]
+fn branches_switch_groups() {
+    let mut host = Host::new().unwrap();
+    let app = load(
+        &mut host,
+        &[
+            "\"before\n[yes\n<b\n\"yes\n>\n]\n\"after",
+            "\"before\n[no\n<i\n\"no\n>\n]\n\"after",
+            "\"before\n[yes\n<b\n\"yes\n>\n]\n\"after",
+        ],
+    );
+    let id = host.mount(&app, &[], Vec::new()).unwrap();
+    assert_eq!(
+        html(&host, id),
+        "before<!--begin:yes--><b>yes</b><!--end--><!--/group-->after"
+    );
+    // A group first seen on update goes after the last node passed
+    next(&mut host, id);
+    assert_eq!(
+        html(&host, id),
+        "before<!--begin:no--><i>no</i><!--end--><!--/group--><!--/group-->after"
+    );
+    next(&mut host, id);
+    assert_eq!(
+        html(&host, id),
+        "before<!--/group--><!--begin:yes--><b>yes</b><!--end--><!--/group-->after"
+    );
+}
+
+#[test]
+fn listeners_are_dropped_with_their_nodes() {
+    let mut host = Host::new().unwrap();
+    let app = load(&mut host, &["[li\n<button\n!click=remove\n\"x\n>\n]", ""]);
+    let id = host.mount(&app, &[], Vec::new()).unwrap();
+    let button = host
+        .document()
+        .find(main(&host, id), |node| node.tag() == Some("button"))
+        .unwrap();
+    // The click handler moves the script on to the empty render
+    host.dispatch(button, Event::new("click")).unwrap();
+    assert_eq!(html(&host, id), "<!--/group-->");
+    assert!(!host.document().contains(button));
+    assert!(host.dispatch(button, Event::new("click")).is_err());
+}
+
+#[test]
+fn same_template_id_in_two_parents() {
+    // Hand-written opcodes can repeat a template ID in another parent
+    // (`html!` gives each splice site its own): the first render must
+    // still build two groups
+    let mut host = Host::new().unwrap();
+    let badge = |text: &str| format!("[badge\n<b\n{{{}\n>\n]\n", text);
+    let render = |a: &str, b: &str| format!("<div\n{}>\n<div\n{}>", badge(a), badge(b));
+    let app = load(&mut host, &[&render("a", "b"), &render("c", "d")]);
+    let id = host.mount(&app, &[], Vec::new()).unwrap();
+    let group = |text: &str| format!("<!--begin:badge--><b>{}</b><!--end--><!--/group-->", text);
+    assert_eq!(
+        html(&host, id),
+        format!("<div>{}</div><div>{}</div>", group("a"), group("b"))
+    );
+    next(&mut host, id);
+    assert_eq!(
+        html(&host, id),
+        format!("<div>{}</div><div>{}</div>", group("c"), group("d"))
+    );
+}
+
+#[test]
+fn nested_groups_update_and_clone() {
+    let mut host = Host::new().unwrap();
+    let row = |cells: &[&str]| {
+        let cells: String = if cells.is_empty() {
+            "^cell@0\n".into()
+        } else {
+            cells
+                .iter()
+                .map(|cell| format!("[cell\n<td\n{{{}\n>\n]\n", cell))
+                .collect()
+        };
+        format!("[row\n<tr\n{}>\n]\n", cells)
+    };
+    let table = |rows: &[&[&str]]| {
+        let rows: String = rows.iter().map(|cells| row(cells)).collect();
+        format!("<table\n{}>", rows)
+    };
+    let app = load(
+        &mut host,
+        &[
+            &table(&[&["a1", "a2"]]),
+            &table(&[&["b1"], &["c1", "c2", "c3"]]),
+            &table(&[&[]]),
+        ],
+    );
+    let id = host.mount(&app, &[], Vec::new()).unwrap();
+    let cell = |text: &str| format!("<!--begin:cell--><td>{}</td><!--end-->", text);
+    let row = |cells: &[&str]| {
+        let cells: String = cells.iter().map(|text| cell(text)).collect();
+        format!("<!--begin:row--><tr>{}<!--/group--></tr><!--end-->", cells)
+    };
+    assert_eq!(
+        html(&host, id),
+        format!("<table>{}<!--/group--></table>", row(&["a1", "a2"]))
+    );
+    let a1 = text_node(&host, main(&host, id), "a1");
+
+    // The second row is cloned with its cells empty, then filled in
+    next(&mut host, id);
+    assert_eq!(
+        html(&host, id),
+        format!(
+            "<table>{}{}<!--/group--></table>",
+            row(&["b1"]),
+            row(&["c1", "c2", "c3"])
+        )
+    );
+    assert_eq!(host.document().text(a1), Some("b1"));
+
+    // A seek leaves the group in place, empty
+    next(&mut host, id);
+    assert_eq!(
+        html(&host, id),
+        format!("<table>{}<!--/group--></table>", row(&[]))
+    );
+    assert!(!host.document().contains(a1));
+}
+
+#[test]
+fn seek_marks_an_empty_group() {
+    let mut host = Host::new().unwrap();
+    let app = load(
+        &mut host,
+        &[
+            "<ul\n^li@0\n>\n\"after",
+            "<ul\n[li\n<li\n{a\n>\n]\n[li\n<li\n{b\n>\n]\n>\n\"after",
+        ],
+    );
+    let id = host.mount(&app, &[], Vec::new()).unwrap();
+    assert_eq!(html(&host, id), "<ul><!--/group--></ul>after");
+    let sentinel = host
+        .document()
+        .children(host.document().children(main(&host, id))[0])[0];
+
+    next(&mut host, id);
+    assert_eq!(
+        html(&host, id),
+        "<ul><!--begin:li--><li>a</li><!--end--><!--begin:li--><li>b</li><!--end--><!--/group--></ul>after"
+    );
+    let ul = host.document().children(main(&host, id))[0];
+    assert_eq!(host.document().children(ul).last(), Some(&sentinel));
+}
+
+#[test]
+fn children_mount_with_their_group_instance() {
+    let mut host = Host::new().unwrap();
+    let child = load(&mut host, &["<b\n\"child\n>"]);
+    let item = "[li\n<li\n+kid\n%name=x\n\"item\n>\n]\n";
+    let app = load(&mut host, &[item, &format!("{}{}", item, item), "^li@0"]);
+    let id = host.mount(&app, &[("kid", &child)], Vec::new()).unwrap();
+    let li = "<!--begin:li--><li><div><main><b>child</b></main></div>item</li><!--end-->";
+    assert_eq!(html(&host, id), format!("{}<!--/group-->", li));
+
+    // The clone's child is mounted by the update that fills it in
+    next(&mut host, id);
+    assert_eq!(html(&host, id), format!("{}{}<!--/group-->", li, li));
+    let kids: Vec<NodeId> = host
+        .document()
+        .descendants(main(&host, id))
+        .into_iter()
+        .filter(|&node| host.document().node(node).tag() == Some("b"))
+        .collect();
+    assert_eq!(kids.len(), 2);
+
+    // Removing the instances unmounts their children and drops their
+    // listeners
+    next(&mut host, id);
+    assert_eq!(html(&host, id), "<!--/group-->");
+    for kid in kids {
+        assert!(!host.document().contains(kid));
+    }
+
+    // The serializer renders them too (the script is on its last render)
+    let app = load(&mut host, &[item]);
+    let html = host
+        .render_to_string(&app, &[("kid", &child)], Vec::new())
+        .unwrap();
+    assert_eq!(
+        html,
+        "<main><li><div><main><b>child</b></main></div>item</li></main>"
+    );
+}
+
+#[test]
+fn an_event_can_unmount_the_child_it_started_in() {
+    let mut host = Host::new().unwrap();
+    let child = load(&mut host, &["<b\n\"child\n>"]);
+    let app = load(&mut host, &["[li\n+kid\n]", ""]);
+    let id = host.mount(&app, &[("kid", &child)], Vec::new()).unwrap();
+    let kid_main = host
+        .document()
+        .find(main(&host, id), |node| node.tag() == Some("div"))
+        .map(|div| host.document().children(div)[0])
+        .unwrap();
+    // `next` bubbles from the child's <main>, re-rendering the child, to
+    // the parent's, whose render removes the child's instance
+    host.dispatch(kid_main, Event::new("next")).unwrap();
+    assert_eq!(html(&host, id), "<!--/group-->");
+}
+
+#[test]
+fn a_renderer_cannot_take_a_binding() {
+    let mut host = Host::new().unwrap();
+    let app = load(&mut host, &["<input\n&value=input\n>"]);
+    let id = host.mount(&app, &[], Vec::new()).unwrap();
+    let input = host
+        .document()
+        .find(main(&host, id), |node| node.tag() == Some("input"))
+        .unwrap();
+    // Only `instances` has `handle-bind`
+    let err = host.dispatch(input, Event::new("input")).unwrap_err();
+    assert!(
+        err.to_string().contains("export `instances` to bind"),
+        "{}",
+        err
+    );
+}
@@ -0,0 +1,144 @@
+mod common;
+
+use common::{load, ops};
+use wasm_dom_host::{opcodes_to_html, opcodes_to_hydratable_html, Host, Opcode, PropValue};
+
+fn html(script: &str) -> String {
+    opcodes_to_html(&ops(script), |_, _| Ok(None)).unwrap()
+}
+
+fn hydratable(script: &str) -> String {
+    opcodes_to_hydratable_html(&ops(script), |_, _| Ok(None)).unwrap()
+}
+
+#[test]
+fn serializes_opcodes() {
+    let cases = [
+        ("<p\n\"hi\n>", "<p>hi</p>"),
+        // Text escapes markup; attribute values escape quotes
+        ("<p\n\"<b> & </b>\n>", "<p>&lt;b&gt; &amp; &lt;/b&gt;</p>"),
+        (
+            "<a\n@title=say \"hi\" <now>\n>",
+            r#"<a title="say &quot;hi&quot; <now>"></a>"#,
+        ),
+        // Void elements have no close tag
+        (
+            "<p\n<input\n@type=text\n>\n<br\n>\n>",
+            r#"<p><input type="text"><br></p>"#,
+        ),
+        (
+            "<object\n<param\n@name=a\n>\n>",
+            r#"<object><param name="a"></object>"#,
+        ),
+        ("<p\n$class=on\n{slot\n>", r#"<p class="on">slot</p>"#),
+        (
+            "<input\n?disabled=true\n?hidden=false\n>",
+            r#"<input disabled="">"#,
+        ),
+        // Listeners, binds and sparse opcodes render nothing
+        ("<button\n!click=go\n\"go\n>", "<button>go</button>"),
+        (
+            "<ul\n[li\n<li\n\"a\n>\n]\n[li\n<li\n\"b\n>\n]\n>",
+            "<ul><li>a</li><li>b</li></ul>",
+        ),
+    ];
+    for (script, expected) in cases {
+        assert_eq!(html(script), expected, "for {:?}", script);
+    }
+}
+
+#[test]
+fn serializes_hydration_markers() {
+    let cases = [
+        // Every text opcode gets its own text node
+        ("<p\n\"a\n{b\n\"c\n\"d\n>", "<p>a<!--slot-->b<!---->c<!---->d</p>"),
+        (
+            "<p\n@id=x\n$class=on\n?hidden=true\n>",
+            r#"<p id="x" class="on" hidden="" data-slots="class hidden"></p>"#,
+        ),
+        (
+            "<ul\n[li\n<li\n\"a\n>\n]\n[li\n<li\n\"b\n>\n]\n>",
+            "<ul><!--begin:li--><li>a</li><!--end--><!--begin:li--><li>b</li><!--end--><!--/group--></ul>",
+        ),
+        // Adjacent groups with different ids each get a sentinel
+        (
+            "[a\n\"x\n]\n[b\n\"y\n]",
+            "<!--begin:a-->x<!--end--><!--/group--><!--begin:b-->y<!--end--><!--/group-->",
+        ),
+    ];
+    for (script, expected) in cases {
+        assert_eq!(hydratable(script), expected, "for {:?}", script);
+    }
+}
+
+#[test]
+fn writes_the_hydration_fixtures() {
+    // The runtime's tests hydrate this markup, so the two agree on markers
+    let fixtures = include_str!("../../../../test/fixtures/hydration.txt");
+    let cases = fixtures.split("\n===\n").skip(1);
+    for case in cases {
+        let (script, expected) = case.split_once("\n---\n").unwrap();
+        let html = opcodes_to_hydratable_html(&ops(script), |name, _| {
+            Ok(Some(hydratable(&format!("<b\n\"{}\n>", name))))
+        })
+        .unwrap();
+        assert_eq!(html, expected.trim(), "for {:?}", script);
+    }
+}
+
+#[test]
+fn rejects_names_that_break_markup() {
+    let bad = [
+        Opcode::Open("img src=x onerror=alert(1)".into()),
+        Opcode::Open("p><script".into()),
+        Opcode::Open("".into()),
+        Opcode::Open("1p".into()),
+    ];
+    for op in bad {
+        assert!(
+            opcodes_to_html(std::slice::from_ref(&op), |_, _| Ok(None)).is_err(),
+            "{:?}",
+            op
+        );
+    }
+    let bad_attrs = [
+        Opcode::Attr(("onclick=\"x\" title".into(), "y".into())),
+        Opcode::AttrSlot(("a>b".into(), "y".into())),
+        Opcode::TypedAttrSlot(("a b".into(), PropValue::Boolean(false))),
+        Opcode::Attr(("".into(), "y".into())),
+    ];
+    for op in bad_attrs {
+        let list = [Opcode::Open("p".into()), op.clone()];
+        assert!(opcodes_to_html(&list, |_, _| Ok(None)).is_err(), "{:?}", op);
+    }
+    // Namespaced and hyphenated names are fine
+    assert_eq!(
+        html("<svg:use\n@xlink:href=#a\n@data-x=1\n>"),
+        r##"<svg:use xlink:href="#a" data-x="1"></svg:use>"##
+    );
+
+    let comment = [Opcode::Begin("x--><script>".into()), Opcode::End];
+    assert!(opcodes_to_hydratable_html(&comment, |_, _| Ok(None)).is_err());
+    assert!(opcodes_to_html(&comment, |_, _| Ok(None)).is_ok());
+}
+
+#[test]
+fn renders_children_through_the_registry() {
+    let mut host = Host::new().unwrap();
+    let child = load(&mut host, &["<b\n\"child\n>"]);
+    let parent = load(&mut host, &["<p\n\"parent\n>\n+kid\n%name=x\n+missing"]);
+    let html = host
+        .render_to_string(&parent, &[("kid", &child)], Vec::new())
+        .unwrap();
+    assert_eq!(
+        html,
+        "<main><p>parent</p><div><main><b>child</b></main></div></main>"
+    );
+    let html = host
+        .render_to_hydratable_string(&parent, &[("kid", &child)], Vec::new())
+        .unwrap();
+    assert_eq!(
+        html,
+        r#"<main><p>parent</p><div data-child="kid"><main><b>child</b></main></div></main>"#
+    );
+}
@@ -0,0 +1,183 @@
+//! Scriptable tests for components: mount one in a native
+//! [`Host`](wasm_dom_host::Host), query the DOM it renders, fire events at
+//! it and check the result.
+//!
+//! ```ignore
+//! let mut app = Harness::mount("test/rust-todo.component.wasm")?;
+//! let input = app.get("input");
+//! app.input(input, "Buy milk")?;
+//! app.keydown(input, "Enter")?;
+//! assert_eq!(app.text(app.get("p")), "1 items");
+//! app.click(app.get(r#"button.delete[data-id="0"]"#))?;
+//! assert_eq!(app.count("li"), 0);
+//! ```
+
+mod query;
+mod selector;
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use wasm_dom_host::{ComponentId, Host};
+
+pub use query::{find_all, find_by_text};
+pub use selector::Selector;
+pub use wasm_dom_host::{Document, Event, NodeId, PropValue, Property};
+
+pub struct Harness {
+    host: Host,
+    id: ComponentId,
+}
+
+impl Harness {
+    /// Mount the component at `path` with no props or children.
+    pub fn mount(path: impl AsRef<Path>) -> Result<Self> {
+        Self::mount_with(path, &[], Vec::new())
+    }
+
+    /// Mount the component at `path`, with `children` naming the
+    /// components for its `child` opcodes.
+    pub fn mount_with(
+        path: impl AsRef<Path>,
+        children: &[(&str, &Path)],
+        props: Vec<(String, PropValue)>,
+    ) -> Result<Self> {
+        let mut host = Host::new()?;
+        let component = host.load_file(path)?;
+        let children = children
+            .iter()
+            .map(|(name, path)| Ok((*name, host.load_file(path)?)))
+            .collect::<Result<Vec<_>>>()?;
+        let children: Vec<_> = children
+            .iter()
+            .map(|(name, child)| (*name, child))
+            .collect();
+        let id = host.mount(&component, &children, props)?;
+        Ok(Harness { host, id })
+    }
+
+    pub fn host(&self) -> &Host {
+        &self.host
+    }
+
+    pub fn host_mut(&mut self) -> &mut Host {
+        &mut self.host
+    }
+
+    pub fn document(&self) -> &Document {
+        self.host.document()
+    }
+
+    /// The element the component is mounted in.
+    pub fn root(&self) -> NodeId {
+        self.host.mount_point(self.id)
+    }
+
+    /// Elements under the root matching a CSS selector, in document order.
+    pub fn find_all(&self, selector: &str) -> Vec<NodeId> {
+        find_all(self.document(), self.root(), selector)
+    }
+
+    /// The first element matching a CSS selector.
+    pub fn find(&self, selector: &str) -> Option<NodeId> {
+        self.find_all(selector).into_iter().next()
+    }
+
+    /// [`Harness::find`], panicking with the HTML if nothing matches.
+    pub fn get(&self, selector: &str) -> NodeId {
+        self.find(selector)
+            .unwrap_or_else(|| panic!("no element matches {:?} in\n{}", selector, self.html()))
+    }
+
+    pub fn count(&self, selector: &str) -> usize {
+        self.find_all(selector).len()
+    }
+
+    /// The first element whose own text is `text` (see [`find_by_text`]).
+    pub fn find_by_text(&self, text: &str) -> Option<NodeId> {
+        find_by_text(self.document(), self.root(), text)
+    }
+
+    /// The first element with `data-id="id"`.
+    pub fn find_by_data_id(&self, id: &str) -> Option<NodeId> {
+        self.find(&format!("[data-id=\"{}\"]", id))
+    }
+
+    /// Dispatch `event` at `target`, which bubbles to its ancestors.
+    pub fn fire(&mut self, target: NodeId, event: Event) -> Result<()> {
+        self.host.dispatch(target, event)
+    }
+
+    pub fn click(&mut self, target: NodeId) -> Result<()> {
+        self.fire(target, Event::new("click"))
+    }
+
+    /// Set an input's value and fire `input`, as typing would.
+    pub fn input(&mut self, target: NodeId, value: &str) -> Result<()> {
+        self.host
+            .document_mut()
+            .set_property(target, "value", Property::Str(value.into()));
+        self.fire(target, Event::new("input"))
+    }
+
+    /// Set a checkbox's `checked` and fire `change`, as clicking it would.
+    pub fn check(&mut self, target: NodeId, checked: bool) -> Result<()> {
+        self.host
+            .document_mut()
+            .set_property(target, "checked", Property::Bool(checked));
+        self.fire(target, Event::new("change"))
+    }
+
+    /// Fire `keydown` with `key`, e.g. "Enter".
+    pub fn keydown(&mut self, target: NodeId, key: &str) -> Result<()> {
+        self.fire(target, Event::new("keydown").with("key", key))
+    }
+
+    /// The text content of `node`.
+    pub fn text(&self, node: NodeId) -> String {
+        self.document().text_content(node)
+    }
+
+    pub fn attribute(&self, node: NodeId, name: &str) -> Option<String> {
+        self.document().attribute(node, name).map(str::to_string)
+    }
+
+    /// The component's HTML, without the runtime's group markers.
+    pub fn html(&self) -> String {
+        strip_comments(self.document().inner_html(self.root()))
+    }
+
+    /// `node`'s HTML, without the runtime's group markers.
+    pub fn html_of(&self, node: NodeId) -> String {
+        strip_comments(self.document().to_html(node))
+    }
+}
+
+// Text escapes `<`, so `<!--` starts a comment outside attribute values
+fn strip_comments(mut html: String) -> String {
+    while let Some(start) = html.find("<!--") {
+        let end = html[start..]
+            .find("-->")
+            .map_or(html.len(), |e| start + e + 3);
+        html.replace_range(start..end, "");
+    }
+    html
+}
+
+/// Where `examples/build.sh` leaves an example component:
+/// `test/<name>.component.wasm` at the top of the repository. `None` if it
+/// hasn't been built, having said so on stderr, so a test using one can
+/// return early rather than fail in a tree without the built examples.
+pub fn example_component(name: &str) -> Option<PathBuf> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../../../test")
+        .join(format!("{}.component.wasm", name));
+    if !path.exists() {
+        eprintln!(
+            "skipping: {} is missing, run examples/build.sh first",
+            path.display()
+        );
+        return None;
+    }
+    Some(path)
+}
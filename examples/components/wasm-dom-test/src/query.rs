@@ -0,0 +1,32 @@
+//! DOM queries behind [`Harness`](crate::Harness)'s finders, usable on any
+//! [`Document`].
+
+use wasm_dom_host::{Document, NodeData, NodeId};
+
+use crate::Selector;
+
+/// Elements under `root` matching a CSS selector, in document order.
+pub fn find_all(doc: &Document, root: NodeId, selector: &str) -> Vec<NodeId> {
+    let selector = Selector::parse(selector);
+    doc.descendants(root)
+        .into_iter()
+        .filter(|&node| node != root && selector.matches(doc, root, node))
+        .collect()
+}
+
+/// The first element under `root` whose own text (its text children,
+/// trimmed) is `text`: for `<li>Buy milk<button>×</button></li>` that's the
+/// `<li>` for "Buy milk" and the `<button>` for "×".
+pub fn find_by_text(doc: &Document, root: NodeId, text: &str) -> Option<NodeId> {
+    doc.descendants(root).into_iter().find(|&node| {
+        let own: String = doc
+            .children(node)
+            .iter()
+            .filter_map(|&child| match &doc.node(child).data {
+                NodeData::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        doc.node(node).is_element() && own.trim() == text
+    })
+}
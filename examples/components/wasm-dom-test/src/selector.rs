@@ -0,0 +1,135 @@
+//! The subset of CSS selectors tests need: type, `.class`, `#id` and
+//! `[attr]`/`[attr="value"]` compounds, joined by descendant combinators,
+//! e.g. `tbody tr.danger a[data-id="3"]`.
+
+use wasm_dom_host::{Document, Node, NodeId};
+
+#[derive(Debug, Default)]
+struct Compound {
+    tag: Option<String>,
+    id: Option<String>,
+    classes: Vec<String>,
+    attrs: Vec<(String, Option<String>)>,
+}
+
+impl Compound {
+    fn matches(&self, node: &Node) -> bool {
+        let Some(tag) = node.tag() else {
+            return false;
+        };
+        if self.tag.as_deref().is_some_and(|t| t != "*" && t != tag) {
+            return false;
+        }
+        if self.id.is_some() && node.attribute("id") != self.id.as_deref() {
+            return false;
+        }
+        let classes = node.attribute("class").unwrap_or_default();
+        if !self
+            .classes
+            .iter()
+            .all(|c| classes.split_whitespace().any(|class| class == c))
+        {
+            return false;
+        }
+        self.attrs.iter().all(|(name, value)| match value {
+            Some(value) => node.attribute(name) == Some(value.as_str()),
+            None => node.attribute(name).is_some(),
+        })
+    }
+}
+
+/// A parsed selector: compounds from outermost to innermost.
+#[derive(Debug)]
+pub struct Selector(Vec<Compound>);
+
+impl Selector {
+    /// Panics on syntax it doesn't support, which is a bug in the test.
+    pub fn parse(selector: &str) -> Self {
+        let mut compounds = Vec::new();
+        let mut chars = selector.trim().chars().peekable();
+        let mut compound = Compound::default();
+        let ident = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+            let mut s = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_alphanumeric() || c == '-' || c == '_' || c == '*' {
+                    s.push(c);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            s
+        };
+        while let Some(&c) = chars.peek() {
+            match c {
+                '.' => {
+                    chars.next();
+                    compound.classes.push(ident(&mut chars));
+                }
+                '#' => {
+                    chars.next();
+                    compound.id = Some(ident(&mut chars));
+                }
+                '[' => {
+                    chars.next();
+                    let name = ident(&mut chars);
+                    let value = match chars.next() {
+                        Some(']') => None,
+                        Some('=') => {
+                            let quote = chars.next_if(|&c| c == '"' || c == '\'');
+                            let mut value = String::new();
+                            for c in chars.by_ref() {
+                                if Some(c) == quote || (quote.is_none() && c == ']') {
+                                    break;
+                                }
+                                value.push(c);
+                            }
+                            if quote.is_some() && chars.next() != Some(']') {
+                                panic!("unclosed [ in selector {:?}", selector);
+                            }
+                            Some(value)
+                        }
+                        _ => panic!("unsupported attribute selector in {:?}", selector),
+                    };
+                    compound.attrs.push((name, value));
+                }
+                c if c.is_whitespace() => {
+                    chars.next();
+                    if chars.peek().is_some_and(|c| !c.is_whitespace()) {
+                        compounds.push(std::mem::take(&mut compound));
+                    }
+                }
+                c if c.is_alphanumeric() || c == '*' => compound.tag = Some(ident(&mut chars)),
+                c => panic!("unsupported {:?} in selector {:?}", c, selector),
+            }
+        }
+        compounds.push(compound);
+        Selector(compounds)
+    }
+
+    /// Whether `node` matches, with ancestors matched up to (not
+    /// including) `root`.
+    pub fn matches(&self, doc: &Document, root: NodeId, node: NodeId) -> bool {
+        let Some((last, rest)) = self.0.split_last() else {
+            return false;
+        };
+        if !last.matches(doc.node(node)) {
+            return false;
+        }
+        let mut ancestor = doc.parent(node);
+        for compound in rest.iter().rev() {
+            loop {
+                match ancestor {
+                    Some(a) if a != root => {
+                        ancestor = doc.parent(a);
+                        if compound.matches(doc.node(a)) {
+                            break;
+                        }
+                    }
+                    _ => return false,
+                }
+            }
+        }
+        true
+    }
+}
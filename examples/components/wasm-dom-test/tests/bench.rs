@@ -0,0 +1,52 @@
+use wasm_dom_test::{example_component, Harness};
+
+// The bench with 1000 rows, or `None` if it hasn't been built
+fn mount() -> anyhow::Result<Option<Harness>> {
+    let Some(bench) = example_component("rust-bench") else {
+        return Ok(None);
+    };
+    let mut app = Harness::mount(bench)?;
+    app.click(app.get("#run"))?;
+    Ok(Some(app))
+}
+
+#[test]
+fn select_row() -> anyhow::Result<()> {
+    let Some(mut app) = mount()? else {
+        return Ok(());
+    };
+    assert_eq!(app.count("tbody tr"), 1000);
+    assert_eq!(app.count("tr.danger"), 0);
+
+    // The tbody's handler reads target.dataset.id of the clicked label
+    app.click(app.get(r#"a.lbl[data-id="5"]"#))?;
+    let selected = app.get("tr.danger");
+    assert_eq!(app.text(app.get("tr.danger td")), "5");
+
+    app.click(app.get(r#"a.lbl[data-id="7"]"#))?;
+    assert_eq!(app.count("tr.danger"), 1);
+    assert_eq!(app.text(app.get("tr.danger td")), "7");
+    assert_eq!(app.attribute(selected, "class").as_deref(), Some(""));
+
+    // Selecting it again clears the selection
+    app.click(app.get(r#"a.lbl[data-id="7"]"#))?;
+    assert_eq!(app.count("tr.danger"), 0);
+    Ok(())
+}
+
+#[test]
+fn remove_row() -> anyhow::Result<()> {
+    let Some(mut app) = mount()? else {
+        return Ok(());
+    };
+    app.click(app.get(r#"a.remove[data-id="3"]"#))?;
+    assert_eq!(app.count("tbody tr"), 999);
+    assert!(app.find_by_data_id("3").is_none());
+
+    // The icon inside the link: the handler reads target.parentElement.dataset.id
+    app.click(app.get(r#"a.remove[data-id="4"] span"#))?;
+    assert_eq!(app.count("tbody tr"), 998);
+    assert!(app.find_by_data_id("4").is_none());
+    assert_eq!(app.text(app.get("tbody tr td")), "1");
+    Ok(())
+}
@@ -0,0 +1,130 @@
+use wasm_dom_test::{find_all, find_by_text, Document, NodeId, Selector};
+
+/// Nodes from a tag and `name=value` attributes, e.g. `a class=lbl`.
+fn element(doc: &mut Document, parent: NodeId, spec: &str) -> NodeId {
+    let mut parts = spec.split(' ');
+    let node = doc.create_element(parts.next().unwrap());
+    for attr in parts {
+        let (name, value) = attr.split_once('=').unwrap_or((attr, ""));
+        doc.set_attribute(node, name, value);
+    }
+    doc.append_child(parent, node);
+    node
+}
+
+fn text(doc: &mut Document, parent: NodeId, text: &str) {
+    let node = doc.create_text(text);
+    doc.append_child(parent, node);
+}
+
+/// A root `<div id=app>` holding a table of three rows, the second
+/// selected, each with a label and a remove link with an icon:
+/// `<tr class=row><td>1</td><td><a class=lbl data-id=1>one</a></td>
+/// <td><a class="remove icon" data-id=1><span>×</span></a></td></tr>`.
+fn table() -> (Document, NodeId) {
+    let mut doc = Document::new();
+    let body = doc.body();
+    let app = element(&mut doc, body, "div id=app");
+    let table = element(&mut doc, app, "table class=table");
+    let tbody = element(&mut doc, table, "tbody");
+    for (id, label) in [(1, "one"), (2, "two"), (3, "three")] {
+        let class = if id == 2 { "row danger" } else { "row" };
+        let tr = doc.create_element("tr");
+        doc.set_attribute(tr, "class", class);
+        doc.append_child(tbody, tr);
+        let td = element(&mut doc, tr, "td");
+        text(&mut doc, td, &id.to_string());
+        let td = element(&mut doc, tr, "td");
+        let a = element(&mut doc, td, &format!("a class=lbl data-id={}", id));
+        text(&mut doc, a, label);
+        let td = element(&mut doc, tr, "td");
+        let a = element(&mut doc, td, &format!("a data-id={}", id));
+        doc.set_attribute(a, "class", "remove icon");
+        let span = element(&mut doc, a, "span");
+        text(&mut doc, span, "×");
+    }
+    (doc, app)
+}
+
+fn texts(doc: &Document, nodes: &[NodeId]) -> Vec<String> {
+    nodes.iter().map(|&node| doc.text_content(node)).collect()
+}
+
+#[test]
+fn selectors_match_compounds() {
+    let (doc, app) = table();
+    let count = |selector: &str| find_all(&doc, app, selector).len();
+    assert_eq!(count("tr"), 3);
+    assert_eq!(count("*"), 1 + 1 + 3 * 7);
+    assert_eq!(count(".row"), 3);
+    assert_eq!(count("tr.row.danger"), 1);
+    assert_eq!(count("td.row"), 0);
+    assert_eq!(count("a.icon.remove"), 3);
+    assert_eq!(count("[data-id]"), 6);
+    assert_eq!(count(r#"a[data-id="2"]"#), 2);
+    assert_eq!(count("a[data-id='2'][class=lbl]"), 1);
+    assert_eq!(count("a[data-id=4]"), 0);
+    // An attribute value is matched whole, a class by word
+    assert_eq!(count(r#"[class="remove"]"#), 0);
+    assert_eq!(count("#app"), 0);
+    assert_eq!(count("table#app"), 0);
+}
+
+#[test]
+fn selectors_match_descendants_under_the_root() {
+    let (doc, app) = table();
+    let all = |selector: &str| texts(&doc, &find_all(&doc, app, selector));
+    assert_eq!(all("tr.danger a.lbl"), ["two"]);
+    assert_eq!(all("tbody   td a.lbl"), ["one", "two", "three"]);
+    assert_eq!(all(r#"a.remove[data-id="3"] span"#), ["×"]);
+    assert_eq!(all("tr.danger td")[0], "2");
+    assert!(all("span a").is_empty());
+    // Ancestors are only looked for below the root
+    assert!(all("#app td").is_empty());
+    assert!(all("div td").is_empty());
+    let tbody = find_all(&doc, app, "tbody")[0];
+    assert_eq!(find_all(&doc, tbody, "table td").len(), 0);
+    assert_eq!(find_all(&doc, tbody, "tr td").len(), 9);
+}
+
+#[test]
+fn selector_matches_single_nodes() {
+    let (doc, app) = table();
+    let label = find_all(&doc, app, "a.lbl")[1];
+    assert!(Selector::parse("table tr.danger a").matches(&doc, app, label));
+    let tbody = find_all(&doc, app, "tbody")[0];
+    assert!(!Selector::parse("table tr.danger a").matches(&doc, tbody, label));
+    assert!(Selector::parse("tr.danger a").matches(&doc, tbody, label));
+    assert!(!Selector::parse("a.remove").matches(&doc, app, label));
+}
+
+#[test]
+#[should_panic(expected = "unsupported")]
+fn unsupported_selectors_panic() {
+    Selector::parse("ul > li");
+}
+
+#[test]
+fn finds_elements_by_their_own_text() {
+    let (mut doc, app) = table();
+    let found = find_by_text(&doc, app, "two").unwrap();
+    assert_eq!(doc.attribute(found, "class"), Some("lbl"));
+    assert_eq!(
+        find_by_text(&doc, app, "3"),
+        find_all(&doc, app, "td").get(6).copied()
+    );
+    // Text in child elements isn't the element's own
+    assert_eq!(
+        doc.node(find_by_text(&doc, app, "×").unwrap()).tag(),
+        Some("span")
+    );
+    assert_eq!(find_by_text(&doc, app, "one×"), None);
+
+    // Own text is joined from the text children and trimmed
+    let li = element(&mut doc, app, "li");
+    text(&mut doc, li, "  Buy ");
+    let button = element(&mut doc, li, "button");
+    text(&mut doc, button, "×");
+    text(&mut doc, li, "milk ");
+    assert_eq!(find_by_text(&doc, app, "Buy milk"), Some(li));
+}
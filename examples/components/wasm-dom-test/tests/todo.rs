@@ -0,0 +1,31 @@
+use wasm_dom_test::{example_component, Harness};
+
+#[test]
+fn add_and_delete() -> anyhow::Result<()> {
+    let Some(todo) = example_component("rust-todo") else {
+        return Ok(());
+    };
+    let mut app = Harness::mount(todo)?;
+    assert_eq!(app.text(app.get("p")), "0 items");
+
+    let input = app.get(".todo-input input");
+    app.input(input, "Buy milk")?;
+    app.keydown(input, "Enter")?;
+    app.input(input, "Walk the dog")?;
+    // Only Enter adds
+    app.keydown(input, "a")?;
+    app.keydown(input, "Enter")?;
+    assert_eq!(app.text(app.get("p")), "2 items");
+    assert_eq!(app.count("ul.todo-list li"), 2);
+    assert!(app.find_by_text("Buy milk").is_some());
+
+    // Ids count from 0; the handler reads target.dataset.id
+    app.click(app.get(r#"button.delete[data-id="0"]"#))?;
+    assert_eq!(app.text(app.get("p")), "1 items");
+    assert!(app.find_by_text("Buy milk").is_none());
+    assert_eq!(
+        app.html_of(app.get("ul.todo-list")),
+        r#"<ul class="todo-list"><li data-id="1">Walk the dog<button class="delete" data-id="1">×</button></li></ul>"#
+    );
+    Ok(())
+}
@@ -0,0 +1,143 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Error, Fields, Meta, Result};
+
+/// `#[derive(Bind)]` on a component's state struct. Its `#[bind]` fields,
+/// of type `String`, `bool`, `i32` or `f64`, can be the target of
+/// `bind:value={self.field}` / `bind:checked={self.field}`, whose handler is
+/// the field's name.
+///
+/// Generates `bind(&mut self, handler, value)`, which stores a value the
+/// instance's `handle-bind` delivers in the field its handler names, and the
+/// hidden `__bind_<field>` accessors `html!` reads a binding's value with,
+/// so binding a field without `#[bind]` fails to compile. `PropValue` must
+/// be in scope, as it is for `html!`.
+pub fn derive(input: DeriveInput) -> Result<TokenStream> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "Bind can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "Bind needs a struct with named fields",
+        ));
+    };
+
+    let mut names = Vec::new();
+    let mut handlers = Vec::new();
+    let mut accessors = Vec::new();
+    for field in &fields.named {
+        let mut bound = false;
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("bind"))
+        {
+            if !matches!(attr.meta, Meta::Path(_)) {
+                return Err(Error::new_spanned(&attr.meta, "expected `#[bind]`"));
+            }
+            bound = true;
+        }
+        if bound {
+            let name = field.ident.as_ref().expect("named field");
+            handlers.push(name.to_string());
+            accessors.push(format_ident!("__bind_{}", name));
+            names.push(name);
+        }
+    }
+    if names.is_empty() {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "Bind needs at least one `#[bind]` field",
+        ));
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        const _: () = {
+            // Converts a bound field to and from `prop-value`
+            trait Bound {
+                fn to_value(&self) -> PropValue;
+                fn set_value(&mut self, value: PropValue);
+            }
+            impl Bound for ::std::string::String {
+                fn to_value(&self) -> PropValue {
+                    PropValue::Str(self.clone())
+                }
+                fn set_value(&mut self, value: PropValue) {
+                    if let PropValue::Str(value) = value {
+                        *self = value;
+                    }
+                }
+            }
+            impl Bound for bool {
+                fn to_value(&self) -> PropValue {
+                    PropValue::Boolean(*self)
+                }
+                fn set_value(&mut self, value: PropValue) {
+                    if let PropValue::Boolean(value) = value {
+                        *self = value;
+                    }
+                }
+            }
+            impl Bound for i32 {
+                fn to_value(&self) -> PropValue {
+                    PropValue::Int(*self)
+                }
+                fn set_value(&mut self, value: PropValue) {
+                    match value {
+                        PropValue::Int(value) => *self = value,
+                        PropValue::Str(value) => {
+                            if let ::core::result::Result::Ok(value) = value.trim().parse() {
+                                *self = value;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            impl Bound for f64 {
+                fn to_value(&self) -> PropValue {
+                    PropValue::Float(*self)
+                }
+                fn set_value(&mut self, value: PropValue) {
+                    match value {
+                        PropValue::Float(value) => *self = value,
+                        PropValue::Str(value) => {
+                            if let ::core::result::Result::Ok(value) = value.trim().parse() {
+                                *self = value;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            impl #impl_generics #ident #ty_generics #where_clause {
+                #(
+                    #[doc(hidden)]
+                    pub fn #accessors(&self) -> PropValue {
+                        Bound::to_value(&self.#names)
+                    }
+                )*
+
+                /// Store a value delivered by the instance's `handle-bind` in
+                /// the `#[bind]` field its handler names. Returns `false` if
+                /// no field has that name.
+                pub fn bind(&mut self, handler: &str, value: PropValue) -> bool {
+                    match handler {
+                        #(#handlers => {
+                            Bound::set_value(&mut self.#names, value);
+                            true
+                        })*
+                        _ => false,
+                    }
+                }
+            }
+        };
+    })
+}
@@ -1,53 +1,596 @@
-use proc_macro2::TokenStream;
-use quote::quote;
-use syn::{Expr, Lit};
-
-use crate::parse::{Node, PropValue};
-
-pub fn generate(nodes: Vec<Node>) -> TokenStream {
-    let items: Vec<TokenStream> = nodes
-        .into_iter()
-        .map(|node| match node {
-            Node::Open(tag) => quote! { Opcode::Open(#tag.into()) },
-            Node::Close => quote! { Opcode::Close },
-            Node::Attr { name, value } => {
-                quote! { Opcode::Attr((#name.into(), #value.into())) }
-            }
-            Node::Event {
-                event_type,
-                handler,
-            } => {
-                quote! { Opcode::Event((#event_type.into(), #handler.into())) }
-            }
-            Node::Text(text) => quote! { Opcode::Text(#text.into()) },
-            Node::Slot(expr) => quote! { Opcode::Slot(#expr) },
-            Node::Child(name) => quote! { Opcode::Child(#name.into()) },
-            Node::DynAttr { name, value } => {
-                quote! { Opcode::AttrSlot((#name.into(), (#value).to_string())) }
-            }
-            Node::Begin(id) => quote! { Opcode::Begin(#id.into()) },
-            Node::End => quote! { Opcode::End },
-            Node::Prop { name, value } => match value {
-                PropValue::Str(s) => {
-                    quote! { Opcode::Prop((#name.into(), PropValue::Str(#s.into()))) }
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use syn::spanned::Spanned;
+use syn::{Error, Expr, Pat, Path, Result};
+
+use crate::parse::{ChildProp, Handler, Node, PropValue, View};
+
+/// Group sites a compact view can track; the top bit of the state word
+/// records that the full stream has been sent at least once.
+const MAX_SITES: u32 = 63;
+
+pub fn generate(view: View) -> Result<TokenStream> {
+    // Mixed-site span keeps the accumulator out of reach of user expressions.
+    let ops = Ident::new("__ops", Span::mixed_site());
+    let values = uses_values(&view.nodes).then(value_traits);
+    let capacity = view.nodes.iter().filter(|n| !n.is_group()).count();
+
+    let Some(state) = view.compact else {
+        if let Some(Node::For {
+            dirty: Some(dirty), ..
+        }) = find_node(&view.nodes, &|n| {
+            matches!(n, Node::For { dirty: Some(_), .. })
+        }) {
+            return Err(Error::new_spanned(
+                dirty,
+                "html!: `@dirty` only applies to the parts stream of an `@compact` view",
+            ));
+        }
+        let stmts = Gen::new(&ops, Stream::Full).nodes(&view.nodes);
+        return Ok(quote! {
+            {
+                #values
+                let mut #ops: ::std::vec::Vec<Opcode> = ::std::vec::Vec::with_capacity(#capacity);
+                #(#stmts)*
+                #ops
+            }
+        });
+    };
+
+    // A spliced view's groups are invisible to the site tracking below, so
+    // the parts stream could reach one whose template isn't cached yet.
+    if let Some(Node::Splice(expr)) = find_node(&view.nodes, &|n| matches!(n, Node::Splice(_))) {
+        return Err(Error::new_spanned(
+            expr,
+            "html!: `{..}` splices can't be tracked in an `@compact` view; drop `@compact`",
+        ));
+    }
+
+    let seen = Ident::new("__seen", Span::mixed_site());
+    let state_var = Ident::new("__state", Span::mixed_site());
+    let mut full = Gen::new(&ops, Stream::Tracked(&seen));
+    let full_stmts = full.nodes(&view.nodes);
+    if full.sites > MAX_SITES {
+        return Err(Error::new_spanned(
+            &state,
+            format!(
+                "html!: `@compact` supports at most {} `for`/`if`/`match` sites per view",
+                MAX_SITES
+            ),
+        ));
+    }
+    let parts_stmts = Gen::new(&ops, Stream::Parts).nodes(&view.nodes);
+    // Every site bit plus the "sent once" bit
+    let ready = ((1u64 << full.sites) - 1) | (1u64 << MAX_SITES);
+
+    // The parts stream skips static content, so bindings only used there
+    // (e.g. a loop variable feeding a child prop) go unread.
+    Ok(quote! {
+        {
+            #values
+            let #state_var: &mut u64 = #state;
+            #[allow(unused_variables)]
+            let #ops = if *#state_var & #ready == #ready {
+                let mut #ops: ::std::vec::Vec<Opcode> = ::std::vec::Vec::new();
+                #(#parts_stmts)*
+                #ops
+            } else {
+                let mut #seen: u64 = 1u64 << #MAX_SITES;
+                let mut #ops: ::std::vec::Vec<Opcode> = ::std::vec::Vec::with_capacity(#capacity);
+                #(#full_stmts)*
+                *#state_var |= #seen;
+                #ops
+            };
+            #ops
+        }
+    })
+}
+
+#[derive(Clone, Copy)]
+enum Stream<'a> {
+    /// Every opcode, as sent on first render.
+    Full,
+    /// Every opcode, recording each group site that produced an instance in
+    /// the given bitmask — the runtime has cached that site's template.
+    Tracked(&'a Ident),
+    /// Only group markers (`Begin`, `End`, `Seek`, ...) and parts, children
+    /// with their props and listeners included: what the runtime reads when
+    /// updating existing nodes or cloning cached templates.
+    Parts,
+}
+
+struct Gen<'a> {
+    ops: &'a Ident,
+    stream: Stream<'a>,
+    /// Group sites numbered so far, in source order.
+    sites: u32,
+    /// Splice sites numbered so far, in source order.
+    splices: u32,
+}
+
+impl<'a> Gen<'a> {
+    fn new(ops: &'a Ident, stream: Stream<'a>) -> Self {
+        Gen {
+            ops,
+            stream,
+            sites: 0,
+            splices: 0,
+        }
+    }
+
+    fn nodes(&mut self, nodes: &[Node]) -> Vec<TokenStream> {
+        nodes.iter().filter_map(|node| self.node(node)).collect()
+    }
+
+    fn node(&mut self, node: &Node) -> Option<TokenStream> {
+        let ops = self.ops;
+        match node {
+            Node::For {
+                pat,
+                iter,
+                template_id,
+                key,
+                dirty,
+                body,
+            } => match dirty {
+                Some(dirty) if matches!(self.stream, Stream::Parts) => {
+                    Some(self.sparse_for(pat, iter, template_id, dirty, body))
                 }
-                PropValue::Expr(expr) => {
-                    let val = match &expr {
-                        Expr::Lit(lit) => match &lit.lit {
-                            Lit::Int(i) => quote! { PropValue::Int(#i) },
-                            Lit::Float(f) => quote! { PropValue::Float(#f) },
-                            Lit::Bool(b) => quote! { PropValue::Boolean(#b.value) },
-                            _ => quote! { PropValue::Str((#expr).to_string()) },
-                        },
-                        _ => quote! { PropValue::Str((#expr).to_string()) },
+                _ => {
+                    let group = self.group(template_id, key.as_deref(), body);
+                    // The full stream covers every row; the dirty set is
+                    // still taken so it doesn't carry over
+                    let dirty = dirty.as_ref().map(|d| quote! { ::core::mem::drop(#d); });
+                    let Some(empty) = self.empty(template_id, body) else {
+                        return Some(quote! {
+                            #dirty
+                            for #pat in #iter #group
+                        });
                     };
-                    quote! { Opcode::Prop((#name.into(), #val)) }
+                    let start = Ident::new("__start", Span::mixed_site());
+                    Some(quote! {
+                        #dirty
+                        {
+                            let #start = #ops.len();
+                            for #pat in #iter #group
+                            if #ops.len() == #start {
+                                #empty
+                            }
+                        }
+                    })
                 }
             },
-        })
-        .collect();
+            Node::If(branches) => {
+                let empty: Vec<Option<TokenStream>> = branches
+                    .iter()
+                    .map(|branch| self.empty(&branch.template_id, &branch.body))
+                    .collect();
+                let arms: Vec<TokenStream> = branches
+                    .iter()
+                    .enumerate()
+                    .map(|(i, branch)| {
+                        let group = self.group(&branch.template_id, None, &branch.body);
+                        let (before, after) = (&empty[..i], &empty[i + 1..]);
+                        let block = quote! { { #(#before)* #group #(#after)* } };
+                        match &branch.cond {
+                            Some(cond) => quote! { if #cond #block },
+                            None => block,
+                        }
+                    })
+                    .collect();
+                // With no `else`, every branch can be left empty
+                let fallback = branches
+                    .last()
+                    .is_some_and(|branch| branch.cond.is_some())
+                    .then(|| quote! { else { #(#empty)* } });
+                Some(quote! { #(#arms)else* #fallback })
+            }
+            Node::Match { expr, arms } => {
+                let empty: Vec<Option<TokenStream>> = arms
+                    .iter()
+                    .map(|arm| self.empty(&arm.template_id, &arm.body))
+                    .collect();
+                let arms: Vec<TokenStream> = arms
+                    .iter()
+                    .enumerate()
+                    .map(|(i, arm)| {
+                        let pat = &arm.pat;
+                        let guard = arm.guard.as_ref().map(|g| quote! { if #g });
+                        let group = self.group(&arm.template_id, None, &arm.body);
+                        let (before, after) = (&empty[..i], &empty[i + 1..]);
+                        quote! { #pat #guard => { #(#before)* #group #(#after)* } }
+                    })
+                    .collect();
+                Some(quote! {
+                    match #expr {
+                        #(#arms)*
+                    }
+                })
+            }
+            // Each splice site gets its own template IDs, so a view spliced
+            // twice renders two groups rather than one split across parents
+            Node::Splice(expr) => {
+                let suffix = format!(".{}", self.splices);
+                self.splices += 1;
+                Some(quote! {
+                    #ops.extend(::std::iter::IntoIterator::into_iter(#expr).map(|op| match op {
+                        Opcode::Begin(id) => Opcode::Begin(id + #suffix),
+                        Opcode::BeginKeyed((id, key)) => Opcode::BeginKeyed((id + #suffix, key)),
+                        Opcode::Seek((id, n)) => Opcode::Seek((id + #suffix, n)),
+                        op => op,
+                    }));
+                })
+            }
+            // The value is a part; the binding itself is static
+            Node::Bind {
+                property,
+                state,
+                field,
+            } => {
+                // Fails to compile at the field if it isn't `#[bind]`
+                let accessor = format_ident!("__bind_{}", field, span = field.span());
+                let handler = field.to_string();
+                let value = quote! {
+                    #ops.push(Opcode::TypedAttrSlot((#property.into(), #state.#accessor())));
+                };
+                if matches!(self.stream, Stream::Parts) {
+                    return Some(value);
+                }
+                Some(quote! {
+                    #value
+                    #ops.push(Opcode::Bind((#property.into(), #handler.into())));
+                })
+            }
+            // A part too: the runtime re-renders the child when its props
+            // change. Its listeners are static, but a child in a group
+            // instance cloned from its template is mounted with them.
+            Node::Child {
+                name,
+                props_ty,
+                props,
+                events,
+            } => Some(child(ops, name, props_ty.as_deref(), props, events)),
+            Node::Slot(_)
+            | Node::DynAttr { .. }
+            | Node::ClassList { .. }
+            | Node::StyleList { .. } => {
+                let op = opcode(node);
+                Some(quote! { #ops.push(#op); })
+            }
+            _ if matches!(self.stream, Stream::Parts) => None,
+            _ => {
+                let op = opcode(node);
+                Some(quote! { #ops.push(#op); })
+            }
+        }
+    }
+
+    /// A `for` with `@dirty(indices)` in the parts stream: only the changed
+    /// instances, a `Seek` to the first and `Skip`s over the gaps and the
+    /// tail. The iterator must be an `ExactSizeIterator`; it's advanced with
+    /// `nth`, so slice iterators skip unchanged rows in O(1).
+    fn sparse_for(
+        &mut self,
+        pat: &Pat,
+        iter: &Expr,
+        template_id: &str,
+        dirty: &Expr,
+        body: &[Node],
+    ) -> TokenStream {
+        let ops = self.ops;
+        let items = Ident::new("__items", Span::mixed_site());
+        let len = Ident::new("__len", Span::mixed_site());
+        let indices = Ident::new("__dirty", Span::mixed_site());
+        let index = Ident::new("__index", Span::mixed_site());
+        let pos = Ident::new("__pos", Span::mixed_site());
+        let first = Ident::new("__first", Span::mixed_site());
+        let group = self.group(template_id, None, body);
+        quote! {
+            {
+                let mut #items = #iter;
+                let #len = ::core::iter::ExactSizeIterator::len(&#items);
+                let mut #indices: ::std::vec::Vec<usize> =
+                    ::core::iter::IntoIterator::into_iter(#dirty).collect();
+                #indices.sort_unstable();
+                #indices.dedup();
+                // Next index of both the iterator and the runtime's cursor
+                let mut #pos = 0usize;
+                let mut #first = true;
+                for #index in #indices {
+                    if #index >= #len {
+                        break;
+                    }
+                    let ::core::option::Option::Some(#pat) =
+                        ::core::iter::Iterator::nth(&mut #items, #index - #pos)
+                    else {
+                        break;
+                    };
+                    if #first {
+                        #ops.push(Opcode::Seek((#template_id.into(), #index as u32)));
+                        #first = false;
+                    } else if #index > #pos {
+                        #ops.push(Opcode::Skip((#index - #pos) as u32));
+                    }
+                    #group
+                    #pos = #index + 1;
+                }
+                if #first {
+                    #ops.push(Opcode::Seek((#template_id.into(), #len as u32)));
+                } else if #pos < #len {
+                    #ops.push(Opcode::Skip((#len - #pos) as u32));
+                }
+            }
+        }
+    }
+
+    /// `Seek((id, 0))` for a group site that rendered no instance, so the
+    /// runtime still makes its empty group in place, where a later render
+    /// or a clone of the instance the site is in finds it. The parts stream
+    /// leaves it out: by then the runtime has every group.
+    fn empty(&self, template_id: &str, body: &[Node]) -> Option<TokenStream> {
+        if body.is_empty() || matches!(self.stream, Stream::Parts) {
+            return None;
+        }
+        let ops = self.ops;
+        Some(quote! { #ops.push(Opcode::Seek((#template_id.into(), 0))); })
+    }
+
+    /// The block for one group instance: `Begin` (or `BeginKeyed`), the
+    /// body, `End`.
+    fn group(&mut self, template_id: &str, key: Option<&Expr>, body: &[Node]) -> TokenStream {
+        if body.is_empty() {
+            // Nothing to render: no group instance at all
+            return quote! { {} };
+        }
+        let ops = self.ops;
+        let site = self.sites;
+        self.sites += 1;
+        let track = match self.stream {
+            Stream::Tracked(seen) => Some(quote! { #seen |= 1u64 << #site; }),
+            _ => None,
+        };
+        let begin = match key {
+            Some(key) => quote! { Opcode::BeginKeyed((#template_id.into(), (#key).to_string())) },
+            None => opcode(&Node::Begin(template_id.to_string())),
+        };
+        let end = opcode(&Node::End);
+        let body = self.nodes(body);
+        quote! {
+            {
+                #track
+                #ops.push(#begin);
+                #(#body)*
+                #ops.push(#end);
+            }
+        }
+    }
+}
 
+/// `Child`, its `Prop`s and its `Event`s. With a props type, each value goes
+/// through the type's `__prop_<name>` converter, so unknown names and
+/// mistyped values are compile errors, and `__require_props` rejects missing
+/// required props.
+fn child(
+    ops: &Ident,
+    name: &str,
+    props_ty: Option<&Path>,
+    props: &[ChildProp],
+    events: &[Node],
+) -> TokenStream {
+    let check = props_ty.map(|ty| {
+        let names = props.iter().map(|prop| prop.name.to_string());
+        quote_spanned! {ty.span()=>
+            const _: () = <#ty>::__require_props(&[#(#names),*]);
+        }
+    });
+    let props = props.iter().map(|prop| {
+        let prop_name = prop.name.to_string();
+        let value = match (props_ty, &prop.value) {
+            (Some(ty), value) => {
+                let converter = format_ident!("__prop_{}", prop.name, span = prop.name.span());
+                let value = match value {
+                    PropValue::Str(s) => syn::LitStr::new(s, prop.name.span()).to_token_stream(),
+                    PropValue::Expr(expr) => quote! { #expr },
+                };
+                quote_spanned! {prop.name.span()=> <#ty>::#converter(#value) }
+            }
+            (None, PropValue::Str(s)) => quote! { PropValue::Str(#s.into()) },
+            (None, PropValue::Expr(expr)) => quote! { (&__Wrap(&(#expr))).__prop_value() },
+        };
+        quote! { #ops.push(Opcode::Prop((#prop_name.into(), #value))); }
+    });
+    let events = events.iter().map(opcode);
     quote! {
-        vec![#(#items),*]
+        #check
+        #ops.push(Opcode::Child(#name.into()));
+        #(#props)*
+        #(#ops.push(#events);)*
+    }
+}
+
+fn opcode(node: &Node) -> TokenStream {
+    match node {
+        Node::Open(tag) => quote! { Opcode::Open(#tag.into()) },
+        Node::Close => quote! { Opcode::Close },
+        Node::Attr { name, value } => {
+            quote! { Opcode::Attr((#name.into(), #value.into())) }
+        }
+        Node::Event {
+            event_type,
+            handler,
+        } => {
+            let handler = match handler {
+                Handler::Name(name) => quote! { #name.into() },
+                Handler::Expr(expr) => quote! { ::std::string::String::from(#expr) },
+            };
+            quote! { Opcode::Event((#event_type.into(), #handler)) }
+        }
+        Node::Text(text) => quote! { Opcode::Text(#text.into()) },
+        Node::Slot(expr) => quote! { Opcode::Slot((#expr).to_string()) },
+        Node::DynAttr { name, value } => {
+            quote! { (&__Wrap(&(#value))).__attr_slot(#name.into()) }
+        }
+        Node::ClassList { base, toggles } => {
+            let class = Ident::new("__class", Span::mixed_site());
+            let base = match base {
+                Some(base) => quote! { (#base).to_string() },
+                None => quote! { ::std::string::String::new() },
+            };
+            let toggles = toggles.iter().map(|(name, cond)| {
+                quote! {
+                    if #cond {
+                        if !#class.is_empty() {
+                            #class.push(' ');
+                        }
+                        #class.push_str(#name);
+                    }
+                }
+            });
+            quote! {
+                Opcode::AttrSlot(("class".into(), {
+                    let mut #class = #base;
+                    #(#toggles)*
+                    #class
+                }))
+            }
+        }
+        Node::StyleList { base, props } => {
+            let style = Ident::new("__style", Span::mixed_site());
+            let base = match base {
+                Some(base) => quote! {
+                    let mut #style = (#base).to_string();
+                    #style.truncate(#style.trim_end().trim_end_matches(';').len());
+                },
+                None => quote! { let mut #style = ::std::string::String::new(); },
+            };
+            let props = props.iter().map(|(name, value)| {
+                let decl = format!("{}: ", name);
+                quote! {
+                    if !#style.is_empty() {
+                        #style.push_str("; ");
+                    }
+                    #style.push_str(#decl);
+                    #style.push_str(&(#value).to_string());
+                }
+            });
+            quote! {
+                Opcode::AttrSlot(("style".into(), {
+                    #base
+                    #(#props)*
+                    #style
+                }))
+            }
+        }
+        Node::Begin(id) => quote! { Opcode::Begin(#id.into()) },
+        Node::End => quote! { Opcode::End },
+        Node::Child { .. }
+        | Node::Bind { .. }
+        | Node::Key(_)
+        | Node::Splice(_)
+        | Node::For { .. }
+        | Node::If(_)
+        | Node::Match { .. } => {
+            unreachable!("children, bindings, keys, splices and control flow are lowered earlier")
+        }
     }
 }
+
+/// `value={x}` as a `prop-value`: `x`'s own type if it has one (`i32`,
+/// `f64`, `bool`, or a reference to one), else its `Display` string. Method
+/// lookup tries `__Typed` on `__Wrap` before autoref reaches `__Shown` on
+/// `&__Wrap`, so the choice follows the type, not the syntax. The traits
+/// are local to each `html!` block, since `PropValue` and `Opcode` are the
+/// component's own bindings.
+fn value_traits() -> TokenStream {
+    quote! {
+        #[allow(dead_code)]
+        struct __Wrap<'a, T: ?::core::marker::Sized>(&'a T);
+        #[allow(dead_code)]
+        trait __Prim {
+            fn __value(&self) -> PropValue;
+        }
+        impl __Prim for i32 {
+            fn __value(&self) -> PropValue {
+                PropValue::Int(*self)
+            }
+        }
+        impl __Prim for f64 {
+            fn __value(&self) -> PropValue {
+                PropValue::Float(*self)
+            }
+        }
+        impl __Prim for bool {
+            fn __value(&self) -> PropValue {
+                PropValue::Boolean(*self)
+            }
+        }
+        impl<T: __Prim + ?::core::marker::Sized> __Prim for &T {
+            fn __value(&self) -> PropValue {
+                __Prim::__value(*self)
+            }
+        }
+        #[allow(dead_code)]
+        trait __Typed {
+            fn __prop_value(&self) -> PropValue;
+            fn __attr_slot(&self, name: ::std::string::String) -> Opcode;
+        }
+        impl<T: __Prim + ?::core::marker::Sized> __Typed for __Wrap<'_, T> {
+            fn __prop_value(&self) -> PropValue {
+                __Prim::__value(self.0)
+            }
+            fn __attr_slot(&self, name: ::std::string::String) -> Opcode {
+                Opcode::TypedAttrSlot((name, __Prim::__value(self.0)))
+            }
+        }
+        #[allow(dead_code)]
+        trait __Shown {
+            fn __prop_value(&self) -> PropValue;
+            fn __attr_slot(&self, name: ::std::string::String) -> Opcode;
+        }
+        impl<T: ::core::fmt::Display + ?::core::marker::Sized> __Shown for &__Wrap<'_, T> {
+            fn __prop_value(&self) -> PropValue {
+                PropValue::Str(self.0.to_string())
+            }
+            fn __attr_slot(&self, name: ::std::string::String) -> Opcode {
+                Opcode::AttrSlot((name, self.0.to_string()))
+            }
+        }
+    }
+}
+
+/// The first node in `nodes` or in the bodies of their groups, at any
+/// depth, that matches `pred`.
+fn find_node<'n>(nodes: &'n [Node], pred: &impl Fn(&Node) -> bool) -> Option<&'n Node> {
+    nodes.iter().find_map(|node| {
+        if pred(node) {
+            return Some(node);
+        }
+        match node {
+            Node::For { body, .. } => find_node(body, pred),
+            Node::If(branches) => branches
+                .iter()
+                .find_map(|branch| find_node(&branch.body, pred)),
+            Node::Match { arms, .. } => arms.iter().find_map(|arm| find_node(&arm.body, pred)),
+            _ => None,
+        }
+    })
+}
+
+/// Whether `nodes` send values through [`value_traits`]: dynamic
+/// attributes, and child props with no props type to convert them.
+fn uses_values(nodes: &[Node]) -> bool {
+    nodes.iter().any(|node| match node {
+        Node::DynAttr { .. } => true,
+        Node::Child {
+            props_ty: None,
+            props,
+            ..
+        } => props
+            .iter()
+            .any(|prop| matches!(prop.value, PropValue::Expr(_))),
+        Node::For { body, .. } => uses_values(body),
+        Node::If(branches) => branches.iter().any(|branch| uses_values(&branch.body)),
+        Node::Match { arms, .. } => arms.iter().any(|arm| uses_values(&arm.body)),
+        _ => false,
+    })
+}
@@ -0,0 +1,84 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Error, Expr, ExprLit, Fields, Lit, Meta, Result};
+
+use crate::parse::pascal_to_kebab;
+
+/// `#[derive(Handlers)]` on a unit-variant enum. Each variant maps to the
+/// handler string sent through `renderer::handle-event`: its snake_case name
+/// (`ToggleDark` → `"toggle_dark"`), or `#[handler = "..."]` to override.
+pub fn derive(input: DeriveInput) -> Result<TokenStream> {
+    let Data::Enum(data) = &input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "Handlers can only be derived for enums",
+        ));
+    };
+
+    let mut variants = Vec::new();
+    let mut names = Vec::new();
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(Error::new_spanned(
+                variant,
+                "Handlers variants cannot carry data; read event details with `event_query`",
+            ));
+        }
+        let mut name = pascal_to_kebab(&variant.ident.to_string()).replace('-', "_");
+        for attr in &variant.attrs {
+            if !attr.path().is_ident("handler") {
+                continue;
+            }
+            match &attr.meta {
+                Meta::NameValue(nv) => match &nv.value {
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Str(s), ..
+                    }) => name = s.value(),
+                    value => {
+                        return Err(Error::new_spanned(value, "expected a string literal"));
+                    }
+                },
+                meta => {
+                    return Err(Error::new_spanned(meta, "expected `#[handler = \"name\"]`"));
+                }
+            }
+        }
+        if names.contains(&name) {
+            return Err(Error::new_spanned(
+                variant,
+                format!("duplicate handler name \"{}\"", name),
+            ));
+        }
+        variants.push(&variant.ident);
+        names.push(name);
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// The handler string for this message.
+            pub fn handler(&self) -> &'static str {
+                match self {
+                    #(#ident::#variants => #names,)*
+                }
+            }
+
+            /// The message for a handler string received by `handle_event`.
+            pub fn from_handler(handler: &str) -> ::core::option::Option<Self> {
+                match handler {
+                    #(#names => ::core::option::Option::Some(#ident::#variants),)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+        }
+
+        impl #impl_generics ::core::convert::From<#ident #ty_generics>
+            for ::std::string::String #where_clause
+        {
+            fn from(msg: #ident #ty_generics) -> Self {
+                msg.handler().into()
+            }
+        }
+    })
+}
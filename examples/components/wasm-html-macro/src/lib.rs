@@ -1,10 +1,51 @@
+mod bind;
 mod codegen;
+mod handlers;
 mod parse;
+mod props;
+mod text;
 
 use proc_macro::TokenStream;
 
 #[proc_macro]
 pub fn html(input: TokenStream) -> TokenStream {
-    let nodes = parse::parse(input.into());
-    codegen::generate(nodes).into()
+    let span = proc_macro::Span::call_site();
+    let call_site = format!(
+        "{}:{}:{}:{}",
+        std::env::var("CARGO_PKG_NAME").unwrap_or_default(),
+        span.file(),
+        span.line(),
+        span.column()
+    );
+    match parse::parse(input.into(), &call_site).and_then(codegen::generate) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+#[proc_macro_derive(Handlers, attributes(handler))]
+pub fn derive_handlers(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    match handlers::derive(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+#[proc_macro_derive(Bind, attributes(bind))]
+pub fn derive_bind(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    match bind::derive(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+#[proc_macro_derive(Props, attributes(prop))]
+pub fn derive_props(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    match props::derive(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
 }
@@ -1,186 +1,923 @@
-use proc_macro2::{Delimiter, TokenStream, TokenTree};
-use syn::{Expr, LitStr};
+use proc_macro2::{Delimiter, Group, Ident, Spacing, Span, TokenStream, TokenTree};
+use syn::parse::{Parse, Parser};
+use syn::{
+    Error, Expr, ExprField, ExprLit, ExprRange, Lit, LitStr, Member, Pat, Path, RangeLimits, Result,
+};
+
+use crate::text::text_nodes;
 
 pub enum PropValue {
     Str(String),
     Expr(Expr),
 }
 
+pub struct ChildProp {
+    pub name: Ident,
+    pub value: PropValue,
+}
+
+/// An `on:event` handler: a literal handler name, or an expression that
+/// converts into one (e.g. a `#[derive(Handlers)]` message enum).
+pub enum Handler {
+    Name(String),
+    Expr(Expr),
+}
+
 pub enum Node {
     Open(String),
     Close,
-    Attr { name: String, value: String },
-    Event { event_type: String, handler: String },
+    Attr {
+        name: String,
+        value: String,
+    },
+    Event {
+        event_type: String,
+        handler: Handler,
+    },
     Text(String),
     Slot(Expr),
-    Child(String),
-    Prop { name: String, value: PropValue },
-    DynAttr { name: String, value: Expr },
+    /// `<ZigChild initial={5} />`. With a props type,
+    /// `<RustChild: CounterProps label="Rust" />`, the props are checked
+    /// against that `#[derive(Props)]` struct at compile time. `on:name`
+    /// handles events the child emits; those are kept as `Event` nodes.
+    Child {
+        name: String,
+        props_ty: Option<Box<Path>>,
+        props: Vec<ChildProp>,
+        events: Vec<Node>,
+    },
+    DynAttr {
+        name: String,
+        value: Expr,
+    },
+    /// `{..expr}`: the opcodes of another view, appended in place.
+    Splice(Expr),
+    /// `bind:value={self.draft}`: the property's value from a `#[bind]`
+    /// field of the component's state (`self.__bind_draft()`), kept in sync
+    /// through the instance's `handle-bind` with the field's name as the
+    /// handler.
+    Bind {
+        property: String,
+        state: Ident,
+        field: Ident,
+    },
+    /// `key={expr}` on the root element of a `for` body; lifted into
+    /// `For::key` by the parser.
+    Key(Expr),
+    /// `class:name={cond}` toggles merged with the element's `class`
+    /// attribute into one dynamic `class` value.
+    ClassList {
+        base: Option<Expr>,
+        toggles: Vec<(String, Expr)>,
+    },
+    /// `style:prop={value}` declarations appended to the element's
+    /// `style` attribute as one dynamic `style` value.
+    StyleList {
+        base: Option<Expr>,
+        props: Vec<(String, Expr)>,
+    },
     Begin(String),
     End,
+    For {
+        pat: Box<Pat>,
+        iter: Box<Expr>,
+        template_id: String,
+        /// Identifies each instance across renders, so the runtime moves
+        /// reordered rows instead of rewriting them in place.
+        key: Option<Box<Expr>>,
+        /// `@dirty(indices)`: the instances that changed since the last
+        /// render. The parts stream sends only those, with `seek`/`skip`.
+        dirty: Option<Box<Expr>>,
+        body: Vec<Node>,
+    },
+    If(Vec<Branch>),
+    Match {
+        expr: Box<Expr>,
+        arms: Vec<Arm>,
+    },
+}
+
+/// One arm of an `if` / `else if` / `else` chain. Each arm renders as its
+/// own zero-or-one instance group, so the runtime adds and removes the
+/// subtree as the condition changes.
+pub struct Branch {
+    pub cond: Option<Expr>,
+    pub template_id: String,
+    pub body: Vec<Node>,
+}
+
+/// One arm of a `match`. Like `if` branches, each arm gets its own template
+/// ID so the runtime caches a separate `<template>` per arm.
+pub struct Arm {
+    pub pat: Pat,
+    pub guard: Option<Expr>,
+    pub template_id: String,
+    pub body: Vec<Node>,
+}
+
+impl Node {
+    pub fn is_group(&self) -> bool {
+        matches!(self, Node::For { .. } | Node::If(_) | Node::Match { .. })
+    }
+}
+
+/// A parsed `html!` invocation.
+pub struct View {
+    /// `@compact(state)`: a `&mut u64` the component keeps between renders.
+    /// Once every group site has been sent in full, re-renders emit only
+    /// the dynamic parts and lean on the runtime's template cache.
+    pub compact: Option<Expr>,
+    pub nodes: Vec<Node>,
+}
+
+/// Parse an `html!` invocation. `call_site` names the crate and source
+/// location of the call; it goes into every template ID, so two calls with
+/// the same markup don't share a cached template.
+pub fn parse(input: TokenStream, call_site: &str) -> Result<View> {
+    let mut tokens: Vec<TokenTree> = input.into_iter().collect();
+    let compact = parse_compact(&mut tokens)?;
+    let mut sites = Sites { next: 0, call_site };
+    let nodes = parse_nodes(tokens.into_iter().collect(), &mut sites)?;
+    reject_keys(&nodes)?;
+    Ok(View { compact, nodes })
+}
+
+/// The group sites of one `html!` call, numbered in source order.
+struct Sites<'a> {
+    next: usize,
+    call_site: &'a str,
+}
+
+/// Strip a leading `@compact(expr)` directive, returning its expression.
+fn parse_compact(tokens: &mut Vec<TokenTree>) -> Result<Option<Expr>> {
+    match tokens.first() {
+        Some(TokenTree::Punct(p)) if p.as_char() == '@' => {}
+        _ => return Ok(None),
+    }
+    match tokens.get(1) {
+        Some(TokenTree::Ident(id)) if id == "compact" => {}
+        Some(tt) => {
+            return Err(Error::new(
+                tt.span(),
+                "html!: unknown directive, expected `@compact(...)`",
+            ))
+        }
+        None => {
+            return Err(Error::new(
+                tokens[0].span(),
+                "html!: expected `compact` after `@`",
+            ))
+        }
+    }
+    let expr = match tokens.get(2) {
+        Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis => parse_nonempty(
+            Expr::parse,
+            g.stream(),
+            g.span(),
+            "html!: `@compact` expects a `&mut u64` state expression",
+        )?,
+        _ => {
+            return Err(Error::new(
+                tokens[1].span(),
+                "html!: expected `(state)` after `@compact`",
+            ))
+        }
+    };
+    tokens.drain(..3);
+    Ok(Some(expr))
 }
 
-pub fn parse(input: TokenStream) -> Vec<Node> {
+fn parse_nodes(input: TokenStream, sites: &mut Sites<'_>) -> Result<Vec<Node>> {
     let tokens: Vec<TokenTree> = input.into_iter().collect();
     let mut nodes = Vec::new();
     let mut pos = 0;
+    // Open elements awaiting their close tag, with the span of their `<`
+    let mut open_tags: Vec<(String, Span)> = Vec::new();
 
     while pos < tokens.len() {
         match &tokens[pos] {
             // `<` — start of an open or close tag
             TokenTree::Punct(p) if p.as_char() == '<' => {
+                let open_span = p.span();
                 pos += 1;
+                // `<>` — fragment: groups its children, emits nothing itself
+                if let TokenTree::Punct(p2) = token(&tokens, pos)? {
+                    if p2.as_char() == '>' {
+                        pos += 1;
+                        open_tags.push((String::new(), open_span));
+                        continue;
+                    }
+                }
                 // Check for `</tag>`
-                if pos < tokens.len() {
-                    if let TokenTree::Punct(p2) = &tokens[pos] {
-                        if p2.as_char() == '/' {
-                            pos += 1;
-                            // consume tag name (may be hyphenated: e.g. `my-component`)
-                            let _tag = consume_tag_name(&tokens, &mut pos);
-                            // consume `>`
-                            expect_punct(&tokens, &mut pos, '>');
-                            nodes.push(Node::Close);
-                            continue;
+                if let TokenTree::Punct(p2) = token(&tokens, pos)? {
+                    if p2.as_char() == '/' {
+                        pos += 1;
+                        // `</>` closes a fragment
+                        if let TokenTree::Punct(p3) = token(&tokens, pos)? {
+                            if p3.as_char() == '>' {
+                                match open_tags.pop() {
+                                    Some((open, _)) if open.is_empty() => {}
+                                    Some((open, _)) => {
+                                        return Err(Error::new(
+                                            p3.span(),
+                                            format!(
+                                                "html!: mismatched closing tag </>, expected </{}>",
+                                                open
+                                            ),
+                                        ));
+                                    }
+                                    None => {
+                                        return Err(Error::new(
+                                            p3.span(),
+                                            "html!: unexpected closing tag </>",
+                                        ));
+                                    }
+                                }
+                                pos += 1;
+                                continue;
+                            }
                         }
+                        // consume tag name (may be hyphenated: e.g. `my-component`)
+                        let tag_span = token(&tokens, pos)?.span();
+                        let tag = consume_tag_name(&tokens, &mut pos)?;
+                        if is_void_element(&tag) {
+                            return Err(Error::new(
+                                tag_span,
+                                format!(
+                                    "html!: <{}> is a void element and has no closing tag",
+                                    tag
+                                ),
+                            ));
+                        }
+                        match open_tags.pop() {
+                            Some((open, _)) if open == tag => {}
+                            Some((open, _)) => {
+                                return Err(Error::new(
+                                    tag_span,
+                                    format!(
+                                        "html!: mismatched closing tag </{}>, expected </{}>",
+                                        tag, open
+                                    ),
+                                ));
+                            }
+                            None => {
+                                return Err(Error::new(
+                                    tag_span,
+                                    format!("html!: unexpected closing tag </{}>", tag),
+                                ));
+                            }
+                        }
+                        // consume `>`
+                        expect_punct(&tokens, &mut pos, '>')?;
+                        nodes.push(Node::Close);
+                        continue;
                     }
                 }
 
                 // Open tag: `<tag`
-                let tag = consume_tag_name(&tokens, &mut pos);
+                let tag = consume_tag_name(&tokens, &mut pos)?;
 
                 // PascalCase tag names are child components: <ZigChild /> → child("zig-child")
                 if tag.starts_with(|c: char| c.is_uppercase()) {
-                    nodes.push(Node::Child(pascal_to_kebab(&tag)));
-                    // Parse props until `/>`
+                    let props_ty = consume_props_type(&tokens, &mut pos)?;
+                    let mut props = Vec::new();
+                    let mut events = Vec::new();
+                    // Parse props and `on:` handlers until `/>`
                     loop {
-                        match &tokens[pos] {
+                        let tt = tokens.get(pos).ok_or_else(|| {
+                            Error::new(
+                                open_span,
+                                format!("html!: unterminated props for <{}>, expected `/>`", tag),
+                            )
+                        })?;
+                        match tt {
                             TokenTree::Punct(p) if p.as_char() == '/' => {
                                 pos += 1;
-                                expect_punct(&tokens, &mut pos, '>');
+                                expect_punct(&tokens, &mut pos, '>')?;
                                 break;
                             }
-                            TokenTree::Ident(_) => {
-                                let name = ident_string(&tokens[pos]);
+                            // on:change="handler" — an event the child emits
+                            TokenTree::Ident(name)
+                                if name == "on" && is_punct(&tokens, pos + 1, ':') =>
+                            {
+                                let (_, event_type) = consume_attr_name(&tokens, &mut pos)?;
+                                expect_punct(&tokens, &mut pos, '=')?;
+                                let handler = consume_handler(&tokens, &mut pos)?;
+                                events.push(Node::Event {
+                                    event_type,
+                                    handler,
+                                });
+                            }
+                            TokenTree::Ident(name) => {
+                                let name = name.clone();
                                 pos += 1;
-                                expect_punct(&tokens, &mut pos, '=');
+                                expect_punct(&tokens, &mut pos, '=')?;
                                 // Value: "string" or {expr}
-                                match &tokens[pos] {
+                                let value = match token(&tokens, pos)? {
                                     TokenTree::Group(g) if g.delimiter() == Delimiter::Brace => {
-                                        let expr: Expr = syn::parse2(g.stream())
-                                            .expect("html!: expected expression inside { }");
-                                        nodes.push(Node::Prop { name, value: PropValue::Expr(expr) });
                                         pos += 1;
+                                        PropValue::Expr(braced_expr(g)?)
                                     }
-                                    _ => {
-                                        let value = consume_string_literal(&tokens, &mut pos);
-                                        nodes.push(Node::Prop { name, value: PropValue::Str(value) });
-                                    }
-                                }
+                                    _ => PropValue::Str(consume_string_literal(&tokens, &mut pos)?),
+                                };
+                                props.push(ChildProp { name, value });
+                            }
+                            _ => {
+                                return Err(Error::new(
+                                    tt.span(),
+                                    format!("html!: unexpected token in props of <{}>", tag),
+                                ))
                             }
-                            _ => panic!("html!: unexpected token in child props: {:?}", tokens[pos]),
                         }
                     }
+                    nodes.push(Node::Child {
+                        name: pascal_to_kebab(&tag),
+                        props_ty,
+                        props,
+                        events,
+                    });
                     continue;
                 }
 
-                nodes.push(Node::Open(tag));
+                let open_idx = nodes.len();
+                nodes.push(Node::Open(tag.clone()));
+                // `class:` / `style:` directives, merged when the tag ends
+                let mut classes = Vec::new();
+                let mut styles = Vec::new();
+                // Attribute names so far, to catch one both set and bound
+                let mut attr_names: Vec<String> = Vec::new();
+                let mut bound: Vec<String> = Vec::new();
 
                 // Parse attributes until `>` or `/>`
                 loop {
-                    if pos >= tokens.len() {
-                        break;
-                    }
-                    match &tokens[pos] {
-                        // `>` — end of open tag
+                    let Some(tt) = tokens.get(pos) else {
+                        return Err(Error::new(
+                            open_span,
+                            format!("html!: unclosed tag <{}>, expected `>` or `/>`", tag),
+                        ));
+                    };
+                    match tt {
+                        // `>` — end of open tag; void elements close immediately
                         TokenTree::Punct(p) if p.as_char() == '>' => {
                             pos += 1;
+                            merge_directives(&mut nodes, open_idx, classes, styles);
+                            if is_void_element(&tag) {
+                                nodes.push(Node::Close);
+                            } else {
+                                open_tags.push((tag, open_span));
+                            }
                             break;
                         }
                         // `/` followed by `>` — self-closing
                         TokenTree::Punct(p) if p.as_char() == '/' => {
                             pos += 1;
-                            expect_punct(&tokens, &mut pos, '>');
+                            expect_punct(&tokens, &mut pos, '>')?;
+                            merge_directives(&mut nodes, open_idx, classes, styles);
                             nodes.push(Node::Close);
                             break;
                         }
                         // attribute: `name="value"` or `on:event="handler"`
-                        TokenTree::Ident(_) => {
-                            let name = ident_string(&tokens[pos]);
-                            pos += 1;
-
-                            // Check for `on:event`
-                            if pos < tokens.len() {
-                                if let TokenTree::Punct(p) = &tokens[pos] {
-                                    if p.as_char() == ':' {
-                                        // event binding: on:click="handler"
-                                        pos += 1; // skip `:`
-                                        let event_type = ident_string(&tokens[pos]);
-                                        pos += 1;
-                                        expect_punct(&tokens, &mut pos, '=');
-                                        let handler = consume_string_literal(&tokens, &mut pos);
-                                        nodes.push(Node::Event { event_type, handler });
-                                        continue;
+                        TokenTree::Ident(_) | TokenTree::Literal(_) => {
+                            let (namespace, name) = consume_attr_name(&tokens, &mut pos)?;
+                            let name = match namespace.as_deref() {
+                                // event binding: on:click="handler" or on:click={Msg::Click}
+                                Some("on") => {
+                                    expect_punct(&tokens, &mut pos, '=')?;
+                                    let handler = consume_handler(&tokens, &mut pos)?;
+                                    nodes.push(Node::Event {
+                                        event_type: name,
+                                        handler,
+                                    });
+                                    continue;
+                                }
+                                // class:danger={selected} — toggle one class
+                                Some("class") => {
+                                    expect_punct(&tokens, &mut pos, '=')?;
+                                    let cond = consume_braced(&tokens, &mut pos, "class:")?;
+                                    classes.push((name, cond));
+                                    continue;
+                                }
+                                // bind:value={self.draft} — two-way binding
+                                Some("bind") => {
+                                    if name != "value" && name != "checked" {
+                                        return Err(Error::new(
+                                            tt.span(),
+                                            format!(
+                                                "html!: cannot bind `{}`, expected `value` or `checked`",
+                                                name
+                                            ),
+                                        ));
+                                    }
+                                    if attr_names.contains(&name) {
+                                        return Err(set_and_bound(tt.span(), &name));
                                     }
+                                    expect_punct(&tokens, &mut pos, '=')?;
+                                    let (state, field) = consume_bind_field(&tokens, &mut pos)?;
+                                    bound.push(name.clone());
+                                    nodes.push(Node::Bind {
+                                        property: name,
+                                        state,
+                                        field,
+                                    });
+                                    continue;
+                                }
+                                // style:color={c} — one style declaration
+                                Some("style") => {
+                                    expect_punct(&tokens, &mut pos, '=')?;
+                                    styles.push((name, consume_value(&tokens, &mut pos)?));
+                                    continue;
+                                }
+                                // key={row.id} — identity of a `for` instance
+                                None if name == "key" => {
+                                    expect_punct(&tokens, &mut pos, '=')?;
+                                    nodes.push(Node::Key(consume_value(&tokens, &mut pos)?));
+                                    continue;
                                 }
+                                // namespaced attribute: xlink:href="..."
+                                Some(ns) => format!("{}:{}", ns, name),
+                                None => name,
+                            };
+                            if bound.contains(&name) {
+                                return Err(set_and_bound(tt.span(), &name));
+                            }
+                            attr_names.push(name.clone());
+
+                            // Valueless attribute: `<button disabled>`
+                            let has_value = matches!(
+                                tokens.get(pos),
+                                Some(TokenTree::Punct(p)) if p.as_char() == '='
+                            );
+                            if !has_value {
+                                nodes.push(Node::Attr {
+                                    name,
+                                    value: String::new(),
+                                });
+                                continue;
                             }
 
                             // Regular attribute: name="value" or name={expr}
-                            expect_punct(&tokens, &mut pos, '=');
-                            match &tokens[pos] {
+                            expect_punct(&tokens, &mut pos, '=')?;
+                            match token(&tokens, pos)? {
                                 TokenTree::Group(g) if g.delimiter() == Delimiter::Brace => {
-                                    let expr: Expr = syn::parse2(g.stream())
-                                        .expect("html!: expected expression inside { }");
+                                    let expr = braced_expr(g)?;
                                     nodes.push(Node::DynAttr { name, value: expr });
                                     pos += 1;
                                 }
                                 _ => {
-                                    let value = consume_string_literal(&tokens, &mut pos);
+                                    let value = consume_string_literal(&tokens, &mut pos)?;
                                     nodes.push(Node::Attr { name, value });
                                 }
                             }
                         }
                         _ => {
-                            panic!(
-                                "html!: unexpected token in attribute position: {:?}",
-                                tokens[pos]
-                            );
+                            return Err(Error::new(
+                                tt.span(),
+                                format!("html!: unexpected token in attributes of <{}>", tag),
+                            ));
                         }
                     }
                 }
             }
-            // String literal — text content
-            TokenTree::Literal(_) => {
-                let lit_str: LitStr = syn::parse2(TokenStream::from(tokens[pos].clone()))
-                    .expect("html!: expected string literal for text content");
-                nodes.push(Node::Text(lit_str.value()));
-                pos += 1;
+            // `for pat in iter { ... }` — each iteration becomes a begin/end group
+            TokenTree::Ident(id) if id == "for" => {
+                nodes.push(parse_for(&tokens, &mut pos, sites)?);
+            }
+            // `if cond { ... } else { ... }` — each branch is a zero-or-one group
+            TokenTree::Ident(id) if id == "if" => {
+                nodes.push(parse_if(&tokens, &mut pos, sites)?);
             }
-            // `{ expr }` — slot (dynamic content)
+            // `match expr { Pat => <view>, ... }` — one zero-or-one group per arm
+            TokenTree::Ident(id) if id == "match" => {
+                nodes.push(parse_match(&tokens, &mut pos, sites)?);
+            }
+            // String literal — text content, with `{expr}` interpolation
+            TokenTree::Literal(lit) => {
+                let span = lit.span();
+                let text = consume_string_literal(&tokens, &mut pos)?;
+                nodes.extend(text_nodes(&text, span)?);
+            }
+            // `{ expr }` — slot (dynamic content), or `{..expr}` — splice
             TokenTree::Group(g) if g.delimiter() == Delimiter::Brace => {
-                let expr: Expr = syn::parse2(g.stream())
-                    .expect("html!: expected expression inside { }");
-                nodes.push(Node::Slot(expr));
+                nodes.push(match braced_expr(g)? {
+                    Expr::Range(ExprRange {
+                        start: None,
+                        limits: RangeLimits::HalfOpen(_),
+                        end,
+                        ..
+                    }) => match end {
+                        Some(expr) => Node::Splice(*expr),
+                        None => {
+                            return Err(Error::new(
+                                g.span(),
+                                "html!: expected `{..expr}` with a `Vec<Opcode>` to splice",
+                            ))
+                        }
+                    },
+                    expr => Node::Slot(expr),
+                });
                 pos += 1;
             }
+            tt => {
+                return Err(Error::new(
+                    tt.span(),
+                    "html!: unexpected token, expected a tag, string literal, `{ expr }`, `for`, `if` or `match`",
+                ));
+            }
+        }
+    }
+
+    if let Some((tag, span)) = open_tags.pop() {
+        return Err(Error::new(
+            span,
+            format!("html!: unclosed tag <{}>, expected </{}>", tag, tag),
+        ));
+    }
+
+    Ok(nodes)
+}
+
+fn parse_for(tokens: &[TokenTree], pos: &mut usize, sites: &mut Sites<'_>) -> Result<Node> {
+    let start = *pos;
+    let for_span = tokens[start].span();
+    *pos += 1; // skip `for`
+
+    // Pattern: everything up to `in`
+    let mut pat_tokens = TokenStream::new();
+    loop {
+        match tokens.get(*pos) {
+            Some(TokenTree::Ident(id)) if id == "in" => {
+                *pos += 1;
+                break;
+            }
+            Some(tt) => {
+                pat_tokens.extend([tt.clone()]);
+                *pos += 1;
+            }
+            None => return Err(Error::new(for_span, "html!: expected `in` in `for` loop")),
+        }
+    }
+
+    let (iter_tokens, body) = take_head_and_body(tokens, pos, for_span, "`for` loop iterator")?;
+    let (iter_tokens, dirty) = take_dirty(iter_tokens)?;
+
+    let pat = parse_nonempty(
+        Pat::parse_multi_with_leading_vert,
+        pat_tokens,
+        for_span,
+        "html!: expected pattern in `for` loop",
+    )?;
+    let iter = parse_nonempty(
+        Expr::parse,
+        iter_tokens,
+        for_span,
+        "html!: expected iterator expression in `for` loop",
+    )?;
+    let (template_id, mut body) = parse_group_body(body, &tokens[start..*pos], sites)?;
+    let key = take_key(&mut body);
+    if let (Some(key), Some(_)) = (&key, &dirty) {
+        return Err(Error::new_spanned(
+            key,
+            "html!: a keyed `for` can't take `@dirty`; the runtime matches keyed rows by key",
+        ));
+    }
+
+    Ok(Node::For {
+        pat: Box::new(pat),
+        iter: Box::new(iter),
+        template_id,
+        key: key.map(Box::new),
+        dirty: dirty.map(Box::new),
+        body,
+    })
+}
+
+/// Split a trailing `@dirty(indices)` off a `for` loop's iterator tokens.
+fn take_dirty(tokens: TokenStream) -> Result<(TokenStream, Option<Expr>)> {
+    let mut tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let Some(at) = tokens
+        .iter()
+        .position(|tt| matches!(tt, TokenTree::Punct(p) if p.as_char() == '@'))
+    else {
+        return Ok((tokens.into_iter().collect(), None));
+    };
+    let dirty = match &tokens[at + 1..] {
+        [TokenTree::Ident(id), TokenTree::Group(g)]
+            if id == "dirty" && g.delimiter() == Delimiter::Parenthesis =>
+        {
+            parse_nonempty(
+                Expr::parse,
+                g.stream(),
+                g.span(),
+                "html!: `@dirty` expects the indices of the changed instances",
+            )?
+        }
+        _ => {
+            return Err(Error::new(
+                tokens[at].span(),
+                "html!: expected `@dirty(indices)` before the `for` body",
+            ))
+        }
+    };
+    tokens.truncate(at);
+    Ok((tokens.into_iter().collect(), Some(dirty)))
+}
+
+fn parse_if(tokens: &[TokenTree], pos: &mut usize, sites: &mut Sites<'_>) -> Result<Node> {
+    let mut branches = Vec::new();
+    let mut start = *pos;
+    loop {
+        let if_span = tokens[*pos].span();
+        *pos += 1; // skip `if`
+        let (cond_tokens, body) = take_head_and_body(tokens, pos, if_span, "`if` condition")?;
+        let cond = parse_nonempty(
+            Expr::parse,
+            cond_tokens,
+            if_span,
+            "html!: expected condition in `if` block",
+        )?;
+        let (template_id, body) = parse_group_body(body, &tokens[start..*pos], sites)?;
+        branches.push(Branch {
+            cond: Some(cond),
+            template_id,
+            body,
+        });
+
+        match tokens.get(*pos) {
+            Some(TokenTree::Ident(id)) if id == "else" => {
+                start = *pos;
+                *pos += 1;
+            }
+            _ => break,
+        }
+        match tokens.get(*pos) {
+            Some(TokenTree::Ident(id)) if id == "if" => continue,
+            Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => {
+                *pos += 1;
+                let (template_id, body) = parse_group_body(g, &tokens[start..*pos], sites)?;
+                branches.push(Branch {
+                    cond: None,
+                    template_id,
+                    body,
+                });
+                break;
+            }
             _ => {
-                panic!("html!: unexpected token: {:?}", tokens[pos]);
+                return Err(Error::new(
+                    tokens[start].span(),
+                    "html!: expected `if` or `{ ... }` after `else`",
+                ))
             }
         }
     }
+    Ok(Node::If(branches))
+}
+
+fn parse_match(tokens: &[TokenTree], pos: &mut usize, sites: &mut Sites<'_>) -> Result<Node> {
+    let match_span = tokens[*pos].span();
+    *pos += 1; // skip `match`
+    let (expr_tokens, arms_group) =
+        take_head_and_body(tokens, pos, match_span, "`match` expression")?;
+    let expr = parse_nonempty(
+        Expr::parse,
+        expr_tokens,
+        match_span,
+        "html!: expected expression after `match`",
+    )?;
+
+    let arm_tokens: Vec<TokenTree> = arms_group.stream().into_iter().collect();
+    let mut arms = Vec::new();
+    let mut i = 0;
+    while i < arm_tokens.len() {
+        let start = i;
 
-    nodes
+        // Pattern (and optional `if` guard) up to `=>`
+        let mut pat_tokens = TokenStream::new();
+        let mut guard_tokens: Option<TokenStream> = None;
+        loop {
+            match (arm_tokens.get(i), arm_tokens.get(i + 1)) {
+                (Some(TokenTree::Punct(eq)), Some(TokenTree::Punct(gt)))
+                    if eq.as_char() == '='
+                        && eq.spacing() == Spacing::Joint
+                        && gt.as_char() == '>' =>
+                {
+                    i += 2;
+                    break;
+                }
+                (Some(TokenTree::Ident(id)), _) if id == "if" && guard_tokens.is_none() => {
+                    guard_tokens = Some(TokenStream::new());
+                    i += 1;
+                }
+                (Some(tt), _) => {
+                    match &mut guard_tokens {
+                        Some(guard) => guard.extend([tt.clone()]),
+                        None => pat_tokens.extend([tt.clone()]),
+                    }
+                    i += 1;
+                }
+                (None, _) => {
+                    return Err(Error::new(
+                        arm_tokens[start].span(),
+                        "html!: expected `=>` in `match` arm",
+                    ))
+                }
+            }
+        }
+
+        // Body: a `{ ... }` block, or view tokens up to the next top-level `,`
+        let body_tokens: TokenStream = match arm_tokens.get(i) {
+            Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => {
+                i += 1;
+                g.stream()
+            }
+            _ => {
+                let mut body = TokenStream::new();
+                while let Some(tt) = arm_tokens.get(i) {
+                    if matches!(tt, TokenTree::Punct(p) if p.as_char() == ',') {
+                        break;
+                    }
+                    body.extend([tt.clone()]);
+                    i += 1;
+                }
+                body
+            }
+        };
+        let end = i;
+        if matches!(arm_tokens.get(i), Some(TokenTree::Punct(p)) if p.as_char() == ',') {
+            i += 1;
+        }
+
+        let arm_span = arm_tokens[start].span();
+        let pat = parse_nonempty(
+            Pat::parse_multi_with_leading_vert,
+            pat_tokens,
+            arm_span,
+            "html!: expected pattern in `match` arm",
+        )?;
+        let guard = guard_tokens
+            .map(|g| {
+                parse_nonempty(
+                    Expr::parse,
+                    g,
+                    arm_span,
+                    "html!: expected expression in `match` arm guard",
+                )
+            })
+            .transpose()?;
+        let (template_id, body) = parse_group_tokens(body_tokens, &arm_tokens[start..end], sites)?;
+        arms.push(Arm {
+            pat,
+            guard,
+            template_id,
+            body,
+        });
+    }
+
+    Ok(Node::Match {
+        expr: Box::new(expr),
+        arms,
+    })
 }
 
-fn consume_tag_name(tokens: &[TokenTree], pos: &mut usize) -> String {
-    let mut name = ident_string(&tokens[*pos]);
+/// Collect the tokens of a `for`/`if`/`match` header up to its `{ ... }` body.
+/// Returns the header and the body group.
+fn take_head_and_body<'a>(
+    tokens: &'a [TokenTree],
+    pos: &mut usize,
+    keyword_span: Span,
+    what: &str,
+) -> Result<(TokenStream, &'a Group)> {
+    let mut head = TokenStream::new();
+    loop {
+        match tokens.get(*pos) {
+            Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => {
+                *pos += 1;
+                return Ok((head, g));
+            }
+            Some(tt) => {
+                head.extend([tt.clone()]);
+                *pos += 1;
+            }
+            None => {
+                return Err(Error::new(
+                    keyword_span,
+                    format!("html!: expected `{{ ... }}` body after {}", what),
+                ))
+            }
+        }
+    }
+}
+
+fn parse_group_body(
+    group: &Group,
+    source: &[TokenTree],
+    sites: &mut Sites<'_>,
+) -> Result<(String, Vec<Node>)> {
+    parse_group_tokens(group.stream(), source, sites)
+}
+
+/// Parse the body of a group-producing block and derive its template ID.
+/// The body can hold groups and children of its own; the runtime keeps them
+/// with the instance they're in.
+fn parse_group_tokens(
+    body_tokens: TokenStream,
+    source: &[TokenTree],
+    sites: &mut Sites<'_>,
+) -> Result<(String, Vec<Node>)> {
+    let site = sites.next;
+    sites.next += 1;
+    let body = parse_nodes(body_tokens, sites)?;
+    let source: TokenStream = source.iter().cloned().collect();
+    let template_id = template_id(&body, &source.to_string(), sites.call_site, site);
+    Ok((template_id, body))
+}
+
+/// Remove the `key` of a `for` body's root element: the attributes that
+/// directly follow the body's first `Open`.
+fn take_key(body: &mut Vec<Node>) -> Option<Expr> {
+    if !matches!(body.first(), Some(Node::Open(_))) {
+        return None;
+    }
+    let idx = body[1..]
+        .iter()
+        .take_while(|n| {
+            matches!(
+                n,
+                Node::Attr { .. }
+                    | Node::DynAttr { .. }
+                    | Node::Event { .. }
+                    | Node::Bind { .. }
+                    | Node::ClassList { .. }
+                    | Node::StyleList { .. }
+                    | Node::Key(_)
+            )
+        })
+        .position(|n| matches!(n, Node::Key(_)))?;
+    match body.remove(idx + 1) {
+        Node::Key(expr) => Some(expr),
+        _ => unreachable!(),
+    }
+}
+
+/// Any `key` left after `parse_for` took its own is misplaced.
+fn reject_keys(nodes: &[Node]) -> Result<()> {
+    for node in nodes {
+        match node {
+            Node::Key(expr) => {
+                return Err(Error::new_spanned(
+                    expr,
+                    "html!: `key` is only allowed on the root element of a `for` body",
+                ))
+            }
+            Node::For { body, .. } => reject_keys(body)?,
+            Node::If(branches) => {
+                for branch in branches {
+                    reject_keys(&branch.body)?;
+                }
+            }
+            Node::Match { arms, .. } => {
+                for arm in arms {
+                    reject_keys(&arm.body)?;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Derive a stable template ID for a group site: the first tag in the body
+/// (for readability in devtools) plus a hash of the site's source tokens and
+/// of where the `html!` call is. The same call always yields the same ID, so
+/// the runtime's group and template cache stay valid across renders.
+fn template_id(body: &[Node], source: &str, call_site: &str, site: usize) -> String {
+    let prefix = body
+        .iter()
+        .find_map(|n| match n {
+            Node::Open(tag) => Some(tag.as_str()),
+            _ => None,
+        })
+        .unwrap_or("group");
+
+    // FNV-1a
+    let mut hash: u32 = 0x811c9dc5;
+    let bytes = source.bytes().chain(call_site.bytes());
+    for b in bytes.chain(site.to_le_bytes()) {
+        hash ^= b as u32;
+        hash = hash.wrapping_mul(0x01000193);
+    }
+    format!("{}-{:08x}", prefix, hash)
+}
+
+fn consume_tag_name(tokens: &[TokenTree], pos: &mut usize) -> Result<String> {
+    let mut name = ident_string(token(tokens, *pos)?)?;
     *pos += 1;
-    // Handle hyphenated tag names: `my-component` tokenizes as `my`, `-`, `component`
+    // Handle hyphenated names: `my-component` tokenizes as `my`, `-`, `component`
     while *pos + 1 < tokens.len() {
         if let TokenTree::Punct(p) = &tokens[*pos] {
             if p.as_char() == '-' {
-                if let TokenTree::Ident(_) = &tokens[*pos + 1] {
+                if let TokenTree::Ident(id) = &tokens[*pos + 1] {
                     name.push('-');
-                    name.push_str(&ident_string(&tokens[*pos + 1]));
+                    name.push_str(&id.to_string());
                     *pos += 2;
                     continue;
                 }
@@ -188,33 +925,262 @@ fn consume_tag_name(tokens: &[TokenTree], pos: &mut usize) -> String {
         }
         break;
     }
-    name
+    Ok(name)
+}
+
+/// The `: Path` after a child component's name, `<RustChild: CounterProps`.
+fn consume_props_type(tokens: &[TokenTree], pos: &mut usize) -> Result<Option<Box<Path>>> {
+    match tokens.get(*pos) {
+        Some(TokenTree::Punct(p)) if p.as_char() == ':' && p.spacing() == Spacing::Alone => {}
+        _ => return Ok(None),
+    }
+    let colon = tokens[*pos].span();
+    *pos += 1;
+    // `Ident` or `a::b::Ident`; the first prop name follows the last segment
+    let mut path = TokenStream::new();
+    loop {
+        match tokens.get(*pos) {
+            Some(tt @ TokenTree::Ident(_)) => {
+                path.extend([tt.clone()]);
+                *pos += 1;
+            }
+            _ => return Err(Error::new(colon, "html!: expected a props type after `:`")),
+        }
+        match (tokens.get(*pos), tokens.get(*pos + 1)) {
+            (Some(TokenTree::Punct(a)), Some(TokenTree::Punct(b)))
+                if a.as_char() == ':' && a.spacing() == Spacing::Joint && b.as_char() == ':' =>
+            {
+                path.extend(tokens[*pos..*pos + 2].iter().cloned());
+                *pos += 2;
+            }
+            _ => break,
+        }
+    }
+    Ok(Some(Box::new(syn::parse2(path)?)))
 }
 
-fn ident_string(tt: &TokenTree) -> String {
+/// Attribute names: `name`, hyphenated `data-id`, namespaced `ns:name`
+/// (`on:click`, `xlink:href`), or a string literal for anything else.
+/// Returns the namespace, if any, separately from the local name.
+fn consume_attr_name(tokens: &[TokenTree], pos: &mut usize) -> Result<(Option<String>, String)> {
+    if let TokenTree::Literal(_) = token(tokens, *pos)? {
+        return Ok((None, consume_string_literal(tokens, pos)?));
+    }
+    let name = consume_tag_name(tokens, pos)?;
+    if let Some(TokenTree::Punct(p)) = tokens.get(*pos) {
+        if p.as_char() == ':' {
+            *pos += 1;
+            let local = consume_tag_name(tokens, pos)?;
+            return Ok((Some(name), local));
+        }
+    }
+    Ok((None, name))
+}
+
+fn consume_handler(tokens: &[TokenTree], pos: &mut usize) -> Result<Handler> {
+    match token(tokens, *pos)? {
+        TokenTree::Group(g) if g.delimiter() == Delimiter::Brace => {
+            *pos += 1;
+            Ok(Handler::Expr(braced_expr(g)?))
+        }
+        _ => Ok(Handler::Name(consume_string_literal(tokens, pos)?)),
+    }
+}
+
+/// A `class:` condition or `bind:` handler: `{expr}`.
+fn consume_braced(tokens: &[TokenTree], pos: &mut usize, directive: &str) -> Result<Expr> {
+    match token(tokens, *pos)? {
+        TokenTree::Group(g) if g.delimiter() == Delimiter::Brace => {
+            *pos += 1;
+            braced_expr(g)
+        }
+        tt => Err(Error::new(
+            tt.span(),
+            format!("html!: expected `{{expr}}` for a `{}` directive", directive),
+        )),
+    }
+}
+
+/// A `bind:` target: `{self.draft}`, a field of the component's state
+/// reached through `self` or a variable holding it. Only a direct field, so
+/// the field's name is the handler the state's `bind` routes by.
+fn consume_bind_field(tokens: &[TokenTree], pos: &mut usize) -> Result<(Ident, Ident)> {
+    let span = token(tokens, *pos)?.span();
+    if let Expr::Field(ExprField {
+        base,
+        member: Member::Named(field),
+        ..
+    }) = consume_braced(tokens, pos, "bind:")?
+    {
+        if let Expr::Path(path) = *base {
+            if path.qself.is_none() {
+                if let Some(state) = path.path.get_ident() {
+                    return Ok((state.clone(), field));
+                }
+            }
+        }
+    }
+    Err(Error::new(
+        span,
+        "html!: expected a `#[bind]` field of the component's state for a `bind:` directive, \
+         e.g. `{self.draft}`",
+    ))
+}
+
+fn set_and_bound(span: Span, name: &str) -> Error {
+    Error::new(
+        span,
+        format!(
+            "html!: `{}` is both set and bound; `bind:{}` already sets it",
+            name, name
+        ),
+    )
+}
+
+/// A directive value: `"literal"` or `{expr}`.
+fn consume_value(tokens: &[TokenTree], pos: &mut usize) -> Result<Expr> {
+    match token(tokens, *pos)? {
+        TokenTree::Group(g) if g.delimiter() == Delimiter::Brace => {
+            *pos += 1;
+            braced_expr(g)
+        }
+        tt => {
+            let span = tt.span();
+            let value = consume_string_literal(tokens, pos)?;
+            Ok(Expr::Lit(ExprLit {
+                attrs: Vec::new(),
+                lit: Lit::Str(LitStr::new(&value, span)),
+            }))
+        }
+    }
+}
+
+/// Fold an element's `class:` / `style:` directives, together with its
+/// `class` / `style` attribute if any, into single list nodes. `start` is
+/// the index of the element's `Open` node.
+fn merge_directives(
+    nodes: &mut Vec<Node>,
+    start: usize,
+    classes: Vec<(String, Expr)>,
+    styles: Vec<(String, Expr)>,
+) {
+    if !classes.is_empty() {
+        let base = take_attr(nodes, start, "class");
+        nodes.push(Node::ClassList {
+            base,
+            toggles: classes,
+        });
+    }
+    if !styles.is_empty() {
+        let base = take_attr(nodes, start, "style");
+        nodes.push(Node::StyleList {
+            base,
+            props: styles,
+        });
+    }
+}
+
+/// Remove the element's static or dynamic attribute `name`, returning its
+/// value as an expression.
+fn take_attr(nodes: &mut Vec<Node>, start: usize, name: &str) -> Option<Expr> {
+    let idx = nodes[start..].iter().position(|node| match node {
+        Node::Attr { name: n, .. } | Node::DynAttr { name: n, .. } => n == name,
+        _ => false,
+    })?;
+    match nodes.remove(start + idx) {
+        Node::Attr { value, .. } => Some(Expr::Lit(ExprLit {
+            attrs: Vec::new(),
+            lit: Lit::Str(LitStr::new(&value, Span::call_site())),
+        })),
+        Node::DynAttr { value, .. } => Some(value),
+        _ => unreachable!(),
+    }
+}
+
+/// The token at `pos`, or an error at the last token if input ended early.
+fn token(tokens: &[TokenTree], pos: usize) -> Result<&TokenTree> {
+    tokens.get(pos).ok_or_else(|| {
+        let span = tokens.last().map_or_else(Span::call_site, |tt| tt.span());
+        Error::new(span, "html!: unexpected end of input")
+    })
+}
+
+fn ident_string(tt: &TokenTree) -> Result<String> {
     match tt {
-        TokenTree::Ident(id) => id.to_string(),
-        _ => panic!("html!: expected identifier, got {:?}", tt),
+        TokenTree::Ident(id) => Ok(id.to_string()),
+        _ => Err(Error::new(tt.span(), "html!: expected identifier")),
     }
 }
 
-fn expect_punct(tokens: &[TokenTree], pos: &mut usize, ch: char) {
-    match &tokens[*pos] {
+fn expect_punct(tokens: &[TokenTree], pos: &mut usize, ch: char) -> Result<()> {
+    match token(tokens, *pos)? {
         TokenTree::Punct(p) if p.as_char() == ch => {
             *pos += 1;
+            Ok(())
         }
-        _ => panic!("html!: expected '{}', got {:?}", ch, tokens[*pos]),
+        tt => Err(Error::new(tt.span(), format!("html!: expected `{}`", ch))),
     }
 }
 
-fn consume_string_literal(tokens: &[TokenTree], pos: &mut usize) -> String {
-    let lit_str: LitStr = syn::parse2(TokenStream::from(tokens[*pos].clone()))
-        .expect("html!: expected string literal");
+fn is_punct(tokens: &[TokenTree], pos: usize, ch: char) -> bool {
+    matches!(tokens.get(pos), Some(TokenTree::Punct(p)) if p.as_char() == ch)
+}
+
+fn consume_string_literal(tokens: &[TokenTree], pos: &mut usize) -> Result<String> {
+    let tt = token(tokens, *pos)?;
+    let lit_str: LitStr = syn::parse2(TokenStream::from(tt.clone()))
+        .map_err(|_| Error::new(tt.span(), "html!: expected string literal or `{ expr }`"))?;
     *pos += 1;
-    lit_str.value()
+    Ok(lit_str.value())
+}
+
+/// Parse the contents of `{ ... }` as an expression.
+fn braced_expr(g: &Group) -> Result<Expr> {
+    if g.stream().is_empty() {
+        return Err(Error::new(
+            g.span(),
+            "html!: expected expression inside `{ }`",
+        ));
+    }
+    syn::parse2(g.stream())
+}
+
+/// Run a syn parser over tokens that must not be empty; empty input is
+/// reported at `span` rather than at the macro call site.
+fn parse_nonempty<T>(
+    parser: impl Parser<Output = T>,
+    tokens: TokenStream,
+    span: Span,
+    message: &str,
+) -> Result<T> {
+    if tokens.is_empty() {
+        return Err(Error::new(span, message));
+    }
+    parser.parse2(tokens)
+}
+
+/// HTML elements that never have children or a closing tag.
+fn is_void_element(tag: &str) -> bool {
+    matches!(
+        tag,
+        "area"
+            | "base"
+            | "br"
+            | "col"
+            | "embed"
+            | "hr"
+            | "img"
+            | "input"
+            | "link"
+            | "meta"
+            | "param"
+            | "source"
+            | "track"
+            | "wbr"
+    )
 }
 
-fn pascal_to_kebab(s: &str) -> String {
+pub fn pascal_to_kebab(s: &str) -> String {
     let mut result = String::new();
     for (i, c) in s.chars().enumerate() {
         if c.is_uppercase() {
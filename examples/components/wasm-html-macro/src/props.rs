@@ -0,0 +1,268 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Error, Expr, Fields, GenericArgument, PathArguments, Result, Type};
+
+/// `#[derive(Props)]` on a struct with named fields of type `i32`, `f64`,
+/// `bool`, `String` or an `Option` of one. Converts to and from the
+/// `list<tuple<string, prop-value>>` passed to `renderer::render`.
+///
+/// A missing prop is an error unless the field is an `Option` (`None`) or
+/// has `#[prop(default)]` / `#[prop(default = expr)]`. Props with no
+/// matching field are ignored.
+///
+/// Also generates the hidden helpers `html!` uses to type-check
+/// `<Child: Props name={value} />`: one `__prop_<name>` converter per field
+/// and a `const fn __require_props` that fails compilation when a required
+/// prop isn't passed.
+pub fn derive(input: DeriveInput) -> Result<TokenStream> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "Props can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "Props needs a struct with named fields",
+        ));
+    };
+
+    let ident = &input.ident;
+    let type_name = ident.to_string();
+    let mut inits = Vec::new();
+    let mut to_props = Vec::new();
+    let mut converters = Vec::new();
+    let mut required = Vec::new();
+    for field in &fields.named {
+        let name = field.ident.as_ref().expect("named field");
+        let prop = name.to_string();
+        let (ty, optional) = match option_inner(&field.ty) {
+            Some(inner) => (inner, true),
+            None => (&field.ty, false),
+        };
+        let default = prop_default(field)?;
+
+        let mistyped = format!("prop `{}` of {} expects {{}}, got {{}}", prop, type_name);
+        let convert = quote! {
+            <#ty as Prop>::from_value(value).ok_or_else(|| {
+                ::std::format!(#mistyped, <#ty as Prop>::KIND, kind(value))
+            })?
+        };
+        let missing = match (&default, optional) {
+            (Some(Some(expr)), _) => quote! { #expr },
+            (Some(None), _) => quote! { ::core::default::Default::default() },
+            (None, true) => quote! { ::core::option::Option::None },
+            (None, false) => {
+                required.push(prop.clone());
+                let message = format!("missing required prop `{}` of {}", prop, type_name);
+                quote! { return ::core::result::Result::Err(#message.into()) }
+            }
+        };
+        let present = if optional {
+            quote! { ::core::option::Option::Some(#convert) }
+        } else {
+            convert
+        };
+        inits.push(quote! {
+            #name: match find(props, #prop) {
+                ::core::option::Option::Some(value) => #present,
+                ::core::option::Option::None => #missing,
+            }
+        });
+
+        to_props.push(if optional {
+            quote! {
+                if let ::core::option::Option::Some(value) = &self.#name {
+                    props.push((#prop.into(), Prop::to_value(value)));
+                }
+            }
+        } else {
+            quote! { props.push((#prop.into(), Prop::to_value(&self.#name))); }
+        });
+
+        let converter = format_ident!("__prop_{}", name);
+        converters.push(quote! {
+            #[doc(hidden)]
+            pub fn #converter(value: impl ::core::convert::Into<#ty>) -> PropValue {
+                Prop::to_value(&value.into())
+            }
+        });
+    }
+
+    let require_checks = required.iter().map(|prop| {
+        let message = format!(
+            "html!: <_: {}> is missing required prop `{}`",
+            type_name, prop
+        );
+        quote! {
+            if !has(given, #prop) {
+                ::core::panic!(#message);
+            }
+        }
+    });
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        const _: () = {
+            trait Prop: Sized {
+                const KIND: &'static str;
+                fn to_value(&self) -> PropValue;
+                fn from_value(value: &PropValue) -> ::core::option::Option<Self>;
+            }
+            impl Prop for i32 {
+                const KIND: &'static str = "int";
+                fn to_value(&self) -> PropValue {
+                    PropValue::Int(*self)
+                }
+                fn from_value(value: &PropValue) -> ::core::option::Option<Self> {
+                    match value {
+                        PropValue::Int(v) => ::core::option::Option::Some(*v),
+                        _ => ::core::option::Option::None,
+                    }
+                }
+            }
+            impl Prop for f64 {
+                const KIND: &'static str = "float";
+                fn to_value(&self) -> PropValue {
+                    PropValue::Float(*self)
+                }
+                fn from_value(value: &PropValue) -> ::core::option::Option<Self> {
+                    match value {
+                        PropValue::Float(v) => ::core::option::Option::Some(*v),
+                        PropValue::Int(v) => ::core::option::Option::Some(*v as f64),
+                        _ => ::core::option::Option::None,
+                    }
+                }
+            }
+            impl Prop for bool {
+                const KIND: &'static str = "boolean";
+                fn to_value(&self) -> PropValue {
+                    PropValue::Boolean(*self)
+                }
+                fn from_value(value: &PropValue) -> ::core::option::Option<Self> {
+                    match value {
+                        PropValue::Boolean(v) => ::core::option::Option::Some(*v),
+                        _ => ::core::option::Option::None,
+                    }
+                }
+            }
+            impl Prop for ::std::string::String {
+                const KIND: &'static str = "str";
+                fn to_value(&self) -> PropValue {
+                    PropValue::Str(self.clone())
+                }
+                fn from_value(value: &PropValue) -> ::core::option::Option<Self> {
+                    match value {
+                        PropValue::Str(v) => ::core::option::Option::Some(v.clone()),
+                        _ => ::core::option::Option::None,
+                    }
+                }
+            }
+
+            fn kind(value: &PropValue) -> &'static str {
+                match value {
+                    PropValue::Int(_) => "int",
+                    PropValue::Float(_) => "float",
+                    PropValue::Boolean(_) => "boolean",
+                    PropValue::Str(_) => "str",
+                }
+            }
+
+            // The last value wins if a prop is passed twice
+            fn find<'a>(
+                props: &'a [(::std::string::String, PropValue)],
+                name: &str,
+            ) -> ::core::option::Option<&'a PropValue> {
+                props.iter().rev().find(|(n, _)| n == name).map(|(_, v)| v)
+            }
+
+            const fn has(given: &[&str], name: &str) -> bool {
+                let name = name.as_bytes();
+                let mut i = 0;
+                while i < given.len() {
+                    let candidate = given[i].as_bytes();
+                    if candidate.len() == name.len() {
+                        let mut j = 0;
+                        while j < name.len() && candidate[j] == name[j] {
+                            j += 1;
+                        }
+                        if j == name.len() {
+                            return true;
+                        }
+                    }
+                    i += 1;
+                }
+                false
+            }
+
+            impl #impl_generics #ident #ty_generics #where_clause {
+                /// Read the props passed to `render`, or explain which one is
+                /// missing or has the wrong type.
+                pub fn from_props(
+                    props: &[(::std::string::String, PropValue)],
+                ) -> ::core::result::Result<Self, ::std::string::String> {
+                    ::core::result::Result::Ok(#ident {
+                        #(#inits,)*
+                    })
+                }
+
+                /// The prop list for these values; `None` fields are left out.
+                pub fn to_props(&self) -> ::std::vec::Vec<(::std::string::String, PropValue)> {
+                    let mut props = ::std::vec::Vec::new();
+                    #(#to_props)*
+                    props
+                }
+
+                #(#converters)*
+
+                #[doc(hidden)]
+                pub const fn __require_props(given: &[&str]) {
+                    #(#require_checks)*
+                }
+            }
+        };
+    })
+}
+
+/// `T` for a field of type `Option<T>`.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first() {
+        Some(GenericArgument::Type(inner)) if args.args.len() == 1 => Some(inner),
+        _ => None,
+    }
+}
+
+/// `#[prop(default)]` → `Some(None)`, `#[prop(default = expr)]` →
+/// `Some(Some(expr))`, no attribute → `None`.
+fn prop_default(field: &syn::Field) -> Result<Option<Option<Expr>>> {
+    let mut default = None;
+    for attr in &field.attrs {
+        if !attr.path().is_ident("prop") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("default") {
+                default = Some(if meta.input.peek(syn::Token![=]) {
+                    Some(meta.value()?.parse()?)
+                } else {
+                    None
+                });
+                Ok(())
+            } else {
+                Err(meta.error("expected `default` or `default = expr`"))
+            }
+        })?;
+    }
+    Ok(default)
+}
@@ -0,0 +1,183 @@
+use std::str::FromStr;
+
+use proc_macro2::{Group, Span, TokenStream, TokenTree};
+use syn::{parse_quote, Error, Expr, LitStr, Result};
+
+use crate::parse::Node;
+
+/// A piece of a text literal: literal text, or a `{expr}` / `{expr:spec}`
+/// placeholder.
+enum Segment {
+    Text(String),
+    Arg(Expr, String),
+}
+
+/// Nodes for a text literal. Placeholders make it `"Count: {count}"`-style
+/// interpolation: static text before the first and after the last
+/// placeholder stays as `Text`, and only the span between becomes a `Slot`.
+/// `{{` and `}}` are literal braces, as in `format!`.
+pub fn text_nodes(value: &str, span: Span) -> Result<Vec<Node>> {
+    let mut segments = segments(value, span)?;
+
+    let mut nodes = Vec::new();
+    if let Some(Segment::Text(prefix)) = segments.first() {
+        nodes.push(Node::Text(prefix.clone()));
+        segments.remove(0);
+    }
+    let suffix = match segments.last() {
+        Some(Segment::Text(_)) => match segments.pop() {
+            Some(Segment::Text(suffix)) => Some(suffix),
+            _ => unreachable!(),
+        },
+        _ => None,
+    };
+
+    match segments.len() {
+        0 => {}
+        // A lone `{expr}` needs no format string
+        1 if matches!(&segments[0], Segment::Arg(_, spec) if spec.is_empty()) => {
+            let Some(Segment::Arg(expr, _)) = segments.pop() else {
+                unreachable!()
+            };
+            nodes.push(Node::Slot(expr));
+        }
+        _ => {
+            let mut format = String::new();
+            let mut args = Vec::new();
+            for segment in segments {
+                match segment {
+                    Segment::Text(text) => {
+                        format.push_str(&text.replace('{', "{{").replace('}', "}}"))
+                    }
+                    Segment::Arg(expr, spec) if spec.is_empty() => {
+                        format.push_str("{}");
+                        args.push(expr);
+                    }
+                    Segment::Arg(expr, spec) => {
+                        format.push_str("{:");
+                        format.push_str(&spec);
+                        format.push('}');
+                        args.push(expr);
+                    }
+                }
+            }
+            // Spanned at the literal, so `format!` errors point there
+            let format = LitStr::new(&format, span);
+            nodes.push(Node::Slot(parse_quote! {
+                ::std::format!(#format, #(#args),*)
+            }));
+        }
+    }
+
+    nodes.extend(suffix.map(Node::Text));
+    Ok(nodes)
+}
+
+fn segments(value: &str, span: Span) -> Result<Vec<Segment>> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+            }
+            '{' => {
+                // Up to the matching `}`; braces may nest inside the expression
+                let mut depth = 0;
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') if depth == 0 => break,
+                        Some(c) => {
+                            match c {
+                                '{' => depth += 1,
+                                '}' => depth -= 1,
+                                _ => {}
+                            }
+                            placeholder.push(c);
+                        }
+                        None => {
+                            return Err(Error::new(
+                                span,
+                                "html!: unclosed `{` in text; write `{{` for a literal brace",
+                            ))
+                        }
+                    }
+                }
+                if !text.is_empty() {
+                    segments.push(Segment::Text(std::mem::take(&mut text)));
+                }
+                let (expr, spec) = placeholder_expr(&placeholder, span)?;
+                segments.push(Segment::Arg(expr, spec));
+            }
+            '}' => {
+                return Err(Error::new(
+                    span,
+                    "html!: unmatched `}` in text; write `}}` for a literal brace",
+                ))
+            }
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+    Ok(segments)
+}
+
+/// Split a placeholder into its expression and optional format spec:
+/// `count`, `items.len()`, `price:.2`.
+fn placeholder_expr(placeholder: &str, span: Span) -> Result<(Expr, String)> {
+    if placeholder.trim().is_empty() {
+        return Err(Error::new(
+            span,
+            "html!: empty `{}` in text; name the value, e.g. `{count}`",
+        ));
+    }
+    if let Ok(expr) = parse_expr(placeholder, span) {
+        return Ok((expr, String::new()));
+    }
+    // A single `:` (not part of a `::` path) starts the format spec
+    let bytes = placeholder.as_bytes();
+    let split = (0..bytes.len()).rev().find(|&i| {
+        bytes[i] == b':' && bytes.get(i + 1) != Some(&b':') && (i == 0 || bytes[i - 1] != b':')
+    });
+    if let Some(i) = split {
+        if let Ok(expr) = parse_expr(&placeholder[..i], span) {
+            return Ok((expr, placeholder[i + 1..].to_string()));
+        }
+    }
+    Err(Error::new(
+        span,
+        format!("html!: `{{{}}}` in text is not an expression", placeholder),
+    ))
+}
+
+/// Parse source text from inside a literal, giving its tokens the literal's
+/// span so names resolve (and errors point) at the call site.
+fn parse_expr(source: &str, span: Span) -> Result<Expr> {
+    let tokens = TokenStream::from_str(source).map_err(|err| Error::new(span, err))?;
+    syn::parse2(respan(tokens, span))
+}
+
+fn respan(tokens: TokenStream, span: Span) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|mut tt| {
+            if let TokenTree::Group(g) = &tt {
+                let mut group = Group::new(g.delimiter(), respan(g.stream(), span));
+                group.set_span(span);
+                tt = TokenTree::Group(group);
+            } else {
+                tt.set_span(span);
+            }
+            tt
+        })
+        .collect()
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use common::{Opcode, PropValue};
+use wasm_html_macro::{html, Bind, Handlers};
+
+#[derive(Default, Bind)]
+struct Form {
+    #[bind]
+    name: String,
+    #[bind]
+    agreed: bool,
+    #[bind]
+    age: i32,
+    submitted: bool,
+}
+
+#[derive(Handlers)]
+enum Msg {
+    Submit,
+}
+
+impl Form {
+    fn view(&self) -> Vec<Opcode> {
+        html! {
+            <input bind:value={self.name}>
+            <input type="checkbox" bind:checked={self.agreed} on:change={Msg::Submit}>
+        }
+    }
+}
+
+// Not a method: the binding names the state it reads
+fn age_field(form: &Form) -> Vec<Opcode> {
+    html! { <input type="number" bind:value={form.age}> }
+}
+
+fn bound(ops: &[Opcode]) -> Vec<(&str, &PropValue)> {
+    ops.iter()
+        .filter_map(|op| match op {
+            Opcode::TypedAttrSlot((name, value)) => Some((name.as_str(), value)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn binds_read_and_write_the_component_state() {
+    let mut form = Form::default();
+    let ops = form.view();
+    // The handler is the field's name
+    assert!(ops.contains(&Opcode::Bind(("value".into(), "name".into()))));
+    assert!(ops.contains(&Opcode::Bind(("checked".into(), "agreed".into()))));
+    assert_eq!(
+        bound(&ops),
+        [
+            ("value", &PropValue::Str(String::new())),
+            ("checked", &PropValue::Boolean(false)),
+        ]
+    );
+
+    assert!(form.bind("name", PropValue::Str("Ada".into())));
+    assert!(form.bind("agreed", PropValue::Boolean(true)));
+    // Not `#[bind]`, so not a handler
+    assert!(!form.bind("submitted", PropValue::Boolean(true)));
+    assert_eq!(form.name, "Ada");
+    assert!(form.agreed);
+    assert!(!form.submitted);
+    assert_eq!(
+        bound(&form.view()),
+        [
+            ("value", &PropValue::Str("Ada".into())),
+            ("checked", &PropValue::Boolean(true)),
+        ]
+    );
+}
+
+#[test]
+fn a_binding_reads_the_state_it_names() {
+    let mut a = Form::default();
+    let b = Form::default();
+    // An `<input>`'s value arrives as a string
+    a.bind("age", PropValue::Str(" 36 ".into()));
+    assert_eq!(a.age, 36);
+    a.bind("age", PropValue::Str("x".into()));
+    assert_eq!(a.age, 36);
+    assert_eq!(
+        age_field(&a),
+        [
+            Opcode::Open("input".into()),
+            Opcode::Attr(("type".into(), "number".into())),
+            Opcode::TypedAttrSlot(("value".into(), PropValue::Int(36))),
+            Opcode::Bind(("value".into(), "age".into())),
+            Opcode::Close,
+        ]
+    );
+    assert_eq!(bound(&age_field(&b)), [("value", &PropValue::Int(0))]);
+}
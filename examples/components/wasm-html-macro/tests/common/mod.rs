@@ -0,0 +1,31 @@
+//! Stand-ins for the `wit/dom.wit` bindings the macros expand against.
+
+#![allow(dead_code)]
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PropValue {
+    Int(i32),
+    Str(String),
+    Boolean(bool),
+    Float(f64),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Opcode {
+    Open(String),
+    Close,
+    Attr((String, String)),
+    Text(String),
+    Slot(String),
+    Event((String, String)),
+    Child(String),
+    Prop((String, PropValue)),
+    AttrSlot((String, String)),
+    Begin(String),
+    End,
+    TypedAttrSlot((String, PropValue)),
+    Bind((String, String)),
+    BeginKeyed((String, String)),
+    Skip(u32),
+    Seek((String, u32)),
+}
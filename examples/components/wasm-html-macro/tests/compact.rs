@@ -0,0 +1,65 @@
+mod common;
+
+use common::Opcode;
+use wasm_html_macro::html;
+
+const SENT: u64 = 1 << 63;
+
+fn view(state: &mut u64, items: &[&str], on: bool) -> Vec<Opcode> {
+    html! {
+        @compact(state)
+        <ul>
+            for item in items {
+                <li class="item">{item}</li>
+            }
+        </ul>
+        if on {
+            <b>"on"</b>
+        }
+    }
+}
+
+fn is_full(ops: &[Opcode]) -> bool {
+    ops.contains(&Opcode::Open("ul".into()))
+}
+
+#[test]
+fn the_full_stream_runs_until_every_site_has_an_instance() {
+    let mut state = 0;
+    assert!(is_full(&view(&mut state, &["a"], false)));
+    // The `for` site (bit 0) has a cached template, the `if` site hasn't
+    assert_eq!(state, SENT | 0b01);
+
+    assert!(is_full(&view(&mut state, &[], true)));
+    assert_eq!(state, SENT | 0b11);
+
+    let parts = view(&mut state, &["b"], true);
+    assert!(!is_full(&parts));
+    assert_eq!(state, SENT | 0b11);
+}
+
+#[test]
+fn the_parts_stream_has_markers_and_parts_only() {
+    let mut state = SENT | 0b11;
+    let begin = |id: &Opcode| match id {
+        Opcode::Begin(id) => id.clone(),
+        op => panic!("expected a begin, got {:?}", op),
+    };
+    let ops = view(&mut state, &["a", "b"], false);
+    let li = begin(&ops[0]);
+    assert_eq!(
+        ops,
+        [
+            Opcode::Begin(li.clone()),
+            Opcode::Slot("a".into()),
+            Opcode::End,
+            Opcode::Begin(li),
+            Opcode::Slot("b".into()),
+            Opcode::End,
+        ]
+    );
+    // A view reset to zero sends the full stream again
+    let mut state = 0;
+    assert!(is_full(&view(&mut state, &[], false)));
+    assert_eq!(state, SENT);
+}
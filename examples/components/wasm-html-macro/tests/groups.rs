@@ -0,0 +1,388 @@
+mod common;
+
+use common::{Opcode, PropValue};
+use wasm_html_macro::html;
+
+/// Opcodes in the compact notation of wasm-dom-host's tests, with template
+/// IDs replaced by `A`, `B`, ... in order of first appearance.
+fn shape(ops: &[Opcode]) -> Vec<String> {
+    let mut ids: Vec<String> = Vec::new();
+    let mut name = |id: &str| {
+        let idx = match ids.iter().position(|seen| seen == id) {
+            Some(idx) => idx,
+            None => {
+                ids.push(id.into());
+                ids.len() - 1
+            }
+        };
+        char::from(b'A' + idx as u8)
+    };
+    ops.iter()
+        .map(|op| match op {
+            Opcode::Open(tag) => format!("<{}", tag),
+            Opcode::Close => ">".into(),
+            Opcode::Attr((name, value)) => format!("@{}={}", name, value),
+            Opcode::Text(text) => format!("\"{}", text),
+            Opcode::Slot(text) => format!("{{{}", text),
+            Opcode::Event((name, handler)) => format!("!{}={}", name, handler),
+            Opcode::Child(name) => format!("+{}", name),
+            Opcode::Prop((name, PropValue::Str(value))) => format!("%{}={}", name, value),
+            Opcode::Prop((name, value)) => format!("%{}={:?}", name, value),
+            Opcode::Begin(id) => format!("[{}", name(id)),
+            Opcode::BeginKeyed((id, key)) => format!("[{}#{}", name(id), key),
+            Opcode::End => "]".into(),
+            Opcode::Skip(n) => format!(">>{}", n),
+            Opcode::Seek((id, n)) => format!("^{}@{}", name(id), n),
+            op => format!("{:?}", op),
+        })
+        .collect()
+}
+
+#[test]
+fn for_emits_an_instance_per_item() {
+    let items = ["a", "b"];
+    let ops = html! {
+        <ul>
+            for item in items {
+                <li>{item}</li>
+            }
+        </ul>
+    };
+    assert_eq!(
+        shape(&ops),
+        ["<ul", "[A", "<li", "{a", ">", "]", "[A", "<li", "{b", ">", "]", ">"]
+    );
+}
+
+#[test]
+fn an_empty_for_seeks_its_group() {
+    let items: [&str; 0] = [];
+    let ops = html! {
+        <ul>
+            for item in items {
+                <li>{item}</li>
+            }
+        </ul>
+    };
+    assert_eq!(shape(&ops), ["<ul", "^A@0", ">"]);
+}
+
+#[test]
+fn for_loops_nest() {
+    let rows = [vec!["a", "b"], vec![]];
+    let ops = html! {
+        <table>
+            for row in &rows {
+                <tr>
+                    for cell in row {
+                        <td>{cell}</td>
+                    }
+                </tr>
+            }
+        </table>
+    };
+    assert_eq!(
+        shape(&ops),
+        [
+            "<table", "[A", "<tr", "[B", "<td", "{a", ">", "]", "[B", "<td", "{b", ">", "]", ">",
+            "]", "[A", "<tr", "^B@0", ">", "]", ">"
+        ]
+    );
+}
+
+#[test]
+fn children_render_inside_for() {
+    let names = ["x", "y"];
+    let ops = html! {
+        for name in names {
+            <Badge label={name} on:pick="on_pick" />
+        }
+    };
+    assert_eq!(
+        shape(&ops),
+        [
+            "[A",
+            "+badge",
+            "%label=x",
+            "!pick=on_pick",
+            "]",
+            "[A",
+            "+badge",
+            "%label=y",
+            "!pick=on_pick",
+            "]"
+        ]
+    );
+}
+
+#[test]
+fn compact_parts_keep_nested_groups_and_children() {
+    let mut state = 0u64;
+    let rows = [vec!["a"]];
+    let render = |state: &mut u64| {
+        html! {
+            @compact(state)
+            for row in &rows {
+                <p>"row"</p>
+                for cell in row {
+                    <Badge label={cell} />
+                    <i>{cell}</i>
+                }
+            }
+        }
+    };
+    assert_eq!(
+        shape(&render(&mut state)),
+        ["[A", "<p", "\"row", ">", "[B", "+badge", "%label=a", "<i", "{a", ">", "]", "]"]
+    );
+    // Once every site has rendered an instance, only markers and parts
+    assert_eq!(
+        shape(&render(&mut state)),
+        ["[A", "[B", "+badge", "%label=a", "{a", "]", "]"]
+    );
+}
+
+#[test]
+fn a_keyed_for_sends_each_key_with_its_instance() {
+    let rows = [(7, "a"), (3, "b")];
+    let ops = html! {
+        <tbody>
+            for row in rows {
+                <tr key={row.0} class="row">{row.1}</tr>
+            }
+        </tbody>
+    };
+    // The key is the instance's, not an attribute of the <tr>
+    assert_eq!(
+        shape(&ops),
+        [
+            "<tbody",
+            "[A#7",
+            "<tr",
+            "@class=row",
+            "{a",
+            ">",
+            "]",
+            "[A#3",
+            "<tr",
+            "@class=row",
+            "{b",
+            ">",
+            "]",
+            ">"
+        ]
+    );
+}
+
+#[test]
+fn compact_parts_keep_their_keys() {
+    let mut state = 0u64;
+    let rows = ["x", "y"];
+    let render = |state: &mut u64| {
+        html! {
+            @compact(state)
+            for (i, row) in rows.iter().enumerate() {
+                <li key={i * 10}>{row}</li>
+            }
+        }
+    };
+    render(&mut state);
+    assert_eq!(
+        shape(&render(&mut state)),
+        ["[A#0", "{x", "]", "[A#10", "{y", "]"]
+    );
+}
+
+fn sparse(state: &mut u64, rows: &[&str], dirty: &[usize]) -> Vec<Opcode> {
+    html! {
+        @compact(state)
+        <ul>
+            for row in rows.iter() @dirty(dirty.iter().copied()) {
+                <li>{row}</li>
+            }
+        </ul>
+    }
+}
+
+#[test]
+fn dirty_is_ignored_in_the_full_stream() {
+    let mut state = 0u64;
+    assert_eq!(
+        shape(&sparse(&mut state, &["a", "b"], &[1])),
+        ["<ul", "[A", "<li", "{a", ">", "]", "[A", "<li", "{b", ">", "]", ">"]
+    );
+}
+
+#[test]
+fn dirty_seeks_the_first_change_and_skips_the_rest() {
+    let mut state = 0u64;
+    let rows = ["a", "b", "c", "d", "e"];
+    sparse(&mut state, &rows, &[]);
+    // Unsorted and repeated indices are fine
+    assert_eq!(
+        shape(&sparse(&mut state, &rows, &[3, 1, 1])),
+        ["^A@1", "[A", "{b", "]", ">>1", "[A", "{d", "]", ">>1"]
+    );
+    // Adjacent changes need no skip between them; none after the last row
+    assert_eq!(
+        shape(&sparse(&mut state, &rows, &[0, 1, 4])),
+        ["^A@0", "[A", "{a", "]", "[A", "{b", "]", ">>2", "[A", "{e", "]"]
+    );
+}
+
+#[test]
+fn dirty_past_the_end_is_ignored() {
+    let mut state = 0u64;
+    let rows = ["a", "b"];
+    sparse(&mut state, &rows, &[]);
+    // Nothing changed: the cursor moves past the last row
+    assert_eq!(shape(&sparse(&mut state, &rows, &[])), ["^A@2"]);
+    assert_eq!(shape(&sparse(&mut state, &rows, &[5])), ["^A@2"]);
+    assert_eq!(
+        shape(&sparse(&mut state, &rows, &[1, 7])),
+        ["^A@1", "[A", "{b", "]"]
+    );
+}
+
+fn branch(n: i32) -> Vec<Opcode> {
+    html! {
+        if n > 1 {
+            <b>"many"</b>
+        } else if n == 1 {
+            <i>"one"</i>
+        } else {
+            "none"
+        }
+    }
+}
+
+#[test]
+fn if_renders_one_branch_and_seeks_the_others() {
+    // Each branch is a group of its own; the untaken ones are left empty
+    // in place
+    assert_eq!(
+        shape(&branch(2)),
+        ["[A", "<b", "\"many", ">", "]", "^B@0", "^C@0"]
+    );
+    // After `branch(2)`, so the IDs keep their letters
+    let one = shape(&[branch(2), branch(1)].concat());
+    assert_eq!(one[7..], ["^A@0", "[B", "<i", "\"one", ">", "]", "^C@0"]);
+    let none = shape(&[branch(2), branch(0)].concat());
+    assert_eq!(none[7..], ["^A@0", "^B@0", "[C", "\"none", "]"]);
+}
+
+#[test]
+fn if_without_else_can_render_nothing() {
+    let show = |on: bool| html! { <p>if on { <b>"on"</b> }</p> };
+    assert_eq!(
+        shape(&show(true)),
+        ["<p", "[A", "<b", "\"on", ">", "]", ">"]
+    );
+    assert_eq!(shape(&show(false)), ["<p", "^A@0", ">"]);
+}
+
+#[test]
+fn if_nests_in_for_and_holds_children() {
+    let items = [1, 2];
+    let ops = html! {
+        for n in items {
+            if n == 2 {
+                <Badge count={n} />
+            }
+        }
+    };
+    assert_eq!(
+        shape(&ops),
+        [
+            "[A",
+            "^B@0",
+            "]",
+            "[A",
+            "[B",
+            "+badge",
+            "%count=Int(2)",
+            "]",
+            "]"
+        ]
+    );
+}
+
+enum Load {
+    Loading,
+    Failed(&'static str),
+    Loaded(Vec<&'static str>),
+}
+
+fn status(load: &Load) -> Vec<Opcode> {
+    html! {
+        match load {
+            Load::Loading => <p>"loading"</p>,
+            Load::Failed(why) => <p class="error">{why}</p>,
+            Load::Loaded(items) => <ul>for item in items { <li>{item}</li> }</ul>,
+        }
+    }
+}
+
+#[test]
+fn match_renders_one_arm_and_seeks_the_others() {
+    assert_eq!(
+        shape(&status(&Load::Loading)),
+        ["[A", "<p", "\"loading", ">", "]", "^B@0", "^C@0"]
+    );
+    // After the first render, so the IDs keep their letters
+    let failed = shape(&[status(&Load::Loading), status(&Load::Failed("oops"))].concat());
+    assert_eq!(
+        failed[7..],
+        [
+            "^A@0",
+            "[B",
+            "<p",
+            "@class=error",
+            "{oops",
+            ">",
+            "]",
+            "^C@0"
+        ]
+    );
+}
+
+#[test]
+fn match_arms_hold_groups_of_their_own() {
+    let ops = [
+        status(&Load::Loading),
+        status(&Load::Loaded(vec!["a"])),
+        status(&Load::Loaded(vec![])),
+    ]
+    .concat();
+    let loaded = shape(&ops);
+    // The arm's `for` is the fourth site, `D`
+    assert_eq!(
+        loaded[7..18],
+        ["^A@0", "^B@0", "[C", "<ul", "[D", "<li", "{a", ">", "]", ">", "]"]
+    );
+    assert_eq!(
+        loaded[18..],
+        ["^A@0", "^B@0", "[C", "<ul", "^D@0", ">", "]"]
+    );
+}
+
+fn first_id(ops: &[Opcode]) -> String {
+    ops.iter()
+        .find_map(|op| match op {
+            Opcode::Begin(id) => Some(id.clone()),
+            _ => None,
+        })
+        .expect("a group")
+}
+
+#[test]
+fn template_ids_are_per_call_site() {
+    let list = |items: &[&str]| html! { for item in items { <li>{item}</li> } };
+    let same = html! { for item in ["a"] { <li>{item}</li> } };
+    let also = html! { for item in ["a"] { <li>{item}</li> } };
+    // Stable across renders of one call
+    assert_eq!(first_id(&list(&["a"])), first_id(&list(&["b"])));
+    // Identical markup at two calls doesn't share a template
+    assert_ne!(first_id(&same), first_id(&also));
+    assert!(first_id(&same).starts_with("li-"));
+}
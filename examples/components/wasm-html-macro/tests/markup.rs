@@ -0,0 +1,99 @@
+mod common;
+
+use common::{Opcode, PropValue};
+use wasm_html_macro::html;
+
+fn open(tag: &str) -> Opcode {
+    Opcode::Open(tag.into())
+}
+
+fn attr(name: &str, value: &str) -> Opcode {
+    Opcode::Attr((name.into(), value.into()))
+}
+
+fn text(text: &str) -> Opcode {
+    Opcode::Text(text.into())
+}
+
+#[test]
+fn void_elements_close_themselves() {
+    let ops = html! {
+        <p>
+            <input type="text">
+            <br>
+            <img src="a.png" />
+            "after"
+        </p>
+    };
+    assert_eq!(
+        ops,
+        [
+            open("p"),
+            open("input"),
+            attr("type", "text"),
+            Opcode::Close,
+            open("br"),
+            Opcode::Close,
+            open("img"),
+            attr("src", "a.png"),
+            Opcode::Close,
+            text("after"),
+            Opcode::Close,
+        ]
+    );
+}
+
+#[test]
+fn close_tags_match_their_open_tags() {
+    let ops = html! { <my-list><li>"a"</li></my-list><>"b"</> };
+    assert_eq!(
+        ops,
+        [
+            open("my-list"),
+            open("li"),
+            text("a"),
+            Opcode::Close,
+            Opcode::Close,
+            text("b"),
+        ]
+    );
+}
+
+#[test]
+fn attribute_names_take_hyphens_and_namespaces() {
+    let ops = html! {
+        <meta http-equiv="refresh" aria-hidden="true" data-row-id="7">
+        <use xlink:href="#icon" "@click"="go" />
+    };
+    assert_eq!(
+        ops,
+        [
+            open("meta"),
+            attr("http-equiv", "refresh"),
+            attr("aria-hidden", "true"),
+            attr("data-row-id", "7"),
+            Opcode::Close,
+            open("use"),
+            attr("xlink:href", "#icon"),
+            attr("@click", "go"),
+            Opcode::Close,
+        ]
+    );
+}
+
+#[test]
+fn dynamic_attribute_names_take_hyphens_and_namespaces() {
+    let id = "7";
+    let href = "#icon";
+    let ops = html! { <use data-id={id} xlink:href={href} on:click="go" /> };
+    assert_eq!(
+        ops,
+        [
+            open("use"),
+            Opcode::AttrSlot(("data-id".into(), "7".into())),
+            Opcode::AttrSlot(("xlink:href".into(), "#icon".into())),
+            Opcode::Event(("click".into(), "go".into())),
+            Opcode::Close,
+        ]
+    );
+}
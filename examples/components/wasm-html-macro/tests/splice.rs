@@ -0,0 +1,36 @@
+mod common;
+
+use common::Opcode;
+use wasm_html_macro::html;
+
+fn badge(on: bool) -> Vec<Opcode> {
+    html! { if on { <b>"!"</b> } }
+}
+
+fn begins(ops: &[Opcode]) -> Vec<&str> {
+    ops.iter()
+        .filter_map(|op| match op {
+            Opcode::Begin(id) => Some(id.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn each_splice_site_gets_its_own_template_ids() {
+    let ops = html! {
+        <div>{..badge(true)}</div>
+        <div>{..badge(true)}</div>
+    };
+    let ids = begins(&ops);
+    assert_eq!(ids.len(), 2);
+    assert_ne!(ids[0], ids[1]);
+    assert!(ids.iter().all(|id| id.starts_with(begins(&badge(true))[0])));
+
+    // The same site keeps its ID from render to render
+    let again = html! {
+        <div>{..badge(false)}</div>
+        <div>{..badge(true)}</div>
+    };
+    assert_eq!(begins(&again), [ids[1]]);
+}
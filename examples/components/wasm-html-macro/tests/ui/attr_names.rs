@@ -0,0 +1,7 @@
+use wasm_html_macro::html;
+
+fn main() {
+    let _ = html! { <p data-1="x"></p> };
+    let _ = html! { <p xlink:="x"></p> };
+    let _ = html! { <p on:click=go></p> };
+}
@@ -0,0 +1,10 @@
+#[path = "../common/mod.rs"]
+mod common;
+
+use common::*;
+use wasm_html_macro::html;
+
+fn main() {
+    let name = "Ada";
+    let _: Vec<Opcode> = html! { <input value={name} bind:value={form.name}> };
+}
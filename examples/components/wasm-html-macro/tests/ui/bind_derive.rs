@@ -0,0 +1,19 @@
+use wasm_html_macro::Bind;
+
+#[derive(Bind)]
+struct NoFields {
+    name: String,
+}
+
+#[derive(Bind)]
+enum Msg {
+    Name,
+}
+
+#[derive(Bind)]
+struct Form {
+    #[bind(name)]
+    name: String,
+}
+
+fn main() {}
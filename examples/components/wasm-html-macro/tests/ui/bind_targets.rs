@@ -0,0 +1,10 @@
+use wasm_html_macro::html;
+
+fn main() {
+    // A `bind:` target is a field of the state, reached through one name
+    let _ = html! { <input bind:value={Msg::Name}> };
+    let _ = html! { <input bind:value={self.form.name}> };
+    let _ = html! { <input bind:value={draft}> };
+    let _ = html! { <input bind:value="draft"> };
+    let _ = html! { <input bind:title={self.title}> };
+}
@@ -0,0 +1,24 @@
+#[path = "../common/mod.rs"]
+mod common;
+
+use common::*;
+use wasm_html_macro::{html, Bind};
+
+#[derive(Default, Bind)]
+struct Form {
+    #[bind]
+    name: String,
+    notes: String,
+}
+
+impl Form {
+    fn view(&self) -> Vec<Opcode> {
+        html! { <input bind:value={self.notes}> }
+    }
+}
+
+fn main() {
+    let mut form = Form::default();
+    let _ = form.view();
+    let _ = form.bind("name", PropValue::Str(form.notes.clone()));
+}
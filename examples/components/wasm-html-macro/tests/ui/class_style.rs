@@ -0,0 +1,10 @@
+use wasm_html_macro::html;
+
+fn main() {
+    // A `class:` toggle needs a braced condition
+    let _ = html! { <p class:on="yes"></p> };
+    let _ = html! { <p class:on></p> };
+    // A `style:` declaration needs a value
+    let _ = html! { <p style:color></p> };
+    let _ = html! { <p style:color=red></p> };
+}
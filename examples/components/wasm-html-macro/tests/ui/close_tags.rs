@@ -0,0 +1,9 @@
+use wasm_html_macro::html;
+
+fn main() {
+    let _ = html! { <div><p>"a"</div> };
+    let _ = html! { <input type="text"></input> };
+    let _ = html! { <ul><li>"a"</li> };
+    let _ = html! { "a"</p> };
+    let _ = html! { <>"a"</div> };
+}
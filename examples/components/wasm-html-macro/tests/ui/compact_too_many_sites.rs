@@ -0,0 +1,80 @@
+#[path = "../common/mod.rs"]
+mod common;
+
+use common::*;
+use wasm_html_macro::html;
+
+// One site past the 63 the state word can track
+fn view(state: &mut u64, on: bool) -> Vec<Opcode> {
+    html! {
+        @compact(state)
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+        if on { <b></b> }
+    }
+}
+
+fn main() {
+    let _ = view(&mut 0, true);
+}
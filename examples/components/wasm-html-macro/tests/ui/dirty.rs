@@ -0,0 +1,8 @@
+use wasm_html_macro::html;
+
+fn main() {
+    let rows = [1, 2];
+    let _ = html! { for row in rows @dirty { <li>{row}</li> } };
+    let _ = html! { for row in rows @dirty() { <li>{row}</li> } };
+    let _ = html! { for row in rows @changed([0]) { <li>{row}</li> } };
+}
@@ -0,0 +1,12 @@
+use wasm_html_macro::html;
+
+fn main() {
+    let rows = [1, 2];
+    let changed = [0];
+    // Only the root element of a `for` body takes a key
+    let _ = html! { <p key={1}></p> };
+    let _ = html! { for row in rows { <tr><td key={row}></td></tr> } };
+    let _ = html! { for row in rows { if row > 1 { <tr key={row}></tr> } } };
+    // The runtime matches keyed rows by key, so there is nothing to mark dirty
+    let _ = html! { for row in rows @dirty(changed) { <tr key={row}></tr> } };
+}
@@ -0,0 +1,10 @@
+use wasm_html_macro::html;
+
+fn main() {
+    let _ = html! { <div class="a" };
+    let _ = html! { <Badge count 5 /> };
+    let _ = html! { <p class=5></p> };
+    let _ = html! { <Badge count={1} };
+    let _ = html! { <p>"a" + "b"</p> };
+    let _ = html! { for x { <li>{x}</li> } };
+}
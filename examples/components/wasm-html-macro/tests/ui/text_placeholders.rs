@@ -0,0 +1,15 @@
+#[path = "../common/mod.rs"]
+mod common;
+
+use common::*;
+use wasm_html_macro::html;
+
+fn main() {
+    let count = 1;
+    let _ = html! { <p>"Count: {count"</p> };
+    let _ = html! { <p>"Count: }"</p> };
+    let _ = html! { <p>"Count: {}"</p> };
+    let _ = html! { <p>"Count: {count +}"</p> };
+    // The spec goes to `format!`, which rejects it
+    let _ = html! { <p>"Count: {count:q}"</p> };
+}
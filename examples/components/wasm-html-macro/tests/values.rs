@@ -0,0 +1,181 @@
+mod common;
+
+use common::{Opcode, PropValue};
+use wasm_html_macro::html;
+
+fn slots(ops: &[Opcode]) -> Vec<Opcode> {
+    ops.iter()
+        .filter(|op| {
+            matches!(
+                op,
+                Opcode::AttrSlot(_) | Opcode::TypedAttrSlot(_) | Opcode::Prop(_)
+            )
+        })
+        .cloned()
+        .collect()
+}
+
+fn typed(name: &str, value: PropValue) -> Opcode {
+    Opcode::TypedAttrSlot((name.into(), value))
+}
+
+fn string(name: &str, value: &str) -> Opcode {
+    Opcode::AttrSlot((name.into(), value.into()))
+}
+
+#[test]
+fn attribute_values_keep_their_type() {
+    let count: i32 = 3;
+    let counts = [4];
+    let ratio = 0.5;
+    let busy = false;
+    let id: u32 = 7;
+    let name = String::from("Ada");
+    let ops = html! {
+        <input
+            value={count}
+            min={counts.first().unwrap()}
+            step={ratio}
+            disabled={busy}
+            hidden={count > 2}
+            data-id={id}
+            title={name}
+            alt={"x"}
+        >
+    };
+    assert_eq!(
+        slots(&ops),
+        [
+            typed("value", PropValue::Int(3)),
+            typed("min", PropValue::Int(4)),
+            typed("step", PropValue::Float(0.5)),
+            typed("disabled", PropValue::Boolean(false)),
+            typed("hidden", PropValue::Boolean(true)),
+            string("data-id", "7"),
+            string("title", "Ada"),
+            string("alt", "x"),
+        ]
+    );
+}
+
+#[test]
+fn child_props_keep_their_type() {
+    let count = 2;
+    let label = "two";
+    let ops = html! { <Badge count={count} shown={count != 0} label={label} kind="plain" /> };
+    let prop = |name: &str, value| Opcode::Prop((name.into(), value));
+    assert_eq!(
+        slots(&ops),
+        [
+            prop("count", PropValue::Int(2)),
+            prop("shown", PropValue::Boolean(true)),
+            prop("label", PropValue::Str("two".into())),
+            prop("kind", PropValue::Str("plain".into())),
+        ]
+    );
+}
+
+#[test]
+fn class_toggles_merge_with_the_class_attribute() {
+    let (selected, busy) = (true, false);
+    let kind = "row";
+    let ops = html! {
+        <div>
+            <p class="item" class:selected={selected} class:busy={busy}></p>
+            <p class={kind} class:busy={!busy}></p>
+            <p class:selected={selected} class:busy={busy}></p>
+            <p class="" class:busy={busy}></p>
+        </div>
+    };
+    assert_eq!(
+        slots(&ops),
+        [
+            string("class", "item selected"),
+            string("class", "row busy"),
+            string("class", "selected"),
+            string("class", ""),
+        ]
+    );
+}
+
+#[test]
+fn class_list_replaces_the_attribute_in_place() {
+    let on = true;
+    let ops = html! { <p id="a" class="x" title="t" class:on={on}></p> };
+    // The static `class` is folded into the list, which ends the attributes
+    assert_eq!(
+        ops,
+        [
+            Opcode::Open("p".into()),
+            Opcode::Attr(("id".into(), "a".into())),
+            Opcode::Attr(("title".into(), "t".into())),
+            string("class", "x on"),
+            Opcode::Close,
+        ]
+    );
+}
+
+#[test]
+fn style_declarations_merge_with_the_style_attribute() {
+    let (width, color) = (40, "red");
+    let ops = html! {
+        <div>
+            <p style="margin: 0;  " style:width={format!("{}px", width)} style:color={color}></p>
+            <p style:display="none"></p>
+            <p style={format!("top: {}px", width)} style:color="blue"></p>
+        </div>
+    };
+    assert_eq!(
+        slots(&ops),
+        [
+            string("style", "margin: 0; width: 40px; color: red"),
+            string("style", "display: none"),
+            string("style", "top: 40px; color: blue"),
+        ]
+    );
+}
+
+fn text(value: &str) -> Opcode {
+    Opcode::Text(value.into())
+}
+
+fn slot(value: &str) -> Opcode {
+    Opcode::Slot(value.into())
+}
+
+#[test]
+fn text_placeholders_become_a_slot_between_static_text() {
+    let (count, items) = (3, ["a", "b"]);
+    assert_eq!(
+        html! { "Count: {count} items" },
+        [text("Count: "), slot("3"), text(" items")]
+    );
+    assert_eq!(html! { "{count}" }, [slot("3")]);
+    // Text between placeholders belongs to the slot
+    assert_eq!(
+        html! { "{items.len()} of {count}!" },
+        [slot("2 of 3"), text("!")]
+    );
+    assert_eq!(
+        html! { "{{literal}} {i32::MAX} }}" },
+        [text("{literal} "), slot("2147483647"), text(" }")]
+    );
+}
+
+#[test]
+fn text_placeholders_take_format_specs() {
+    let price = 2.5;
+    let id = 255;
+    let name = "ab";
+    let items = ["a"];
+    assert_eq!(html! { "{price:.2}" }, [slot("2.50")]);
+    assert_eq!(html! { "#{id:04}" }, [text("#"), slot("0255")]);
+    assert_eq!(html! { "{id:#x}" }, [slot("0xff")]);
+    assert_eq!(
+        html! { "[{name:>4}]" },
+        [text("["), slot("  ab"), text("]")]
+    );
+    assert_eq!(html! { "{items:?}" }, [slot("[\"a\"]")]);
+    // A `::` path isn't a spec, but the `:` after it is
+    assert_eq!(html! { "{i32::MAX:e}" }, [slot("2.147483647e9")]);
+}
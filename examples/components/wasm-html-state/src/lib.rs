@@ -0,0 +1,30 @@
+//! State for `html!` components: safe global storage and containers that
+//! record what changed since the last `render`.
+//!
+//! ```ignore
+//! static MODEL: State<Model> = State::new(Model {
+//!     rows: TrackedVec::new(),
+//!     filter: Signal::new(String::new()),
+//!     templates: 0,
+//! });
+//!
+//! fn render() -> Vec<Opcode> {
+//!     let mut model = MODEL.lock();
+//!     let mut dirty = model.rows.take_dirty();
+//!     if model.filter.take_dirty() {
+//!         // Every row's visibility may have changed
+//!         dirty.extend(0..model.rows.len());
+//!     }
+//!     html! { @compact(&mut model.templates)
+//!         for row in model.rows.iter() @dirty(dirty) { ... }
+//!     }
+//! }
+//! ```
+
+mod signal;
+mod state;
+mod tracked_vec;
+
+pub use signal::Signal;
+pub use state::State;
+pub use tracked_vec::TrackedVec;
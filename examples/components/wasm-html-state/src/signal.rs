@@ -0,0 +1,69 @@
+use std::ops::Deref;
+
+/// A single value that records whether it was written since the last
+/// [`take_dirty`](Signal::take_dirty), as [`TrackedVec`](crate::TrackedVec)
+/// does for rows: e.g. a selection or filter whose change means re-sending
+/// the parts that depend on it.
+///
+/// Reads go through `Deref<Target = T>`; every write marks the signal
+/// dirty, whether or not the value is different.
+pub struct Signal<T> {
+    value: T,
+    dirty: bool,
+}
+
+impl<T> Signal<T> {
+    /// A signal holding `value`, clean until first written.
+    pub const fn new(value: T) -> Self {
+        Signal {
+            value,
+            dirty: false,
+        }
+    }
+
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// Store a new value, marking the signal dirty.
+    pub fn set(&mut self, value: T) {
+        self.replace(value);
+    }
+
+    /// Store a new value and return the old one, e.g. to mark the rows the
+    /// previous value affected.
+    pub fn replace(&mut self, value: T) -> T {
+        self.dirty = true;
+        std::mem::replace(&mut self.value, value)
+    }
+
+    /// Modify the value in place, marking the signal dirty.
+    pub fn update<R>(&mut self, f: impl FnOnce(&mut T) -> R) -> R {
+        self.dirty = true;
+        f(&mut self.value)
+    }
+
+    /// Whether the value was written since the last `take_dirty`.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Whether the value was written since the last call, clearing the mark.
+    pub fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+}
+
+impl<T> Deref for Signal<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: Default> Default for Signal<T> {
+    fn default() -> Self {
+        Signal::new(T::default())
+    }
+}
@@ -0,0 +1,26 @@
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// A `static` home for component state, in place of `static mut`.
+///
+/// Components run single-threaded, so the lock is never contended; it only
+/// makes the static safe to share. Locking it again while a guard is alive
+/// (e.g. from a helper called during `render`) deadlocks or panics.
+pub struct State<T>(Mutex<T>);
+
+impl<T> State<T> {
+    pub const fn new(value: T) -> Self {
+        State(Mutex::new(value))
+    }
+
+    /// Borrow the state until the guard is dropped.
+    pub fn lock(&self) -> MutexGuard<'_, T> {
+        // A panic in a handler aborts the component anyway; don't turn it
+        // into a second one here
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Run `f` with the state borrowed.
+    pub fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        f(&mut self.lock())
+    }
+}
@@ -0,0 +1,125 @@
+use std::ops::Deref;
+
+/// A `Vec` that records which indices changed since the last
+/// [`take_dirty`](TrackedVec::take_dirty), for `html!`'s `@dirty(...)`.
+///
+/// Reads go through `Deref<Target = [T]>`; every write marks the rows whose
+/// content it changed. Operations that shift rows (`insert`, `remove`,
+/// `retain`) mark every row after the first one moved, since a non-keyed
+/// list is updated by position.
+pub struct TrackedVec<T> {
+    items: Vec<T>,
+    dirty: Vec<usize>,
+}
+
+impl<T> TrackedVec<T> {
+    pub const fn new() -> Self {
+        TrackedVec {
+            items: Vec::new(),
+            dirty: Vec::new(),
+        }
+    }
+
+    /// The indices changed since the last call, possibly unsorted and with
+    /// repeats (`@dirty` sorts them), and indices past the end if the list
+    /// shrank.
+    pub fn take_dirty(&mut self) -> Vec<usize> {
+        std::mem::take(&mut self.dirty)
+    }
+
+    /// Mark row `index` changed, for state kept outside the row (e.g. a
+    /// selection) that its view depends on.
+    pub fn mark(&mut self, index: usize) {
+        self.dirty.push(index);
+    }
+
+    /// Mark every row changed.
+    pub fn mark_all(&mut self) {
+        self.dirty.extend(0..self.items.len());
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        let item = self.items.get_mut(index)?;
+        self.dirty.push(index);
+        Some(item)
+    }
+
+    pub fn push(&mut self, item: T) {
+        self.dirty.push(self.items.len());
+        self.items.push(item);
+    }
+
+    pub fn insert(&mut self, index: usize, item: T) {
+        self.items.insert(index, item);
+        self.dirty.extend(index..self.items.len());
+    }
+
+    pub fn remove(&mut self, index: usize) -> T {
+        let item = self.items.remove(index);
+        self.dirty.extend(index..self.items.len());
+        item
+    }
+
+    pub fn swap(&mut self, a: usize, b: usize) {
+        self.items.swap(a, b);
+        self.dirty.extend([a, b]);
+    }
+
+    pub fn retain(&mut self, mut f: impl FnMut(&T) -> bool) {
+        let mut first_removed = None;
+        let mut index = 0;
+        self.items.retain(|item| {
+            let keep = f(item);
+            if !keep && first_removed.is_none() {
+                first_removed = Some(index);
+            }
+            index += 1;
+            keep
+        });
+        if let Some(first) = first_removed {
+            self.dirty.extend(first..self.items.len());
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.dirty.clear();
+    }
+
+    /// Replace the contents, marking every row changed.
+    pub fn replace(&mut self, items: Vec<T>) {
+        self.items = items;
+        self.dirty.clear();
+        self.mark_all();
+    }
+}
+
+impl<T> Extend<T> for TrackedVec<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let start = self.items.len();
+        self.items.extend(iter);
+        self.dirty.extend(start..self.items.len());
+    }
+}
+
+impl<T> Deref for TrackedVec<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.items
+    }
+}
+
+impl<T> Default for TrackedVec<T> {
+    fn default() -> Self {
+        TrackedVec::new()
+    }
+}
+
+impl<T> From<Vec<T>> for TrackedVec<T> {
+    fn from(items: Vec<T>) -> Self {
+        let mut vec = TrackedVec::new();
+        vec.replace(items);
+        vec
+    }
+}
@@ -0,0 +1,48 @@
+use wasm_html_state::Signal;
+
+#[test]
+fn a_new_signal_is_clean() {
+    let mut signal = Signal::new(1);
+    assert!(!signal.is_dirty());
+    assert!(!signal.take_dirty());
+    assert_eq!(*signal, 1);
+    assert_eq!(Signal::<String>::default().get(), "");
+}
+
+#[test]
+fn writes_mark_it_dirty_until_taken() {
+    let mut signal = Signal::new(1);
+    signal.set(2);
+    assert!(signal.is_dirty());
+    assert!(signal.take_dirty());
+    assert!(!signal.is_dirty());
+    assert!(!signal.take_dirty());
+    assert_eq!(*signal, 2);
+}
+
+#[test]
+fn replace_returns_the_old_value() {
+    let mut signal = Signal::new("a");
+    assert_eq!(signal.replace("b"), "a");
+    assert!(signal.take_dirty());
+    assert_eq!(*signal, "b");
+}
+
+#[test]
+fn update_modifies_in_place() {
+    let mut signal = Signal::new(vec![1]);
+    let len = signal.update(|v| {
+        v.push(2);
+        v.len()
+    });
+    assert_eq!(len, 2);
+    assert_eq!(*signal, [1, 2]);
+    assert!(signal.take_dirty());
+}
+
+#[test]
+fn writing_the_same_value_still_marks_it() {
+    let mut signal = Signal::new(3);
+    signal.set(3);
+    assert!(signal.take_dirty());
+}
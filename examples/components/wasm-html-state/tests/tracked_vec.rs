@@ -0,0 +1,104 @@
+use wasm_html_state::TrackedVec;
+
+/// A vec of `0..n` with nothing marked.
+fn clean(n: u32) -> TrackedVec<u32> {
+    let mut vec: TrackedVec<u32> = (0..n).collect::<Vec<_>>().into();
+    vec.take_dirty();
+    vec
+}
+
+fn dirty(vec: &mut TrackedVec<u32>) -> Vec<usize> {
+    let mut dirty = vec.take_dirty();
+    dirty.sort_unstable();
+    dirty.dedup();
+    dirty
+}
+
+#[test]
+fn take_dirty_resets() {
+    let mut vec: TrackedVec<u32> = vec![1, 2].into();
+    assert_eq!(dirty(&mut vec), [0, 1]);
+    assert_eq!(dirty(&mut vec), []);
+}
+
+#[test]
+fn push_marks_the_new_row() {
+    let mut vec = clean(2);
+    vec.push(2);
+    assert_eq!(dirty(&mut vec), [2]);
+    assert_eq!(*vec, [0, 1, 2]);
+}
+
+#[test]
+fn insert_marks_the_shifted_rows() {
+    let mut vec = clean(4);
+    vec.insert(1, 9);
+    assert_eq!(dirty(&mut vec), [1, 2, 3, 4]);
+    assert_eq!(*vec, [0, 9, 1, 2, 3]);
+}
+
+#[test]
+fn remove_marks_the_shifted_rows() {
+    let mut vec = clean(4);
+    assert_eq!(vec.remove(1), 1);
+    assert_eq!(dirty(&mut vec), [1, 2]);
+
+    // Removing the last row leaves nothing to mark
+    vec.remove(2);
+    assert_eq!(dirty(&mut vec), []);
+    assert_eq!(*vec, [0, 2]);
+}
+
+#[test]
+fn retain_marks_from_the_first_removed() {
+    let mut vec = clean(6);
+    vec.retain(|&n| n != 2 && n != 4);
+    assert_eq!(dirty(&mut vec), [2, 3]);
+    assert_eq!(*vec, [0, 1, 3, 5]);
+
+    vec.retain(|_| true);
+    assert_eq!(dirty(&mut vec), []);
+}
+
+#[test]
+fn swap_marks_both_rows() {
+    let mut vec = clean(4);
+    vec.swap(0, 3);
+    assert_eq!(dirty(&mut vec), [0, 3]);
+    assert_eq!(*vec, [3, 1, 2, 0]);
+}
+
+#[test]
+fn get_mut_marks_the_row() {
+    let mut vec = clean(3);
+    *vec.get_mut(1).unwrap() = 7;
+    assert_eq!(dirty(&mut vec), [1]);
+    assert_eq!(*vec, [0, 7, 2]);
+
+    assert!(vec.get_mut(3).is_none());
+    assert_eq!(dirty(&mut vec), []);
+}
+
+#[test]
+fn clear_drops_pending_marks() {
+    let mut vec = clean(3);
+    vec.push(3);
+    vec.clear();
+    assert_eq!(dirty(&mut vec), []);
+    assert!(vec.is_empty());
+}
+
+#[test]
+fn bulk_writes_mark_what_they_touch() {
+    let mut vec = clean(2);
+    vec.extend([2, 3]);
+    assert_eq!(dirty(&mut vec), [2, 3]);
+
+    vec.replace(vec![5, 6, 7]);
+    assert_eq!(dirty(&mut vec), [0, 1, 2]);
+
+    vec.mark(1);
+    assert_eq!(dirty(&mut vec), [1]);
+    vec.mark_all();
+    assert_eq!(dirty(&mut vec), [0, 1, 2]);
+}
@@ -34,6 +34,11 @@ pub const OP_PROP: u8 = 7;
 pub const OP_ATTR_SLOT: u8 = 8;
 pub const OP_BEGIN: u8 = 9;
 pub const OP_END: u8 = 10;
+pub const OP_TYPED_ATTR_SLOT: u8 = 11;
+pub const OP_BIND: u8 = 12;
+pub const OP_BEGIN_KEYED: u8 = 13;
+pub const OP_SKIP: u8 = 14;
+pub const OP_SEEK: u8 = 15;
 
 // --- Comptime HTML parser ---
 
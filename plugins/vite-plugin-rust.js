@@ -34,9 +34,14 @@ export default function rustPlugin() {
       const crateName = getCrateName(crateDir);
       const coreWasm = join(crateDir, 'target/wasm32-unknown-unknown/release', `${crateName}.wasm`);
 
-      // Determine world from source
-      let world = 'pure-component';
-      if (source.includes('"counter-app"')) world = 'counter-app';
+      // Determine world from source. Like #[component]: it exports
+      // `instances`, and components that use the host are leaves.
+      const flavour = source.includes('#[component') ? 'instances' : 'component';
+      const leaf = /\b(event_query|emit|event_detail)\b/.test(source);
+      let world = `${leaf ? 'leaf' : 'pure'}-${flavour}`;
+      const named = source.match(/"((?:leaf|pure)-(?:component|instances))"/);
+      if (named) world = named[1];
+      else if (source.includes('"counter-app"')) world = 'counter-app';
       else if (source.includes('"rust-counter"')) world = 'rust-counter';
 
       // 2. Embed WIT
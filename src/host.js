@@ -1,4 +1,6 @@
 let currentEvent = null;
+let currentDetail = [];
+let emitted = [];
 
 export function eventQuery(path) {
   if (!currentEvent) return '';
@@ -10,6 +12,25 @@ export function eventQuery(path) {
   return value == null ? '' : String(value);
 }
 
-export function setCurrentEvent(event) {
+// Queued while an event is being handled; the runtime delivers them to the
+// parent afterwards (see takeEmitted).
+export function emit(name, detail) {
+  if (currentEvent) {
+    emitted.push({ name, detail });
+  }
+}
+
+export function eventDetail() {
+  return currentDetail;
+}
+
+export function setCurrentEvent(event, detail = []) {
   currentEvent = event;
+  currentDetail = detail;
+}
+
+export function takeEmitted() {
+  const events = emitted;
+  emitted = [];
+  return events;
 }
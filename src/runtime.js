@@ -5,6 +5,13 @@
  *   renderer.render()       → Array<Opcode>
  *   renderer.handleEvent(s) → void
  *
+ * or an `instances` interface, whose `Component` resource has the same
+ * methods; each mount constructs its own, so instances don't share state:
+ *   new instances.Component(props)
+ *   component.handleBind(s, v) → void
+ *
+ * Only an instance has `handleBind`, so only it can use `bind` opcodes.
+ *
  * Opcodes are structured JS objects with a `tag` and optional `val`:
  *   { tag: 'open',      val: 'div' }
  *   { tag: 'close' }
@@ -13,14 +20,152 @@
  *   { tag: 'slot',      val: '42' }
  *   { tag: 'event',     val: ['click', 'on_increment'] }
  *   { tag: 'child',     val: 'zig-child' }
+ *   { tag: 'prop',      val: ['initial', { tag: 'int', val: 5 }] }
  *   { tag: 'attr-slot', val: ['class', 'active'] }
+ *   { tag: 'typed-attr-slot', val: ['disabled', { tag: 'boolean', val: true }] }
+ *   { tag: 'bind',      val: ['value', 'input'] }
  *   { tag: 'begin',     val: 'todo-item' }
+ *   { tag: 'begin-keyed', val: ['todo-item', '42'] }
  *   { tag: 'end' }
+ *   { tag: 'skip',      val: 998 }
+ *   { tag: 'seek',      val: ['todo-item', 1] }
+ *
+ * Groups nest: the opcodes between `begin` and `end` can hold groups and
+ * children of their own, which belong to that instance.
+ *
+ * `skip` and `seek` make sparse updates of a group: `seek` moves to
+ * instance i of a group and `skip` (after an instance) passes over n more,
+ * leaving the instances passed over as they are. A `seek` with no instance
+ * after it marks where a group that rendered nothing goes, so a later
+ * render (or a clone of the instance it's in) finds it in place.
+ *
+ * `child` mounts a child component, passing the `prop`s that follow it to
+ * its `render`; the child resolves its own `child`s through the same map.
+ * A child is a part: on re-render its props are compared with the last
+ * ones and the child re-renders if they changed. It's unmounted with the
+ * group instance it's in.
+ * `event`s after a child's props listen for what it emits through the
+ * host's `emit`; the parent's handler reads the detail with `event-detail`.
+ *
+ * `hydrate` mounts a component into markup prerendered by the Rust host's
+ * `render_to_hydratable_string`, adopting the existing nodes instead of
+ * creating them. The markup carries markers for what can't be told from
+ * the opcodes alone:
+ *   <!--slot-->x             a slot's text (`<!---->` separates other text)
+ *   data-slots="class ..."   the attr-slots of an element, in order
+ *   <!--begin:id-->…<!--end-->…<!--/group-->   group instances, as buildDOM
+ *                            (an empty group is its `<!--/group-->` alone)
+ *   <div data-child="name">  a child's mount point
+ * Markers are removed as they're adopted. If the markup doesn't match the
+ * opcodes, the component is built from scratch instead.
  */
-import { setCurrentEvent } from './host.js';
+import { setCurrentEvent, takeEmitted } from './host.js';
 
 const PROP_ATTRS = new Set(['value', 'checked', 'selected']);
 
+// Apply a typed attribute value, touching the DOM only on change. A false
+// boolean removes the attribute; value/checked/selected keep their type as
+// properties.
+function setTypedAttr(element, name, value) {
+  const val = value.val;
+  if (PROP_ATTRS.has(name)) {
+    // `value` reads back as a string; don't reassign (and move the caret)
+    // when only the type differs
+    const cur = element[name];
+    if (typeof cur === 'string' ? cur !== String(val) : cur !== val) {
+      element[name] = val;
+    }
+  } else if (value.tag === 'boolean') {
+    if (element.hasAttribute(name) !== val) {
+      if (val) {
+        element.setAttribute(name, '');
+      } else {
+        element.removeAttribute(name);
+      }
+    }
+  } else {
+    const str = String(val);
+    if (element.getAttribute(name) !== str) {
+      element.setAttribute(name, str);
+    }
+  }
+}
+
+function sameProps(a, b) {
+  if (a.length !== b.length) {
+    return false;
+  }
+  for (let i = 0; i < a.length; i++) {
+    const [nameA, valueA] = a[i];
+    const [nameB, valueB] = b[i];
+    if (nameA !== nameB || valueA.tag !== valueB.tag || valueA.val !== valueB.val) {
+      return false;
+    }
+  }
+  return true;
+}
+
+// The `prop` and `event` opcodes following the `child` at index i
+function childOps(opcodes, i) {
+  const start = i;
+  const props = [];
+  const listeners = {};
+  while (i + 1 < opcodes.length) {
+    const op = opcodes[i + 1];
+    if (op.tag === 'prop') {
+      props.push(op.val);
+    } else if (op.tag === 'event') {
+      listeners[op.val[0]] = op.val[1];
+    } else {
+      break;
+    }
+    i++;
+  }
+  return { props, listeners, count: i - start };
+}
+
+// The template ID of a begin or begin-keyed opcode, else null
+function beginId(op) {
+  if (!op) return null;
+  if (op.tag === 'begin') return op.val;
+  if (op.tag === 'begin-keyed') return op.val[0];
+  return null;
+}
+
+// The template ID of a begin, begin-keyed or seek opcode
+function groupId(op) {
+  return op.tag === 'begin' ? op.val : op.val[0];
+}
+
+// What updateDOM needs of the nodes it built from a run of opcodes: the
+// component's top level, or one group instance. Parts, groups and static
+// nodes are in opcode order; a nested group belongs to its instance.
+function newScope() {
+  return { parts: [], groups: [], staticNodes: [] };
+}
+
+// A group instance: a scope between two markers, with the listeners added
+// in it kept for the group's template
+function makeInstance(startMarker, key) {
+  return { ...newScope(), startMarker, endMarker: null, key, _events: [], _oldIdx: -1 };
+}
+
+// Thrown by hydrateDOM when the markup doesn't match the opcodes
+class HydrationMismatch extends Error {}
+
+const symbolDispose = Symbol.dispose || Symbol.for('dispose');
+
+// Drop the resource a component constructed for its mount, if any
+function disposeRenderer(comp) {
+  if (typeof comp.renderer[symbolDispose] === 'function') {
+    comp.renderer[symbolDispose]();
+  }
+}
+
+function isComment(node, data) {
+  return node !== null && node.nodeType === 8 && node.data === data;
+}
+
 function dfsCollect(node, list) {
   list.push(node);
   let child = node.firstChild;
@@ -30,64 +175,211 @@ function dfsCollect(node, list) {
   }
 }
 
+// A copy of `node` without the subtrees in `skip`, collecting the nodes
+// copied in depth-first order
+function copySkeleton(node, skip, copied) {
+  const copy = node.cloneNode(false);
+  copied.push(node);
+  for (let child = node.firstChild; child; child = child.nextSibling) {
+    if (!skip.has(child)) {
+      copy.appendChild(copySkeleton(child, skip, copied));
+    }
+  }
+  return copy;
+}
+
+// Indices of a longest strictly increasing subsequence of `seq`
+function longestIncreasing(seq) {
+  const tails = [];
+  const prevIdx = new Array(seq.length);
+  for (let i = 0; i < seq.length; i++) {
+    let lo = 0;
+    let hi = tails.length;
+    while (lo < hi) {
+      const mid = (lo + hi) >> 1;
+      if (seq[tails[mid]] < seq[i]) {
+        lo = mid + 1;
+      } else {
+        hi = mid;
+      }
+    }
+    prevIdx[i] = lo > 0 ? tails[lo - 1] : -1;
+    tails[lo] = i;
+  }
+  const result = new Array(tails.length);
+  for (let i = tails.length - 1, k = tails[tails.length - 1]; i >= 0; i--, k = prevIdx[k]) {
+    result[i] = k;
+  }
+  return result;
+}
+
+
 export function createHost(container) {
   const components = [];
 
-  function mount(renderer, children, mountPoint, props) {
+  function mount(renderer, children, mountPoint, props, hydrating) {
+    props = props || [];
     const comp = {
-      renderer,
+      renderer: typeof renderer.Component === 'function' ? new renderer.Component(props) : renderer,
       children: children || {},
-      props: props || [],
+      props,
       mountPoint: mountPoint || document.createElement('div'),
-      topParts: [],
-      groups: [],
-      staticElements: [],
+      scope: newScope(),
+      // Group templates by template ID, cached from each group's first instance
+      templates: new Map(),
       initialized: false,
+      parent: null,
+      listeners: {},
     };
     if (!mountPoint) {
       container.appendChild(comp.mountPoint);
     }
     components.push(comp);
-    renderComponent(comp);
+    if (hydrating) {
+      hydrateComponent(comp);
+    } else {
+      renderComponent(comp);
+    }
     return comp;
   }
 
+  // Mount into an element holding prerendered markup, adopting its nodes
+  function hydrate(renderer, children, mountPoint, props) {
+    return mount(renderer, children, mountPoint, props, true);
+  }
+
+  // Mount the component of a child part into the part's node
+  function mountChild(comp, part, props, listeners, hydrating) {
+    const child = mount(comp.children[part.name], comp.children, part.node, props, hydrating);
+    child.parent = comp;
+    child.listeners = listeners;
+    part.comp = child;
+  }
+
+  // Unmount the children in a scope, and in its groups' instances, before
+  // its nodes go
+  function unmountChildren(scope) {
+    for (const part of scope.parts) {
+      if (part.type === 'child' && part.comp) {
+        unmountChildren(part.comp.scope);
+        disposeRenderer(part.comp);
+        const idx = components.indexOf(part.comp);
+        if (idx >= 0) {
+          components.splice(idx, 1);
+        }
+      }
+    }
+    for (const group of scope.groups) {
+      for (const inst of group.instances) {
+        unmountChildren(inst);
+      }
+    }
+  }
+
+  function hydrateComponent(comp) {
+    const opcodes = comp.renderer.render(comp.props);
+    // Children hydrated before a mismatch are mounted again by buildDOM
+    const mark = components.length;
+    try {
+      hydrateDOM(opcodes, comp, comp.mountPoint);
+    } catch (e) {
+      if (!(e instanceof HydrationMismatch)) {
+        throw e;
+      }
+      console.warn('Hydration mismatch, rendering from scratch:', e.message);
+      for (const child of components.splice(mark)) {
+        disposeRenderer(child);
+      }
+      comp.scope = newScope();
+      comp.templates = new Map();
+      comp.mountPoint.replaceChildren();
+      buildDOM(opcodes, comp, comp.scope, comp.mountPoint);
+    }
+    comp.initialized = true;
+  }
+
+  // Run a component's handler for an event, re-render it, then deliver
+  // what it emitted to its parent.
+  function dispatch(comp, event, detail, call) {
+    setCurrentEvent(event, detail);
+    let emitted;
+    try {
+      call();
+    } finally {
+      // A handler that throws mustn't leave its event current for the next
+      setCurrentEvent(null);
+      emitted = takeEmitted();
+    }
+    renderComponent(comp);
+    for (const { name, detail } of emitted) {
+      const handler = comp.parent && comp.listeners[name];
+      if (handler) {
+        const parent = comp.parent;
+        const event = {
+          type: name,
+          detail: Object.fromEntries(detail.map(([key, value]) => [key, value.val])),
+        };
+        dispatch(parent, event, detail, () => parent.renderer.handleEvent(handler));
+      }
+    }
+  }
+
+  function listen(comp, element, type, handler) {
+    element.addEventListener(type, (e) => {
+      dispatch(comp, e, [], () => comp.renderer.handleEvent(handler));
+    });
+  }
+
+  // Two-way binding: deliver the property's new value with the handler,
+  // so the component needs no event-query round-trip, then re-render.
+  function bindProperty(comp, element, prop, handler) {
+    const type = prop === 'value' ? 'input' : 'change';
+    element.addEventListener(type, (e) => {
+      if (typeof comp.renderer.handleBind !== 'function') {
+        throw new Error(`cannot deliver bind \`${handler}\`: the component exports \`renderer\`, `
+          + 'which has no `handle-bind`; export `instances` to bind');
+      }
+      const val = element[prop];
+      dispatch(comp, e, [], () => comp.renderer.handleBind(handler, typeof val === 'boolean'
+        ? { tag: 'boolean', val }
+        : { tag: 'str', val: String(val) }));
+    });
+  }
+
   function renderComponent(comp) {
     const opcodes = comp.renderer.render(comp.props);
     if (!comp.initialized) {
-      buildDOM(opcodes, comp, comp.mountPoint);
+      buildDOM(opcodes, comp, comp.scope, comp.mountPoint);
       comp.initialized = true;
     } else {
-      updateDOM(opcodes, comp);
+      updateDOM(opcodes, comp, comp.scope, comp.mountPoint);
     }
   }
 
-  function buildDOM(opcodes, comp, parent) {
+  // Build the opcodes from `start` into `parent`, recording them in
+  // `scope`, up to the `end` of the instance being built (if it's one).
+  // Nodes at the root of the scope go before `before`: an instance's go
+  // before its group's sentinel, so every instance stays between its own
+  // markers. Returns the index of that `end`.
+  function buildDOM(opcodes, comp, scope, parent, before = null, start = 0) {
     const stack = [];
     let current = parent;
     let element = null;
 
-    // begin/end tracking
-    let currentGroup = null;
-    let currentInstance = null;
+    function append(node) {
+      current.insertBefore(node, stack.length === 0 ? before : null);
+    }
 
-    for (let i = 0; i < opcodes.length; i++) {
+    for (let i = start; i < opcodes.length; i++) {
       const op = opcodes[i];
       switch (op.tag) {
         case 'open': {
           const el = document.createElement(op.val);
-          if (currentInstance && currentInstance._insertBefore) {
-            currentGroup.parent.insertBefore(el, currentGroup.sentinel);
-            currentInstance._insertBefore = false;
-          } else {
-            current.appendChild(el);
-          }
+          append(el);
           stack.push({ parent: current, element });
           current = el;
           element = el;
-          if (!currentInstance) {
-            comp.staticElements.push(el);
-          }
+          scope.staticNodes.push(el);
           break;
         }
         case 'close':
@@ -96,30 +388,28 @@ export function createHost(container) {
         case 'attr':
           element.setAttribute(op.val[0], op.val[1]);
           break;
-        case 'text':
-          current.appendChild(document.createTextNode(op.val));
+        case 'text': {
+          const node = document.createTextNode(op.val);
+          append(node);
+          scope.staticNodes.push(node);
           break;
+        }
         case 'slot': {
           const node = document.createTextNode(op.val);
-          current.appendChild(node);
-          if (currentInstance) {
-            currentInstance.parts.push({ type: 'slot', node });
-          } else {
-            comp.topParts.push({ type: 'slot', node });
-          }
+          append(node);
+          scope.parts.push({ type: 'slot', node });
           break;
         }
         case 'event': {
           const [eventType, handlerName] = op.val;
-          element.addEventListener(eventType, (e) => {
-            setCurrentEvent(e);
-            comp.renderer.handleEvent(handlerName);
-            setCurrentEvent(null);
-            renderComponent(comp);
-          });
-          if (currentInstance) {
-            currentInstance._events.push({ element, type: eventType, handler: handlerName });
-          }
+          listen(comp, element, eventType, handlerName);
+          scope._events?.push({ element, type: eventType, handler: handlerName });
+          break;
+        }
+        case 'bind': {
+          const [prop, handlerName] = op.val;
+          bindProperty(comp, element, prop, handlerName);
+          scope._events?.push({ element, bind: prop, handler: handlerName });
           break;
         }
         case 'attr-slot': {
@@ -129,382 +419,556 @@ export function createHost(container) {
           } else {
             element.setAttribute(name, val);
           }
-          const binding = { type: 'attr-slot', element, name };
-          if (currentInstance) {
-            currentInstance.parts.push(binding);
-          } else {
-            comp.topParts.push(binding);
-          }
+          scope.parts.push({ type: 'attr-slot', element, name });
           break;
         }
-        case 'child': {
-          const childRenderer = comp.children[op.val];
-          if (childRenderer) {
-            const childProps = [];
-            while (i + 1 < opcodes.length && opcodes[i + 1].tag === 'prop') {
-              i++;
-              childProps.push(opcodes[i].val);
-            }
-            const childMount = document.createElement('div');
-            current.appendChild(childMount);
-            mount(childRenderer, {}, childMount, childProps);
-          }
+        case 'typed-attr-slot': {
+          const [name, value] = op.val;
+          setTypedAttr(element, name, value);
+          scope.parts.push({ type: 'typed-attr-slot', element, name });
           break;
         }
-        case 'begin': {
-          const templateId = op.val;
-          // Find or create the group for this template ID
-          if (!currentGroup || currentGroup.templateId !== templateId) {
-            // New group
-            const sentinel = document.createComment('/group');
-            current.appendChild(sentinel);
-            currentGroup = {
-              templateId,
-              parent: current,
-              sentinel,
-              instances: [],
-            };
-            comp.groups.push(currentGroup);
+        case 'child': {
+          const { props, listeners, count } = childOps(opcodes, i);
+          i += count;
+          if (comp.children[op.val]) {
+            const part = { type: 'child', name: op.val, node: document.createElement('div'), comp: null };
+            append(part.node);
+            mountChild(comp, part, props, listeners);
+            scope.parts.push(part);
           }
-          // Create start marker and instance
-          const startMarker = document.createComment('begin:' + templateId);
-          current.insertBefore(startMarker, currentGroup.sentinel);
-          currentInstance = {
-            startMarker,
-            endMarker: null,
-            parts: [],
-            _events: [],
-            _insertBefore: true,
-          };
-          // Push group parent onto stack so DOM builds inside the group's parent
-          stack.push({ parent: current, element });
           break;
         }
-        case 'end': {
-          // Pop back to group parent level
-          const ctx = stack.pop();
-          current = ctx.parent;
-          element = ctx.element;
-          // Create end marker
-          const endMarker = document.createComment('end');
-          current.insertBefore(endMarker, currentGroup.sentinel);
-          currentInstance.endMarker = endMarker;
-          currentGroup.instances.push(currentInstance);
-          // Cache template after first instance
-          if (currentGroup.instances.length === 1) {
-            cacheGroupTemplate(currentGroup);
-          }
-          currentInstance = null;
+        case 'begin':
+        case 'begin-keyed':
+        case 'seek':
+          i = buildGroup(opcodes, comp, scope, current, stack.length === 0 ? before : null, i);
           break;
-        }
+        case 'end':
+          return i;
       }
     }
+    return opcodes.length;
   }
 
-  function updateDOM(opcodes, comp) {
-    let topPartIdx = 0;
-    let groupIdx = 0;
-    let instanceIdx = 0;
-    let partIdx = 0;
-    let insideBegin = false;
-    let reusing = false;
-    let building = false;
+  // Build a group from its first `begin`, or the `seek` marking an empty
+  // one, and the instances that follow. A later group with the same ID (a
+  // view spliced twice) is a group of its own. Returns the index of the
+  // last opcode consumed.
+  function buildGroup(opcodes, comp, scope, parent, before, i) {
+    const templateId = groupId(opcodes[i]);
+    const sentinel = document.createComment('/group');
+    parent.insertBefore(sentinel, before);
+    const group = { templateId, parent, sentinel, instances: [] };
+    scope.groups.push(group);
+    if (opcodes[i].tag !== 'seek') {
+      i = buildInstance(opcodes, comp, group, group.instances, i);
+    }
+    while (beginId(opcodes[i + 1]) === templateId) {
+      i = buildInstance(opcodes, comp, group, group.instances, i + 1);
+    }
+    return i;
+  }
+
+  function newInstance(group, key) {
+    const startMarker = document.createComment('begin:' + group.templateId);
+    group.parent.insertBefore(startMarker, group.sentinel);
+    return makeInstance(startMarker, key);
+  }
 
-    // Build-mode state
-    let buildStack = [];
-    let buildCurrent = null;
-    let buildElement = null;
-    let buildInstance = null;
-    let currentGroup = null;
+  // Build an instance of `group` from its `begin` at index i, adding it to
+  // `list`. Returns the index of its `end`.
+  function buildInstance(opcodes, comp, group, list, i) {
+    const op = opcodes[i];
+    const inst = newInstance(group, op.tag === 'begin-keyed' ? op.val[1] : null);
+    i = buildDOM(opcodes, comp, inst, group.parent, group.sentinel, i + 1);
+    inst.endMarker = document.createComment('end');
+    group.parent.insertBefore(inst.endMarker, group.sentinel);
+    list.push(inst);
+    // Cache template after first instance
+    if (!comp.templates.has(group.templateId)) {
+      cacheTemplate(comp, group.templateId, inst);
+    }
+    return i;
+  }
 
-    // DOM position tracking via staticElements recorded in buildDOM
-    let staticElIdx = 0;
-    let domStack = [comp.mountPoint];
+  // buildDOM over existing markup: walk it alongside the opcodes, claiming
+  // nodes and recording parts, groups and listeners as buildDOM would.
+  function hydrateDOM(opcodes, comp, parent) {
+    const stack = [];
+    let current = parent;
+    let element = null;
+    // The next unclaimed node at this level, whether the last node claimed
+    // was text, and the attr-slots left on `element`
+    let cursor = parent.firstChild;
+    let prevText = false;
+    let slots = [];
+
+    // The scope being recorded, the scopes and groups of the instances it
+    // is nested in, and a group whose next instance follows
+    let scope = comp.scope;
+    const scopes = [];
+    let openGroup = null;
+
+    function mismatch(what) {
+      throw new HydrationMismatch(`expected ${what}`);
+    }
+
+    function claimMarker(data) {
+      if (!isComment(cursor, data)) {
+        mismatch(`<!--${data}-->`);
+      }
+      const marker = cursor;
+      cursor = cursor.nextSibling;
+      return marker;
+    }
+
+    // An empty text opcode has no node in the markup: create it
+    function claimText(text) {
+      let node = cursor;
+      if (node && node.nodeType === 3) {
+        cursor = node.nextSibling;
+        if (node.data !== text) {
+          node.data = text;
+        }
+      } else {
+        node = document.createTextNode(text);
+        current.insertBefore(node, cursor);
+      }
+      prevText = true;
+      return node;
+    }
+
+    function claimSlot(name) {
+      if (slots.shift() !== name) {
+        mismatch(`attr-slot ${name}`);
+      }
+    }
+
+    // The group's sentinel follows its last instance
+    function closeGroup(group, i) {
+      if (beginId(opcodes[i + 1]) === group.templateId) {
+        openGroup = group;
+      } else {
+        group.sentinel = claimMarker('/group');
+      }
+      prevText = false;
+    }
 
     for (let i = 0; i < opcodes.length; i++) {
       const op = opcodes[i];
-
-      if (!insideBegin) {
-        // Top-level opcode processing
-        switch (op.tag) {
-          case 'open': {
-            if (staticElIdx < comp.staticElements.length) {
-              domStack.push(comp.staticElements[staticElIdx++]);
-            }
-            break;
+      switch (op.tag) {
+        case 'open': {
+          const el = cursor;
+          if (!el || el.nodeType !== 1 || el.localName !== op.val) {
+            mismatch(`<${op.val}>`);
           }
-          case 'close': {
-            if (domStack.length > 1) {
-              domStack.pop();
-            }
-            break;
+          stack.push({ parent: current, element, cursor: el.nextSibling, slots });
+          slots = el.hasAttribute('data-slots') ? el.getAttribute('data-slots').split(' ') : [];
+          el.removeAttribute('data-slots');
+          current = el;
+          element = el;
+          cursor = el.firstChild;
+          prevText = false;
+          scope.staticNodes.push(el);
+          break;
+        }
+        case 'close': {
+          if (cursor || slots.length) {
+            mismatch(`</${current.localName}>`);
           }
-          case 'slot': {
-            const part = comp.topParts[topPartIdx++];
-            if (part.node.textContent !== op.val) {
-              part.node.textContent = op.val;
-            }
-            break;
+          ({ parent: current, element, cursor, slots } = stack.pop());
+          prevText = false;
+          break;
+        }
+        case 'text': {
+          if (prevText) {
+            claimMarker('').remove();
           }
-          case 'attr-slot': {
-            const part = comp.topParts[topPartIdx++];
-            const newVal = op.val[1];
-            if (PROP_ATTRS.has(part.name)) {
-              if (part.element[part.name] !== newVal) {
-                part.element[part.name] = newVal;
-              }
-            } else {
-              if (part.element.getAttribute(part.name) !== newVal) {
-                part.element.setAttribute(part.name, newVal);
-              }
+          scope.staticNodes.push(claimText(op.val));
+          break;
+        }
+        case 'slot': {
+          claimMarker('slot').remove();
+          scope.parts.push({ type: 'slot', node: claimText(op.val) });
+          break;
+        }
+        case 'event': {
+          const [eventType, handlerName] = op.val;
+          listen(comp, element, eventType, handlerName);
+          scope._events?.push({ element, type: eventType, handler: handlerName });
+          break;
+        }
+        case 'bind': {
+          const [prop, handlerName] = op.val;
+          bindProperty(comp, element, prop, handlerName);
+          scope._events?.push({ element, bind: prop, handler: handlerName });
+          break;
+        }
+        case 'attr-slot': {
+          const [name, val] = op.val;
+          claimSlot(name);
+          if (PROP_ATTRS.has(name)) {
+            if (element[name] !== val) {
+              element[name] = val;
             }
-            break;
+          } else if (element.getAttribute(name) !== val) {
+            element.setAttribute(name, val);
           }
-          case 'begin': {
-            insideBegin = true;
-            currentGroup = comp.groups[groupIdx];
-            if (!currentGroup) {
-              // Group doesn't exist yet (e.g. list was empty on first render).
-              // Create it lazily using the current DOM parent.
-              const parent = domStack[domStack.length - 1];
-              const sentinel = document.createComment('/group');
-              parent.appendChild(sentinel);
-              currentGroup = {
-                templateId: op.val,
-                parent,
-                sentinel,
-                instances: [],
-              };
-              comp.groups.splice(groupIdx, 0, currentGroup);
-            }
-            if (instanceIdx < currentGroup.instances.length) {
-              // Reuse existing instance
-              reusing = true;
-              building = false;
-              partIdx = 0;
-            } else if (currentGroup.template) {
-              // Clone from cached template, then update parts
-              cloneFromTemplate(currentGroup, comp);
-              reusing = true;
-              building = false;
-              partIdx = 0;
-            } else {
-              // Build new instance
-              reusing = false;
-              building = true;
-              const parent = currentGroup.parent;
-              const startMarker = document.createComment('begin:' + op.val);
-              parent.insertBefore(startMarker, currentGroup.sentinel);
-              buildInstance = {
-                startMarker,
-                endMarker: null,
-                parts: [],
-                _events: [],
-              };
-              buildStack = [];
-              buildCurrent = parent;
-              buildElement = null;
+          scope.parts.push({ type: 'attr-slot', element, name });
+          break;
+        }
+        case 'typed-attr-slot': {
+          const [name, value] = op.val;
+          claimSlot(name);
+          setTypedAttr(element, name, value);
+          scope.parts.push({ type: 'typed-attr-slot', element, name });
+          break;
+        }
+        case 'child': {
+          const { props, listeners, count } = childOps(opcodes, i);
+          i += count;
+          if (comp.children[op.val]) {
+            const node = cursor;
+            if (!node || node.nodeType !== 1 || node.getAttribute('data-child') !== op.val) {
+              mismatch(`<div data-child="${op.val}">`);
             }
-            break;
+            node.removeAttribute('data-child');
+            cursor = node.nextSibling;
+            prevText = false;
+            const part = { type: 'child', name: op.val, node, comp: null };
+            mountChild(comp, part, props, listeners, true);
+            scope.parts.push(part);
           }
-          default:
-            // open, close, attr, text, event, child — skip at top level during update
-            break;
+          break;
         }
-      } else {
-        // Inside begin/end block
-        switch (op.tag) {
-          case 'end': {
-            if (building) {
-              const endMarker = document.createComment('end');
-              currentGroup.parent.insertBefore(endMarker, currentGroup.sentinel);
-              buildInstance.endMarker = endMarker;
-              currentGroup.instances.push(buildInstance);
-              // Cache template after first instance
-              if (currentGroup.instances.length === 1) {
-                cacheGroupTemplate(currentGroup);
-              }
-              buildInstance = null;
-              building = false;
-            }
-            reusing = false;
-            instanceIdx++;
-
-            // Check if next opcode is another begin with same template
-            if (i + 1 < opcodes.length && opcodes[i + 1].tag === 'begin' &&
-                currentGroup && opcodes[i + 1].val === currentGroup.templateId) {
-              // Stay in insideBegin mode, next begin will handle reuse/build
-              // Peek and handle the begin
-              i++;
-              if (instanceIdx < currentGroup.instances.length) {
-                reusing = true;
-                building = false;
-                partIdx = 0;
-              } else if (currentGroup.template) {
-                cloneFromTemplate(currentGroup, comp);
-                reusing = true;
-                building = false;
-                partIdx = 0;
-              } else {
-                reusing = false;
-                building = true;
-                const parent = currentGroup.parent;
-                const startMarker = document.createComment('begin:' + opcodes[i].val);
-                parent.insertBefore(startMarker, currentGroup.sentinel);
-                buildInstance = {
-                  startMarker,
-                  endMarker: null,
-                  parts: [],
-                  _events: [],
-                };
-                buildStack = [];
-                buildCurrent = parent;
-                buildElement = null;
-              }
-            } else {
-              // Done with this group — trim excess instances
-              if (currentGroup) {
-                trimGroup(currentGroup, instanceIdx);
-              }
-              insideBegin = false;
-              groupIdx++;
-              instanceIdx = 0;
-            }
-            break;
+        case 'begin':
+        case 'begin-keyed':
+        case 'seek': {
+          const templateId = groupId(op);
+          let group = openGroup;
+          if (op.tag === 'seek' || !group || group.templateId !== templateId) {
+            // The sentinel is claimed after the group's last instance
+            group = { templateId, parent: current, sentinel: null, instances: [] };
+            scope.groups.push(group);
           }
-          case 'slot': {
-            if (reusing) {
-              const inst = currentGroup.instances[instanceIdx];
-              const part = inst.parts[partIdx++];
-              if (part.node.textContent !== op.val) {
-                part.node.textContent = op.val;
-              }
-            } else if (building) {
-              const node = document.createTextNode(op.val);
-              buildCurrent.appendChild(node);
-              buildInstance.parts.push({ type: 'slot', node });
-            }
+          openGroup = null;
+          if (op.tag === 'seek') {
+            closeGroup(group, i);
             break;
           }
-          case 'attr-slot': {
-            if (reusing) {
-              const inst = currentGroup.instances[instanceIdx];
-              const part = inst.parts[partIdx++];
-              const newVal = op.val[1];
-              if (PROP_ATTRS.has(part.name)) {
-                if (part.element[part.name] !== newVal) {
-                  part.element[part.name] = newVal;
-                }
-              } else {
-                if (part.element.getAttribute(part.name) !== newVal) {
-                  part.element.setAttribute(part.name, newVal);
-                }
-              }
-            } else if (building) {
-              const [name, val] = op.val;
-              if (PROP_ATTRS.has(name)) {
-                buildElement[name] = val;
-              } else {
-                buildElement.setAttribute(name, val);
-              }
-              buildInstance.parts.push({ type: 'attr-slot', element: buildElement, name });
-            }
-            break;
+          scopes.push({ scope, group });
+          scope = makeInstance(claimMarker('begin:' + templateId), op.tag === 'begin-keyed' ? op.val[1] : null);
+          prevText = false;
+          break;
+        }
+        case 'end': {
+          const inst = scope;
+          inst.endMarker = claimMarker('end');
+          const { scope: outer, group } = scopes.pop();
+          group.instances.push(inst);
+          if (!comp.templates.has(group.templateId)) {
+            cacheTemplate(comp, group.templateId, inst);
           }
-          case 'open': {
-            if (building) {
-              const el = document.createElement(op.val);
-              if (buildStack.length === 0) {
-                // First open inside a building instance — insert before sentinel
-                currentGroup.parent.insertBefore(el, currentGroup.sentinel);
-              } else {
-                buildCurrent.appendChild(el);
-              }
-              buildStack.push({ parent: buildCurrent, element: buildElement });
-              buildCurrent = el;
-              buildElement = el;
-            }
-            // If reusing, skip — DOM already exists
-            break;
+          scope = outer;
+          closeGroup(group, i);
+          break;
+        }
+      }
+    }
+    if (cursor) {
+      mismatch('the end of the mount point');
+    }
+  }
+
+  // Update `scope` from the opcodes from `start` up to the `end` of its
+  // instance (if it's one). `prev` is the node the scope starts after, or
+  // null for the start of `parent`. Returns the index of that `end`.
+  function updateDOM(opcodes, comp, scope, parent, prev = null, start = 0) {
+    let partIdx = 0;
+    let groupIdx = 0;
+
+    // DOM position tracking via the static nodes recorded in buildDOM.
+    // `prev` is the last node passed at the current level, so a group
+    // seen for the first time can be anchored in the right place.
+    let staticNodeIdx = 0;
+    const domStack = [parent];
+    const prevStack = [];
+
+    // Find the group for a `begin` or `seek`. Groups are matched by
+    // template ID in document order: groups passed over rendered nothing
+    // this time (an empty list or an untaken branch) and are emptied. A
+    // template ID not seen before (e.g. from hand-written opcodes that
+    // leave out an empty group's `seek`) gets a new group anchored after
+    // the last node passed at this level.
+    function enterGroup(templateId) {
+      const groups = scope.groups;
+      for (let g = groupIdx; g < groups.length; g++) {
+        if (groups[g].templateId === templateId) {
+          while (groupIdx < g) {
+            trimGroup(groups[groupIdx++], 0);
           }
-          case 'close': {
-            if (building) {
-              ({ parent: buildCurrent, element: buildElement } = buildStack.pop());
-            }
-            break;
+          return groups[g];
+        }
+      }
+      const parent = domStack[domStack.length - 1];
+      const sentinel = document.createComment('/group');
+      parent.insertBefore(sentinel, prev ? prev.nextSibling : parent.firstChild);
+      // Groups still ahead in the list that sit before the new sentinel in
+      // the document are empty this render.
+      while (groupIdx < groups.length &&
+             groups[groupIdx].sentinel.compareDocumentPosition(sentinel) &
+               Node.DOCUMENT_POSITION_FOLLOWING) {
+        trimGroup(groups[groupIdx++], 0);
+      }
+      const group = {
+        templateId,
+        parent,
+        sentinel,
+        instances: [],
+      };
+      groups.splice(groupIdx, 0, group);
+      return group;
+    }
+
+    let i = start;
+    for (; i < opcodes.length; i++) {
+      const op = opcodes[i];
+      if (op.tag === 'end') {
+        break;
+      }
+      switch (op.tag) {
+        case 'open': {
+          if (staticNodeIdx < scope.staticNodes.length) {
+            const el = scope.staticNodes[staticNodeIdx++];
+            domStack.push(el);
+            prevStack.push(el);
+            prev = null;
           }
-          case 'attr': {
-            if (building) {
-              buildElement.setAttribute(op.val[0], op.val[1]);
-            }
-            break;
+          break;
+        }
+        case 'close': {
+          if (domStack.length > 1) {
+            domStack.pop();
+            prev = prevStack.pop();
           }
-          case 'text': {
-            if (building) {
-              buildCurrent.appendChild(document.createTextNode(op.val));
+          break;
+        }
+        case 'text': {
+          if (staticNodeIdx < scope.staticNodes.length) {
+            prev = scope.staticNodes[staticNodeIdx++];
+          }
+          break;
+        }
+        case 'child': {
+          // Listeners are static, like element events
+          const { props, listeners, count } = childOps(opcodes, i);
+          i += count;
+          if (comp.children[op.val]) {
+            const part = scope.parts[partIdx++];
+            if (!part.comp) {
+              // In an instance cloned from its group's template
+              mountChild(comp, part, props, listeners);
+            } else if (!sameProps(part.comp.props, props)) {
+              part.comp.props = props;
+              renderComponent(part.comp);
             }
-            break;
+            prev = part.node;
+          }
+          break;
+        }
+        case 'slot': {
+          const part = scope.parts[partIdx++];
+          if (part.node.textContent !== op.val) {
+            part.node.textContent = op.val;
           }
-          case 'event': {
-            if (building) {
-              const [eventType, handlerName] = op.val;
-              buildElement.addEventListener(eventType, (e) => {
-                setCurrentEvent(e);
-                comp.renderer.handleEvent(handlerName);
-                setCurrentEvent(null);
-                renderComponent(comp);
-              });
-              buildInstance._events.push({ element: buildElement, type: eventType, handler: handlerName });
+          prev = part.node;
+          break;
+        }
+        case 'attr-slot': {
+          const part = scope.parts[partIdx++];
+          const newVal = op.val[1];
+          if (PROP_ATTRS.has(part.name)) {
+            if (part.element[part.name] !== newVal) {
+              part.element[part.name] = newVal;
+            }
+          } else {
+            if (part.element.getAttribute(part.name) !== newVal) {
+              part.element.setAttribute(part.name, newVal);
             }
-            break;
           }
-          default:
-            break;
+          break;
         }
+        case 'typed-attr-slot': {
+          const part = scope.parts[partIdx++];
+          setTypedAttr(part.element, part.name, op.val[1]);
+          break;
+        }
+        case 'begin':
+        case 'begin-keyed':
+        case 'seek': {
+          const group = enterGroup(groupId(op));
+          i = updateGroup(opcodes, comp, group, i);
+          prev = group.sentinel;
+          groupIdx++;
+          break;
+        }
+        default:
+          // attr, event, bind, prop, skip — static after the first render
+          break;
       }
     }
 
-    // If we ended while still inside a group, trim it
-    if (insideBegin && currentGroup) {
-      trimGroup(currentGroup, instanceIdx);
-      groupIdx++;
+    // Trim any groups that weren't visited (e.g. list went from N items to 0)
+    while (groupIdx < scope.groups.length) {
+      trimGroup(scope.groups[groupIdx++], 0);
     }
+    return i;
+  }
 
-    // Trim any groups that weren't visited (e.g. list went from N items to 0)
-    while (groupIdx < comp.groups.length) {
-      trimGroup(comp.groups[groupIdx], 0);
-      groupIdx++;
+  // Update a group from its first `begin` or `seek` and what follows: its
+  // instances, and any skip/seek between them. Returns the index of the
+  // last opcode consumed.
+  function updateGroup(opcodes, comp, group, i) {
+    let instanceIdx = 0;
+
+    // Keyed groups: instances of the previous render by key, still
+    // unclaimed, and the instances of this render in order
+    let keyedOld = null;
+    const keyedNew = [];
+
+    // Update the next instance from its `begin` at index j: reuse one (by
+    // position, or by key for begin-keyed), clone the cached template, or
+    // build it. Returns the index of its `end`.
+    function updateInstance(j) {
+      const op = opcodes[j];
+      const keyed = op.tag === 'begin-keyed';
+      const key = keyed ? op.val[1] : null;
+      let inst = null;
+      if (keyed) {
+        if (!keyedOld) {
+          keyedOld = new Map();
+          group.instances.forEach((old, idx) => {
+            if (keyedOld.has(old.key)) {
+              // Duplicate key: only the first instance can be matched
+              removeInstance(group, old);
+              return;
+            }
+            old._oldIdx = idx;
+            keyedOld.set(old.key, old);
+          });
+        }
+        inst = keyedOld.get(key) || null;
+        if (inst) {
+          keyedOld.delete(key);
+        }
+      } else if (instanceIdx < group.instances.length) {
+        inst = group.instances[instanceIdx];
+      }
+      instanceIdx++;
+
+      if (!inst && comp.templates.has(group.templateId)) {
+        // Clone from cached template, then update it like a reused one
+        inst = cloneFromTemplate(comp, group);
+        inst.key = key;
+        if (!keyed) {
+          group.instances.push(inst);
+        }
+      }
+      if (!inst) {
+        return buildInstance(opcodes, comp, group, keyed ? keyedNew : group.instances, j);
+      }
+      if (keyed) {
+        keyedNew.push(inst);
+      }
+      return updateDOM(opcodes, comp, inst, group.parent, inst.startMarker, j + 1);
     }
+
+    if (opcodes[i].tag === 'seek') {
+      instanceIdx = Math.min(opcodes[i].val[1], group.instances.length);
+    } else {
+      i = updateInstance(i);
+    }
+    for (;;) {
+      const next = opcodes[i + 1];
+      const tag = next && next.tag;
+      if (tag === 'skip') {
+        instanceIdx = Math.min(instanceIdx + next.val, group.instances.length);
+      } else if (tag === 'seek' && next.val[0] === group.templateId) {
+        instanceIdx = Math.max(instanceIdx, Math.min(next.val[1], group.instances.length));
+      } else if (beginId(next) === group.templateId) {
+        i = updateInstance(i + 1);
+        continue;
+      } else {
+        break;
+      }
+      i++;
+    }
+
+    // Done with this group — drop what wasn't rendered
+    if (keyedOld) {
+      for (const old of keyedOld.values()) {
+        removeInstance(group, old);
+      }
+      arrangeKeyed(group, keyedNew);
+      group.instances = keyedNew;
+    } else {
+      trimGroup(group, instanceIdx);
+    }
+    return i;
   }
 
-  function cacheGroupTemplate(group) {
-    const inst = group.instances[0];
-    const tmpl = document.createElement('template');
-    let node = inst.startMarker.nextSibling;
-    while (node && node !== inst.endMarker) {
-      tmpl.content.appendChild(node.cloneNode(true));
-      node = node.nextSibling;
+  // Cache a group's template from its first instance: a copy of its nodes
+  // without the instances of nested groups or the content of children, so
+  // a clone starts with its nested groups empty and its children not yet
+  // mounted. Parts, groups, static nodes and listeners are recorded as
+  // indices into the copy's depth-first order.
+  function cacheTemplate(comp, templateId, inst) {
+    const skip = new Set();
+    for (const group of inst.groups) {
+      for (const nested of group.instances) {
+        for (let node = nested.startMarker; node; node = node.nextSibling) {
+          skip.add(node);
+          if (node === nested.endMarker) break;
+        }
+      }
     }
+    for (const part of inst.parts) {
+      if (part.type === 'child') {
+        part.node.childNodes.forEach((node) => skip.add(node));
+      }
+    }
+    const tmpl = document.createElement('template');
     const origNodes = [];
-    node = inst.startMarker.nextSibling;
-    while (node && node !== inst.endMarker) {
-      dfsCollect(node, origNodes);
-      node = node.nextSibling;
-    }
-    const partMap = inst.parts.map(part => {
-      const target = part.type === 'slot' ? part.node : part.element;
-      const idx = origNodes.indexOf(target);
-      return { type: part.type, nodeIdx: idx, name: part.name };
-    });
-    const eventMap = (inst._events || []).map(ev => {
-      const idx = origNodes.indexOf(ev.element);
-      return { nodeIdx: idx, type: ev.type, handler: ev.handler };
+    for (let node = inst.startMarker.nextSibling; node && node !== inst.endMarker; node = node.nextSibling) {
+      if (!skip.has(node)) {
+        tmpl.content.appendChild(copySkeleton(node, skip, origNodes));
+      }
+    }
+    const index = new Map(origNodes.map((node, idx) => [node, idx]));
+    comp.templates.set(templateId, {
+      el: tmpl,
+      partMap: inst.parts.map((part) => ({
+        type: part.type,
+        nodeIdx: index.get(part.node || part.element),
+        name: part.name,
+      })),
+      groupMap: inst.groups.map((group) => ({
+        templateId: group.templateId,
+        nodeIdx: index.get(group.sentinel),
+      })),
+      staticMap: inst.staticNodes.map((node) => index.get(node)),
+      eventMap: inst._events.map((ev) => ({
+        nodeIdx: index.get(ev.element),
+        type: ev.type,
+        bind: ev.bind,
+        handler: ev.handler,
+      })),
     });
-    group.template = { el: tmpl, partMap, eventMap };
   }
 
-  function cloneFromTemplate(group, comp) {
-    const { el: tmpl, partMap, eventMap } = group.template;
+  function cloneFromTemplate(comp, group) {
+    const { el: tmpl, partMap, groupMap, staticMap, eventMap } = comp.templates.get(group.templateId);
     const clone = tmpl.content.cloneNode(true);
     const cloneNodes = [];
     let child = clone.firstChild;
@@ -512,48 +976,90 @@ export function createHost(container) {
       dfsCollect(child, cloneNodes);
       child = child.nextSibling;
     }
-    const startMarker = document.createComment('begin:' + group.templateId);
-    const endMarker = document.createComment('end');
-    const parent = group.parent;
-    parent.insertBefore(startMarker, group.sentinel);
-    parent.insertBefore(clone, group.sentinel);
-    parent.insertBefore(endMarker, group.sentinel);
-    const parts = partMap.map(pm => {
-      const node = cloneNodes[pm.nodeIdx];
-      if (pm.type === 'slot') {
-        return { type: 'slot', node };
+    const inst = newInstance(group, null);
+    group.parent.insertBefore(clone, group.sentinel);
+    inst.endMarker = document.createComment('end');
+    group.parent.insertBefore(inst.endMarker, group.sentinel);
+    inst.parts = partMap.map(({ type, nodeIdx, name }) => {
+      const node = cloneNodes[nodeIdx];
+      if (type === 'slot') {
+        return { type, node };
+      } else if (type === 'child') {
+        return { type, name, node, comp: null };
       } else {
-        return { type: 'attr-slot', element: node, name: pm.name };
+        return { type, element: node, name };
       }
     });
+    inst.groups = groupMap.map(({ templateId, nodeIdx }) => {
+      const sentinel = cloneNodes[nodeIdx];
+      return { templateId, parent: sentinel.parentNode, sentinel, instances: [] };
+    });
+    inst.staticNodes = staticMap.map((idx) => cloneNodes[idx]);
     for (const em of eventMap) {
       const el = cloneNodes[em.nodeIdx];
-      el.addEventListener(em.type, (e) => {
-        setCurrentEvent(e);
-        comp.renderer.handleEvent(em.handler);
-        setCurrentEvent(null);
-        renderComponent(comp);
-      });
-    }
-    const inst = { startMarker, endMarker, parts, _events: [] };
-    group.instances.push(inst);
+      if (em.bind) {
+        bindProperty(comp, el, em.bind, em.handler);
+      } else {
+        listen(comp, el, em.type, em.handler);
+      }
+    }
     return inst;
   }
 
   function trimGroup(group, keepCount) {
     while (group.instances.length > keepCount) {
-      const inst = group.instances.pop();
-      // Remove all DOM nodes between startMarker and endMarker (inclusive)
-      const parent = group.parent;
-      let node = inst.startMarker;
-      while (node) {
-        const next = node.nextSibling;
-        parent.removeChild(node);
-        if (node === inst.endMarker) break;
-        node = next;
+      removeInstance(group, group.instances.pop());
+    }
+  }
+
+  // Remove all DOM nodes between startMarker and endMarker (inclusive),
+  // unmounting the children among them
+  function removeInstance(group, inst) {
+    unmountChildren(inst);
+    const parent = group.parent;
+    let node = inst.startMarker;
+    while (node) {
+      const next = node.nextSibling;
+      parent.removeChild(node);
+      if (node === inst.endMarker) break;
+      node = next;
+    }
+  }
+
+  // Move an instance's nodes (markers inclusive) before `ref`
+  function moveInstance(group, inst, ref) {
+    const parent = group.parent;
+    let node = inst.startMarker;
+    while (node) {
+      const next = node.nextSibling;
+      parent.insertBefore(node, ref);
+      if (node === inst.endMarker) break;
+      node = next;
+    }
+  }
+
+  // Put a keyed group's instances in render order with as few moves as
+  // possible: reused instances on the longest run that kept its previous
+  // order stay put, everything else is moved into place.
+  function arrangeKeyed(group, instances) {
+    const oldIdx = [];
+    const newIdx = [];
+    instances.forEach((inst, i) => {
+      if (inst._oldIdx >= 0) {
+        oldIdx.push(inst._oldIdx);
+        newIdx.push(i);
+      }
+    });
+    const stay = new Set(longestIncreasing(oldIdx).map(j => newIdx[j]));
+    let ref = group.sentinel;
+    for (let i = instances.length - 1; i >= 0; i--) {
+      const inst = instances[i];
+      if (!stay.has(i) && inst.endMarker.nextSibling !== ref) {
+        moveInstance(group, inst, ref);
       }
+      ref = inst.startMarker;
     }
   }
 
-  return { mount };
+  return { mount, hydrate };
 }
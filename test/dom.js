@@ -0,0 +1,333 @@
+// A minimal DOM for running src/runtime.js under node: the node types,
+// tree operations and attributes the runtime uses, plus `parseInto` for
+// the markup the Rust host's serializer writes.
+
+class Node {
+  constructor(nodeType) {
+    this.nodeType = nodeType;
+    this.parentNode = null;
+    this.childNodes = [];
+    this.listeners = {};
+  }
+
+  get firstChild() {
+    return this.childNodes[0] || null;
+  }
+
+  get lastChild() {
+    return this.childNodes[this.childNodes.length - 1] || null;
+  }
+
+  get nextSibling() {
+    const siblings = this.parentNode ? this.parentNode.childNodes : [];
+    return siblings[siblings.indexOf(this) + 1] || null;
+  }
+
+  get previousSibling() {
+    const siblings = this.parentNode ? this.parentNode.childNodes : [];
+    return siblings[siblings.indexOf(this) - 1] || null;
+  }
+
+  get parentElement() {
+    return this.parentNode;
+  }
+
+  appendChild(node) {
+    return this.insertBefore(node, null);
+  }
+
+  insertBefore(node, ref) {
+    if (node.nodeType === 11) {
+      for (const child of [...node.childNodes]) {
+        this.insertBefore(child, ref);
+      }
+      return node;
+    }
+    if (node.parentNode) {
+      node.parentNode.removeChild(node);
+    }
+    const index = ref ? this.childNodes.indexOf(ref) : this.childNodes.length;
+    if (index < 0) {
+      throw new Error('insertBefore: ref is not a child');
+    }
+    this.childNodes.splice(index, 0, node);
+    node.parentNode = this;
+    return node;
+  }
+
+  removeChild(node) {
+    const index = this.childNodes.indexOf(node);
+    if (index < 0) {
+      throw new Error('removeChild: not a child');
+    }
+    this.childNodes.splice(index, 1);
+    node.parentNode = null;
+    return node;
+  }
+
+  remove() {
+    if (this.parentNode) {
+      this.parentNode.removeChild(this);
+    }
+  }
+
+  replaceChildren(...nodes) {
+    for (const child of [...this.childNodes]) {
+      this.removeChild(child);
+    }
+    for (const node of nodes) {
+      this.appendChild(node);
+    }
+  }
+
+  compareDocumentPosition(other) {
+    const path = (node) => {
+      const nodes = [];
+      for (; node; node = node.parentNode) {
+        nodes.unshift(node);
+      }
+      return nodes;
+    };
+    const a = path(this);
+    const b = path(other);
+    let i = 0;
+    while (i < a.length && i < b.length && a[i] === b[i]) {
+      i++;
+    }
+    if (i === a.length) {
+      return Node.DOCUMENT_POSITION_CONTAINED_BY | Node.DOCUMENT_POSITION_FOLLOWING;
+    }
+    if (i === b.length) {
+      return Node.DOCUMENT_POSITION_CONTAINS | Node.DOCUMENT_POSITION_PRECEDING;
+    }
+    const siblings = a[i - 1].childNodes;
+    return siblings.indexOf(b[i]) > siblings.indexOf(a[i])
+      ? Node.DOCUMENT_POSITION_FOLLOWING
+      : Node.DOCUMENT_POSITION_PRECEDING;
+  }
+
+  addEventListener(type, listener) {
+    (this.listeners[type] ||= []).push(listener);
+  }
+
+  removeEventListener(type, listener) {
+    const listeners = this.listeners[type] || [];
+    const index = listeners.indexOf(listener);
+    if (index >= 0) {
+      listeners.splice(index, 1);
+    }
+  }
+
+  // Bubbles to the root unless `bubbles` is false
+  dispatchEvent(event) {
+    event.target ||= this;
+    for (let node = this; node; node = node.parentNode) {
+      event.currentTarget = node;
+      for (const listener of node.listeners[event.type] || []) {
+        listener(event);
+      }
+      if (event.bubbles === false) {
+        break;
+      }
+    }
+  }
+
+  get textContent() {
+    if (this.nodeType === 3 || this.nodeType === 8) {
+      return this.data;
+    }
+    return this.childNodes.map((child) => (child.nodeType === 8 ? '' : child.textContent)).join('');
+  }
+
+  set textContent(value) {
+    if (this.nodeType === 3 || this.nodeType === 8) {
+      this.data = String(value);
+    } else {
+      this.replaceChildren(new Text(value));
+    }
+  }
+
+  get nodeValue() {
+    return this.data;
+  }
+
+  set nodeValue(value) {
+    this.data = value;
+  }
+}
+
+Node.DOCUMENT_POSITION_PRECEDING = 2;
+Node.DOCUMENT_POSITION_FOLLOWING = 4;
+Node.DOCUMENT_POSITION_CONTAINS = 8;
+Node.DOCUMENT_POSITION_CONTAINED_BY = 16;
+
+class Text extends Node {
+  constructor(data) {
+    super(3);
+    this.data = String(data);
+  }
+
+  cloneNode() {
+    return new Text(this.data);
+  }
+}
+
+class Comment extends Node {
+  constructor(data) {
+    super(8);
+    this.data = String(data);
+  }
+
+  cloneNode() {
+    return new Comment(this.data);
+  }
+}
+
+class DocumentFragment extends Node {
+  constructor() {
+    super(11);
+  }
+
+  cloneNode(deep) {
+    const fragment = new DocumentFragment();
+    if (deep) {
+      for (const child of this.childNodes) {
+        fragment.appendChild(child.cloneNode(true));
+      }
+    }
+    return fragment;
+  }
+}
+
+// Properties an element keeps apart from its attributes
+const PROPS = ['value', 'checked', 'selected'];
+
+class Element extends Node {
+  constructor(tag) {
+    super(1);
+    this.localName = tag;
+    this.tagName = tag.toUpperCase();
+    this.attributes = new Map();
+    if (tag === 'template') {
+      this.content = new DocumentFragment();
+    }
+  }
+
+  setAttribute(name, value) {
+    this.attributes.set(name, String(value));
+  }
+
+  getAttribute(name) {
+    return this.attributes.has(name) ? this.attributes.get(name) : null;
+  }
+
+  hasAttribute(name) {
+    return this.attributes.has(name);
+  }
+
+  removeAttribute(name) {
+    this.attributes.delete(name);
+  }
+
+  get children() {
+    return this.childNodes.filter((child) => child.nodeType === 1);
+  }
+
+  cloneNode(deep) {
+    const element = new Element(this.localName);
+    for (const [name, value] of this.attributes) {
+      element.attributes.set(name, value);
+    }
+    for (const prop of PROPS) {
+      if (Object.hasOwn(this, prop)) {
+        element[prop] = this[prop];
+      }
+    }
+    if (deep) {
+      for (const child of this.childNodes) {
+        element.appendChild(child.cloneNode(true));
+      }
+    }
+    return element;
+  }
+
+  get innerHTML() {
+    return this.childNodes.map(serialize).join('');
+  }
+
+  get outerHTML() {
+    return serialize(this);
+  }
+}
+
+// Markup without escaping; properties set on an element show as `.name=`
+function serialize(node) {
+  switch (node.nodeType) {
+    case 3:
+      return node.data;
+    case 8:
+      return `<!--${node.data}-->`;
+    case 11:
+      return node.childNodes.map(serialize).join('');
+  }
+  const attrs = [...node.attributes].map(([name, value]) => ` ${name}="${value}"`).join('');
+  const props = PROPS.filter((prop) => Object.hasOwn(node, prop))
+    .map((prop) => ` .${prop}=${JSON.stringify(node[prop])}`)
+    .join('');
+  const children = node.childNodes.map(serialize).join('');
+  return `<${node.localName}${attrs}${props}>${children}</${node.localName}>`;
+}
+
+export const document = {
+  createElement: (tag) => new Element(tag),
+  createTextNode: (data) => new Text(data),
+  createComment: (data) => new Comment(data),
+  createDocumentFragment: () => new DocumentFragment(),
+};
+
+globalThis.document = document;
+globalThis.Node = Node;
+
+const VOID = new Set([
+  'area', 'base', 'br', 'col', 'embed', 'hr', 'img', 'input', 'link', 'meta', 'param', 'source', 'track', 'wbr',
+]);
+
+function unescape(text) {
+  return text
+    .replace(/&lt;/g, '<')
+    .replace(/&gt;/g, '>')
+    .replace(/&quot;/g, '"')
+    .replace(/&amp;/g, '&');
+}
+
+// Parse markup as `render_to_hydratable_string` writes it into `parent`
+export function parseInto(parent, html) {
+  let current = parent;
+  let i = 0;
+  while (i < html.length) {
+    if (html.startsWith('<!--', i)) {
+      const end = html.indexOf('-->', i + 4);
+      current.appendChild(new Comment(html.slice(i + 4, end)));
+      i = end + 3;
+    } else if (html.startsWith('</', i)) {
+      i = html.indexOf('>', i) + 1;
+      current = current.parentNode;
+    } else if (html[i] === '<') {
+      const tag = /^<([a-z][^\s>]*)((?:\s+[^\s=>]+(?:="[^"]*")?)*)>/i.exec(html.slice(i));
+      const element = new Element(tag[1]);
+      for (const [, name, value] of tag[2].matchAll(/([^\s=>]+)(?:="([^"]*)")?/g)) {
+        element.setAttribute(name, unescape(value || ''));
+      }
+      current.appendChild(element);
+      i += tag[0].length;
+      if (!VOID.has(tag[1])) {
+        current = element;
+      }
+    } else {
+      const next = html.indexOf('<', i);
+      const end = next < 0 ? html.length : next;
+      current.appendChild(new Text(unescape(html.slice(i, end))));
+      i = end;
+    }
+  }
+}
@@ -0,0 +1,244 @@
+// Runtime tests under node: `npm test`.
+import { test } from 'node:test';
+import assert from 'node:assert/strict';
+import { readFileSync } from 'node:fs';
+import { document, parseInto } from './dom.js';
+import { createHost } from '../src/runtime.js';
+import { eventQuery } from '../src/host.js';
+
+// Opcodes from the compact notation of wasm-dom-host's tests/common
+function ops(script) {
+  const pair = (s) => {
+    const eq = s.indexOf('=');
+    return eq < 0 ? [s, ''] : [s.slice(0, eq), s.slice(eq + 1)];
+  };
+  return script.split('\n').map((line) => line.trim()).filter(Boolean).map((line) => {
+    const rest = line.slice(1);
+    switch (line[0]) {
+      case '<': return { tag: 'open', val: rest };
+      case '>':
+        return rest.startsWith('>') ? { tag: 'skip', val: Number(rest.slice(1)) } : { tag: 'close' };
+      case '"': return { tag: 'text', val: rest };
+      case '{': return { tag: 'slot', val: rest };
+      case '@': return { tag: 'attr', val: pair(rest) };
+      case '$': return { tag: 'attr-slot', val: pair(rest) };
+      case '?': {
+        const [name, value] = pair(rest);
+        return { tag: 'typed-attr-slot', val: [name, { tag: 'boolean', val: value === 'true' }] };
+      }
+      case '!': return { tag: 'event', val: pair(rest) };
+      case '&': return { tag: 'bind', val: pair(rest) };
+      case '[': {
+        const hash = rest.indexOf('#');
+        return hash < 0
+          ? { tag: 'begin', val: rest }
+          : { tag: 'begin-keyed', val: [rest.slice(0, hash), rest.slice(hash + 1)] };
+      }
+      case ']': return { tag: 'end' };
+      case '^': {
+        const [id, n] = rest.split('@');
+        return { tag: 'seek', val: [id, Number(n)] };
+      }
+      case '+': return { tag: 'child', val: rest };
+      case '%': {
+        const [name, value] = pair(rest);
+        return { tag: 'prop', val: [name, { tag: 'str', val: value }] };
+      }
+      default: throw new Error(`unknown opcode line ${line}`);
+    }
+  });
+}
+
+function renderer(script) {
+  return { render: () => ops(script), handleEvent() {} };
+}
+
+// Renders in turn, wrapped in a <main> whose `next` event moves on to the
+// following one, as the host tests' scripted components do
+function scripted(renders) {
+  let n = 0;
+  return {
+    render: () => ops(`<main\n!next=next\n${renders[n]}\n>`),
+    handleEvent() {
+      n = Math.min(n + 1, renders.length - 1);
+    },
+  };
+}
+
+function next(container) {
+  container.firstChild.firstChild.dispatchEvent({ type: 'next' });
+}
+
+function fixtures() {
+  const file = readFileSync(new URL('./fixtures/hydration.txt', import.meta.url), 'utf8');
+  return file.split('\n===\n').slice(1).map((fixture) => {
+    const [script, html] = fixture.split('\n---\n');
+    return { script, html: html.trim() };
+  });
+}
+
+// The elements and text of `root`'s markup, which hydrating should keep
+function contentNodes(root) {
+  const nodes = [];
+  for (const child of root.childNodes) {
+    if (child.nodeType !== 8) {
+      nodes.push(child, ...contentNodes(child));
+    }
+  }
+  return nodes;
+}
+
+function isInside(root, node) {
+  for (; node; node = node.parentNode) {
+    if (node === root) {
+      return true;
+    }
+  }
+  return false;
+}
+
+const children = { kid: renderer('<b\n"kid\n>') };
+
+test('hydrating server markup matches building from scratch', () => {
+  for (const { script, html } of fixtures()) {
+    const root = document.createElement('div');
+    parseInto(root, html);
+    const nodes = contentNodes(root);
+    createHost(document.createElement('div')).hydrate(renderer(script), children, root);
+
+    const container = document.createElement('div');
+    createHost(container).mount(renderer(script), children);
+    assert.equal(root.innerHTML, container.firstChild.innerHTML, `for ${JSON.stringify(script)}`);
+    for (const node of nodes) {
+      assert.ok(isInside(root, node), `${html}: node ${node.data ?? node.localName} not adopted`);
+    }
+  }
+});
+
+test('a hydration mismatch builds from scratch and drops hydrated children', (t) => {
+  const warn = t.mock.method(console, 'warn', () => {});
+  const symbolDispose = Symbol.dispose || Symbol.for('dispose');
+  const live = new Set();
+  let made = 0;
+  class Component {
+    constructor() {
+      this.id = ++made;
+      live.add(this.id);
+    }
+    render() {
+      return ops('<b\n"kid\n>');
+    }
+    handleEvent() {}
+    handleBind() {}
+    [symbolDispose]() {
+      live.delete(this.id);
+    }
+  }
+
+  // The child hydrates before the parent's <span> fails to match a <p>
+  const script = '+kid\n<p\n"a\n>';
+  const root = document.createElement('div');
+  parseInto(root, '<div data-child="kid"><b>kid</b></div><span>a</span>');
+  createHost(document.createElement('div')).hydrate(renderer(script), { kid: { Component } }, root);
+
+  assert.equal(warn.mock.callCount(), 1);
+  assert.equal(root.innerHTML, '<div><b>kid</b></div><p>a</p>');
+  assert.equal(made, 2);
+  assert.deepEqual([...live], [2]);
+});
+
+test('a handler that throws leaves no current event behind', () => {
+  const container = document.createElement('div');
+  const comp = {
+    render: () => ops('<button\n!click=boom\n"go\n>'),
+    handleEvent() {
+      throw new Error('boom');
+    },
+  };
+  createHost(container).mount(comp);
+  const button = container.firstChild.firstChild;
+  assert.throws(() => button.dispatchEvent({ type: 'click' }), /boom/);
+  assert.equal(eventQuery('type'), '');
+});
+
+test('only instances take bindings', () => {
+  const container = document.createElement('div');
+  createHost(container).mount(renderer('<input\n&value=input\n>'));
+  const input = container.firstChild.firstChild;
+  assert.throws(() => input.dispatchEvent({ type: 'input' }), /export `instances` to bind/);
+
+  const bound = [];
+  class Component {
+    render() {
+      return ops('<input\n&value=input\n>');
+    }
+    handleEvent() {}
+    handleBind(handler, value) {
+      bound.push([handler, value]);
+    }
+  }
+  const other = document.createElement('div');
+  createHost(other).mount({ Component });
+  other.firstChild.firstChild.value = 'hi';
+  other.firstChild.firstChild.dispatchEvent({ type: 'input' });
+  assert.deepEqual(bound, [['input', { tag: 'str', val: 'hi' }]]);
+});
+
+test('nested groups update, and clone with their inner groups empty', () => {
+  const cells = (texts) => texts.length
+    ? texts.map((text) => `[cell\n<td\n{${text}\n>\n]`).join('\n')
+    : '^cell@0';
+  const table = (rows) => `<table\n${rows.map((texts) => `[row\n<tr\n${cells(texts)}\n>\n]`).join('\n')}\n>`;
+  const container = document.createElement('div');
+  createHost(container).mount(scripted([
+    table([['a1', 'a2']]),
+    table([['b1'], ['c1', 'c2', 'c3']]),
+    table([[]]),
+  ]));
+  const cell = (text) => `<!--begin:cell--><td>${text}</td><!--end-->`;
+  const row = (texts) => `<!--begin:row--><tr>${texts.map(cell).join('')}<!--/group--></tr><!--end-->`;
+  const html = () => container.firstChild.firstChild.innerHTML;
+  assert.equal(html(), `<table>${row(['a1', 'a2'])}<!--/group--></table>`);
+
+  next(container);
+  assert.equal(html(), `<table>${row(['b1'])}${row(['c1', 'c2', 'c3'])}<!--/group--></table>`);
+
+  next(container);
+  assert.equal(html(), `<table>${row([])}<!--/group--></table>`);
+});
+
+test('children mount and unmount with their group instance', () => {
+  const symbolDispose = Symbol.dispose || Symbol.for('dispose');
+  const live = new Set();
+  let made = 0;
+  class Component {
+    constructor() {
+      this.id = ++made;
+      live.add(this.id);
+    }
+    render() {
+      return ops('<b\n"kid\n>');
+    }
+    handleEvent() {}
+    handleBind() {}
+    [symbolDispose]() {
+      live.delete(this.id);
+    }
+  }
+
+  const item = '[li\n<li\n+kid\n%name=x\n>\n]';
+  const container = document.createElement('div');
+  createHost(container).mount(scripted([item, `${item}\n${item}`, '^li@0']), { kid: { Component } });
+  const li = '<!--begin:li--><li><div><b>kid</b></div></li><!--end-->';
+  const html = () => container.firstChild.firstChild.innerHTML;
+  assert.equal(html(), `${li}<!--/group-->`);
+
+  // The clone's child is mounted by the update that fills it in
+  next(container);
+  assert.equal(html(), `${li}${li}<!--/group-->`);
+  assert.deepEqual([...live], [1, 2]);
+
+  next(container);
+  assert.equal(html(), '<!--/group-->');
+  assert.deepEqual([...live], []);
+});